@@ -0,0 +1,45 @@
+//! Headless AI-vs-AI matches to compare the balance of units and boosts
+//!
+//! Run with `cargo run --release --bin tinywar-sim -- [OPTIONS]`.
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    use std::process::exit;
+    use tinywar::core::batch::{run_batch, BatchConfig};
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|a| a == "--help") {
+        println!("{}", BatchConfig::USAGE);
+        return;
+    }
+
+    let config = BatchConfig::from_args(args.into_iter()).unwrap_or_else(|e| {
+        eprintln!("{e}\n\n{}", BatchConfig::USAGE);
+        exit(1);
+    });
+
+    let mut i = 0;
+    let report = run_batch(&config, |result| {
+        i += 1;
+        eprint!("\rSimulated match {i}/{} (seed {})", config.matches, result.seed);
+    })
+    .unwrap_or_else(|e| {
+        eprintln!("{e}");
+        exit(1);
+    });
+    eprintln!();
+
+    println!("{}", report.table());
+
+    if let Some(path) = &config.csv {
+        if let Err(e) = std::fs::write(path, report.csv()) {
+            eprintln!("Failed to write {}: {e}", path.display());
+            exit(1);
+        }
+    } else {
+        println!("{}", report.csv());
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {}
@@ -3,10 +3,14 @@ use crate::core::settings::PlayerColor;
 use crate::core::units::buildings::BuildingName;
 use crate::core::units::units::{ActionKind, UnitName};
 use crate::utils::NameFromEnum;
-use bevy::asset::AssetServer;
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AssetServer, LoadContext};
 use bevy::prelude::*;
 use bevy_kira_audio::AudioSource;
+use serde::de::DeserializeOwned;
 use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
 use strum::IntoEnumIterator;
 
 #[derive(Clone)]
@@ -94,11 +98,7 @@ impl FromWorld for WorldAssets {
             ("guard", assets.load("images/icons/guard.png")),
             ("march", assets.load("images/icons/march.png")),
             ("berserk", assets.load("images/icons/berserk.png")),
-            ("any arrow", assets.load("images/icons/any arrow.png")),
-            ("top arrow", assets.load("images/icons/top arrow.png")),
-            ("top-mid arrow", assets.load("images/icons/top-mid arrow.png")),
             ("mid arrow", assets.load("images/icons/mid arrow.png")),
-            ("top bot arrow", assets.load("images/icons/top bot arrow.png")),
             ("health", assets.load("images/icons/health.png")),
             ("attack range", assets.load("images/icons/attack range.png")),
             ("spawn duration", assets.load("images/icons/spawn duration.png")),
@@ -113,6 +113,7 @@ impl FromWorld for WorldAssets {
             ("magic resist", assets.load("images/icons/magic resist.png")),
             ("armor penetration", assets.load("images/icons/armor penetration.png")),
             ("magic penetration", assets.load("images/icons/magic penetration.png")),
+            ("sword", assets.load("images/icons/sword.png")),
             // Background
             ("bg", assets.load("images/bg/bg.png")),
             ("victory", assets.load("images/bg/victory.png")),
@@ -135,6 +136,7 @@ impl FromWorld for WorldAssets {
             ("dust2", assets.load("images/effects/dust2.png")),
             ("explosion1", assets.load("images/effects/explosion1.png")),
             ("explosion2", assets.load("images/effects/explosion2.png")),
+            ("impact", assets.load("images/effects/splash.png")),
             ("fire1", assets.load("images/effects/fire1.png")),
             ("fire2", assets.load("images/effects/fire2.png")),
             ("fire3", assets.load("images/effects/fire3.png")),
@@ -145,7 +147,7 @@ impl FromWorld for WorldAssets {
             ("enemy boost", assets.load("images/boosts/enemy boost.png")),
         ]);
 
-        for boost in Boost::iter() {
+        for boost in Boost::iter().filter(|b| !b.is_deprecated()) {
             let name = Box::leak(Box::new(boost.to_lowername())).as_str();
             images.insert(name, assets.load(format!("images/boosts/{}.png", boost.to_lowername())));
         }
@@ -274,6 +276,7 @@ impl FromWorld for WorldAssets {
         let dust2 = TextureAtlasLayout::from_grid(UVec2::splat(64), 10, 1, None, None);
         let explosion1 = TextureAtlasLayout::from_grid(UVec2::splat(192), 8, 1, None, None);
         let explosion2 = TextureAtlasLayout::from_grid(UVec2::splat(192), 10, 1, None, None);
+        let impact = TextureAtlasLayout::from_grid(UVec2::splat(192), 9, 1, None, None);
         let fire1 = TextureAtlasLayout::from_grid(UVec2::splat(64), 8, 1, None, None);
         let fire2 = TextureAtlasLayout::from_grid(UVec2::splat(64), 10, 1, None, None);
         let fire3 = TextureAtlasLayout::from_grid(UVec2::splat(64), 12, 1, None, None);
@@ -355,6 +358,17 @@ impl FromWorld for WorldAssets {
                     last_index: 9,
                 },
             ),
+            (
+                "impact",
+                AtlasInfo {
+                    image: images["impact"].clone(),
+                    atlas: TextureAtlas {
+                        layout: texture.add(impact),
+                        index: 0,
+                    },
+                    last_index: 8,
+                },
+            ),
             (
                 "fire1",
                 AtlasInfo {
@@ -399,3 +413,79 @@ impl FromWorld for WorldAssets {
         }
     }
 }
+
+#[derive(Debug)]
+pub enum DataError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for DataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DataError::Io(e) => write!(f, "Failed to read the data file: {e}"),
+            DataError::Parse(e) => write!(f, "Failed to parse the data file: {e}"),
+            DataError::Invalid(e) => write!(f, "Invalid data file: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for DataError {}
+
+impl From<std::io::Error> for DataError {
+    fn from(e: std::io::Error) -> Self {
+        DataError::Io(e)
+    }
+}
+
+impl From<ron::error::SpannedError> for DataError {
+    fn from(e: ron::error::SpannedError) -> Self {
+        DataError::Parse(e)
+    }
+}
+
+/// Game data stored as a RON file in `assets/data`
+pub trait DataAsset: Asset + DeserializeOwned {
+    /// Full extension of the file, e.g., `units.ron`
+    const EXTENSION: &'static str;
+
+    /// Check that the values in the file make sense
+    fn validate(&self) -> Result<(), String>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, DataError> {
+        let data: Self = ron::de::from_bytes(bytes)?;
+        data.validate().map_err(DataError::Invalid)?;
+        Ok(data)
+    }
+}
+
+#[derive(TypePath)]
+pub struct DataLoader<T: DataAsset>(PhantomData<T>);
+
+impl<T: DataAsset> Default for DataLoader<T> {
+    fn default() -> Self {
+        Self(PhantomData)
+    }
+}
+
+impl<T: DataAsset> AssetLoader for DataLoader<T> {
+    type Asset = T;
+    type Settings = ();
+    type Error = DataError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes).await?;
+        T::from_bytes(&bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        std::slice::from_ref(&T::EXTENSION)
+    }
+}
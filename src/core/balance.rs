@@ -0,0 +1,315 @@
+use crate::core::assets::DataAsset;
+use crate::core::boosts::Boost;
+use crate::core::map::ui::systems::NoticeMsg;
+use crate::core::player::Players;
+use crate::core::settings::Settings;
+use crate::core::units::buildings::{BuildingAttack, BuildingName};
+use crate::core::units::heroes::Ability;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock, RwLockReadGuard};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+/// Balance shipped with the game, used until the asset is loaded
+const DEFAULT_BALANCE: &[u8] = include_bytes!("../../assets/data/balance.ron");
+
+/// Balance currently in use by the game
+///
+/// This is a global instead of a resource because it's read through accessors
+/// like `Boost::value()` and from plain functions (e.g. `Player::new`) without
+/// access to the world. It's only written by `update_balance` when the asset is
+/// (re)loaded.
+static BALANCE: LazyLock<RwLock<Balance>> = LazyLock::new(|| {
+    RwLock::new(Balance::from_bytes(DEFAULT_BALANCE).expect("Invalid default balance file."))
+});
+
+fn default_value() -> f32 {
+    1.
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BoostBalance {
+    #[serde(default)]
+    pub duration: u64,
+    #[serde(default = "default_value")]
+    pub value: f32,
+    /// Radius (in unit radii) of boosts cast on a location of the map
+    #[serde(default)]
+    pub radius: Option<f32>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EconomyBalance {
+    /// Gold of the players at the start of the game
+    pub start: f32,
+    /// Gold earned per second
+    pub income: f32,
+    /// Fraction of the cost of an enemy unit earned when it dies
+    pub bounty: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StrategyBalance {
+    /// Multiplier of the armor and magic resist of guarding units
+    pub guard_defense: f32,
+    /// Multiplier of the speed of units in march mode
+    pub march_speed: f32,
+    /// Multiplier of the armor and magic resist of units in berserk mode
+    pub berserk_defense: f32,
+    /// Multiplier of the attack speed of attacking units in berserk mode
+    pub berserk_attack_speed: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AbilityBalance {
+    /// Seconds before the ability can be used again
+    pub cooldown: f32,
+    /// Radius of the ability around the hero, in unit radii
+    pub radius: f32,
+    pub value: f32,
+    /// Seconds the status applied by the ability lasts
+    #[serde(default)]
+    pub duration: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HeroBalance {
+    /// Seconds before a killed hero respawns
+    pub respawn: f32,
+    /// Distance (in unit radii) within which kills give experience to the hero
+    pub radius: f32,
+    /// Experience needed per level, the next level costs `xp * level`
+    pub xp: u32,
+    pub max_level: u32,
+    /// Multiplier of the health and damage of the hero's unit at level 1
+    pub power: f32,
+    /// Fraction of health and damage gained every level
+    pub growth: f32,
+    pub abilities: HashMap<Ability, AbilityBalance>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BuildingsBalance {
+    /// Fraction of damage and attack speed gained every upgrade level
+    pub bonus: f32,
+    /// Range (in unit radii) gained every upgrade level
+    pub range: f32,
+    /// Attack of the buildings that shoot, at level 1
+    pub attacks: HashMap<BuildingName, BuildingAttack>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RankBalance {
+    /// Kills needed to reach the rank
+    pub kills: u32,
+    /// Damage (or healing) dealt needed to reach the rank
+    pub damage: f32,
+    /// Fraction of health and damage gained at the rank
+    pub bonus: f32,
+}
+
+#[derive(Asset, TypePath, Clone, Debug, Serialize, Deserialize)]
+pub struct Balance {
+    pub boost_timer: f32,
+    pub strategy_timer: u64,
+    pub min_damage: f32,
+    /// Units moving faster than this speed can dodge projectiles
+    #[serde(default)]
+    pub miss_speed: Option<f32>,
+    /// Maximum total weight of the units of a player
+    pub population: u32,
+    /// Maximum number of units in the queue
+    pub max_queue: usize,
+    /// Number of boosts every player selects during a match
+    pub max_boosts: usize,
+    pub strategies: StrategyBalance,
+    pub economy: EconomyBalance,
+    pub buildings: BuildingsBalance,
+    pub hero: HeroBalance,
+    /// Veterancy ranks, from lowest to highest
+    pub veterancy: Vec<RankBalance>,
+    pub boosts: HashMap<Boost, BoostBalance>,
+}
+
+impl DataAsset for Balance {
+    const EXTENSION: &'static str = "balance.ron";
+
+    fn validate(&self) -> Result<(), String> {
+        if self.boost_timer <= 0. {
+            Err("boost_timer must be larger than zero.".to_string())
+        } else if self.strategy_timer == 0 {
+            Err("strategy_timer must be larger than zero.".to_string())
+        } else if self.min_damage < 0. {
+            Err("min_damage can't be negative.".to_string())
+        } else if self.miss_speed.is_some_and(|s| s <= 0.) {
+            Err("miss_speed must be larger than zero.".to_string())
+        } else if self.population == 0 {
+            Err("population must be larger than zero.".to_string())
+        } else if self.max_queue == 0 {
+            Err("max_queue must be larger than zero.".to_string())
+        } else if !(1..=4).contains(&self.max_boosts) {
+            Err("max_boosts must be between 1 and 4.".to_string())
+        } else if [
+            self.strategies.guard_defense,
+            self.strategies.march_speed,
+            self.strategies.berserk_defense,
+            self.strategies.berserk_attack_speed,
+        ]
+        .iter()
+        .any(|v| *v <= 0.)
+        {
+            Err("The strategy multipliers must be larger than zero.".to_string())
+        } else if [self.economy.start, self.economy.income, self.economy.bounty]
+            .iter()
+            .any(|v| *v < 0.)
+        {
+            Err("The economy values can't be negative.".to_string())
+        } else if self.buildings.bonus < 0. || self.buildings.range < 0. {
+            Err("The bonus and range of the building upgrades can't be negative.".to_string())
+        } else if let Some((building, _)) = self.buildings.attacks.iter().find(|(_, a)| {
+            a.range <= 0. || a.attack_speed <= 0. || a.physical_damage < 0. || a.magic_damage < 0.
+        }) {
+            Err(format!(
+                "The attack of building {building:?} needs a range and attack speed, and can't have negative damage."
+            ))
+        } else if self.hero.respawn < 0. || self.hero.radius <= 0. || self.hero.power <= 0. {
+            Err("The hero's respawn can't be negative, nor its radius and power zero.".to_string())
+        } else if self.hero.xp == 0 || self.hero.max_level == 0 || self.hero.growth < 0. {
+            Err("The hero's xp and max_level must be larger than zero, and growth can't be negative."
+                .to_string())
+        } else if let Some(ability) = Ability::iter().find(|a| !self.hero.abilities.contains_key(a))
+        {
+            Err(format!("Ability {ability:?} is missing."))
+        } else if let Some((ability, _)) = self
+            .hero
+            .abilities
+            .iter()
+            .find(|(_, a)| a.cooldown < 0. || a.radius <= 0. || a.duration < 0.)
+        {
+            Err(format!("Ability {ability:?} has a negative cooldown or duration, or no radius."))
+        } else if self.veterancy.iter().any(|r| r.damage < 0. || r.bonus < 0.) {
+            Err("The damage and bonus of the veterancy ranks can't be negative.".to_string())
+        } else if self
+            .veterancy
+            .windows(2)
+            .any(|w| w[1].kills < w[0].kills || w[1].damage < w[0].damage)
+        {
+            Err("The veterancy ranks must be sorted by kills and damage.".to_string())
+        } else if let Some((boost, _)) = self.boosts.iter().find(|(_, b)| b.value < 0.) {
+            Err(format!("The value of boost {boost:?} can't be negative."))
+        } else if let Some((boost, _)) =
+            self.boosts.iter().find(|(_, b)| b.radius.is_some_and(|r| r <= 0.))
+        {
+            Err(format!("The radius of boost {boost:?} must be larger than zero."))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct BalanceHandle(pub Handle<Balance>);
+
+/// Get the balance currently in use
+pub fn balance() -> RwLockReadGuard<'static, Balance> {
+    BALANCE.read().unwrap()
+}
+
+pub fn load_balance(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(BalanceHandle(assets.load("data/balance.ron")));
+}
+
+pub fn update_balance(
+    mut asset_msg: MessageReader<AssetEvent<Balance>>,
+    mut notice_msg: MessageWriter<NoticeMsg>,
+    handle: Res<BalanceHandle>,
+    balances: Res<Assets<Balance>>,
+    mut settings: ResMut<Settings>,
+    mut players: Option<ResMut<Players>>,
+) {
+    for msg in asset_msg.read() {
+        let reloaded = msg.is_modified(&handle.0);
+        if !reloaded && !msg.is_loaded_with_dependencies(&handle.0) {
+            continue;
+        }
+
+        if let Some(new) = balances.get(&handle.0) {
+            *BALANCE.write().unwrap() = new.clone();
+
+            // Update the timers that are already running
+            settings.boost_timer.set_duration(Duration::from_secs_f32(new.boost_timer));
+            if let Some(players) = players.as_mut() {
+                for player in players.iter_mut() {
+                    player.strategy_timer.set_duration(Duration::from_secs(new.strategy_timer));
+                    for boost in player.boosts.iter_mut() {
+                        boost.timer.set_duration(Duration::from_secs(boost.name.duration()));
+                    }
+                }
+            }
+
+            if reloaded {
+                notice_msg.write(NoticeMsg::new("Balance reloaded"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::assets::DataError;
+
+    fn default_balance() -> Balance {
+        Balance::from_bytes(DEFAULT_BALANCE).unwrap()
+    }
+
+    #[test]
+    fn default_balance_is_valid() {
+        assert!(default_balance().validate().is_ok());
+    }
+
+    #[test]
+    fn invalid_file_fails_to_parse() {
+        assert!(matches!(Balance::from_bytes(b"(boost_timer: 30.,"), Err(DataError::Parse(_))));
+    }
+
+    #[test]
+    fn invalid_file_is_rejected() {
+        let file = String::from_utf8(DEFAULT_BALANCE.to_vec())
+            .unwrap()
+            .replace("min_damage: 5.", "min_damage: -5.");
+
+        assert!(matches!(Balance::from_bytes(file.as_bytes()), Err(DataError::Invalid(_))));
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let cases: Vec<fn(&mut Balance)> = vec![
+            |b| b.boost_timer = 0.,
+            |b| b.strategy_timer = 0,
+            |b| b.min_damage = -1.,
+            |b| b.miss_speed = Some(0.),
+            |b| b.population = 0,
+            |b| b.max_queue = 0,
+            |b| b.max_boosts = 5,
+            |b| b.strategies.march_speed = 0.,
+            |b| b.economy.income = -1.,
+            |b| b.buildings.range = -1.,
+            |b| b.hero.xp = 0,
+            |b| {
+                b.hero.abilities.remove(&Ability::Heal);
+            },
+            |b| b.veterancy.reverse(),
+            |b| b.boosts.get_mut(&Boost::Run).unwrap().value = -1.,
+            |b| b.boosts.get_mut(&Boost::Lightning).unwrap().radius = Some(0.),
+        ];
+
+        for (i, case) in cases.iter().enumerate() {
+            let mut balance = default_balance();
+            case(&mut balance);
+            assert!(balance.validate().is_err(), "case {i} should be invalid");
+        }
+    }
+}
@@ -0,0 +1,412 @@
+use crate::core::boosts::Boost;
+use crate::core::constants::SIMULATION_HZ;
+use crate::core::map::map::{Map, MapName};
+use crate::core::mechanics::combat::{apply_damage_message, DamageDealtMsg};
+use crate::core::mechanics::spawn::SpawnBuildingMsg;
+use crate::core::menu::systems::{spawn_bases, Host};
+use crate::core::player::{Player, Players, Pool, Side};
+use crate::core::rng::GameRng;
+use crate::core::settings::{GameMode, PlayerColor, Settings};
+use crate::core::simulation::{SimulationPlugin, Step};
+use crate::core::states::{AppState, GameState};
+use crate::core::units::buildings::Building;
+use crate::core::units::units::UnitName;
+use crate::utils::NameFromEnum;
+use bevy::ecs::system::RunSystemOnce;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+use bevy_ecs_tiled::prelude::tiled;
+use std::fmt::{Debug, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+/// Settings of a batch of headless AI-vs-AI matches
+#[derive(Clone, Debug)]
+pub struct BatchConfig {
+    pub matches: u64,
+    /// Seed of the first match, every next match uses the next seed
+    pub seed: u64,
+    pub map: MapName,
+    /// Pools of the left and right player
+    pub pools: [Pool; 2],
+    /// Matches that last longer end in a draw
+    pub max_secs: u64,
+    /// File to write the report to as CSV, printed after the table when not set
+    pub csv: Option<PathBuf>,
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            matches: 100,
+            seed: rand::random(),
+            map: MapName::default(),
+            pools: [Pool::default(), Pool::default()],
+            max_secs: 1800,
+            csv: None,
+        }
+    }
+}
+
+impl BatchConfig {
+    pub const USAGE: &'static str = "\
+Usage: tinywar-sim [OPTIONS]
+
+Options:
+  --matches <N>            Number of matches to simulate [default: 100]
+  --seed <SEED>            Seed of the first match [default: random]
+  --map <MAP>              Map to play on: island, duel or crossroads [default: island]
+  --units <UNITS>          Comma-separated units both players queue [default: queueable units]
+  --left-units <UNITS>     Units the left player queues
+  --right-units <UNITS>    Units the right player queues
+  --boosts <BOOSTS>        Comma-separated boosts both players pick from, or none [default: all]
+  --left-boosts <BOOSTS>   Boosts the left player picks from
+  --right-boosts <BOOSTS>  Boosts the right player picks from
+  --max-secs <SECS>        Matches that last longer end in a draw [default: 1800]
+  --csv <FILE>             Write the report as CSV to this file
+  --help                   Print this message";
+
+    pub fn from_args(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut config = Self::default();
+
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or_else(|| format!("Missing value for {arg}."))?;
+
+            match arg.as_str() {
+                "--matches" => config.matches = parse_number(&value)?,
+                "--seed" => config.seed = parse_number(&value)?,
+                "--map" => config.map = parse_name(&value)?,
+                "--units" | "--left-units" | "--right-units" => {
+                    let units = parse_list::<UnitName>(&value)?;
+                    if units.is_empty() {
+                        return Err("The unit pool can't be empty.".to_string());
+                    }
+
+                    for pool in select_pools(&arg, &mut config.pools) {
+                        pool.units = Some(units.clone());
+                    }
+                },
+                "--boosts" | "--left-boosts" | "--right-boosts" => {
+                    let boosts = parse_list::<Boost>(&value)?;
+                    for pool in select_pools(&arg, &mut config.pools) {
+                        pool.boosts = Some(boosts.clone());
+                    }
+                },
+                "--max-secs" => config.max_secs = parse_number(&value)?,
+                "--csv" => config.csv = Some(PathBuf::from(value)),
+                _ => return Err(format!("Unknown argument {arg}.")),
+            }
+        }
+
+        if config.matches == 0 {
+            Err("The number of matches must be larger than zero.".to_string())
+        } else {
+            Ok(config)
+        }
+    }
+}
+
+/// Pools an option applies to: both, or one when prefixed with `--left` or `--right`
+fn select_pools<'a>(arg: &str, pools: &'a mut [Pool; 2]) -> impl Iterator<Item = &'a mut Pool> {
+    let [left, right] = pools;
+    let left = (!arg.starts_with("--right")).then_some(left);
+    let right = (!arg.starts_with("--left")).then_some(right);
+    left.into_iter().chain(right)
+}
+
+fn parse_number(value: &str) -> Result<u64, String> {
+    value.parse().map_err(|_| format!("Invalid number {value}."))
+}
+
+fn parse_name<T: IntoEnumIterator + Debug>(value: &str) -> Result<T, String> {
+    T::iter()
+        .find(|v| format!("{v:?}").eq_ignore_ascii_case(value))
+        .ok_or_else(|| format!("Unknown name {value}."))
+}
+
+fn parse_list<T: IntoEnumIterator + Debug>(value: &str) -> Result<Vec<T>, String> {
+    if value.eq_ignore_ascii_case("none") {
+        Ok(vec![])
+    } else {
+        value.split(',').map(|v| parse_name(v.trim())).collect()
+    }
+}
+
+/// Damage dealt and taken by all units of one type
+#[derive(Clone, Copy, Debug, Default)]
+pub struct UnitStats {
+    pub dealt: f32,
+    pub taken: f32,
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+struct DamageStats(HashMap<UnitName, UnitStats>);
+
+pub struct MatchResult {
+    pub seed: u64,
+    /// Side whose base survived, `None` for a draw
+    pub winner: Option<Side>,
+    pub secs: f32,
+}
+
+pub struct BatchReport {
+    pub map: MapName,
+    pub results: Vec<MatchResult>,
+    pub units: HashMap<UnitName, UnitStats>,
+}
+
+impl BatchReport {
+    fn wins(&self, winner: Option<Side>) -> usize {
+        self.results.iter().filter(|r| r.winner == winner).count()
+    }
+
+    fn avg_secs(&self) -> f32 {
+        self.results.iter().map(|r| r.secs).sum::<f32>() / self.results.len() as f32
+    }
+
+    /// Outcomes to report: the left side, the right side and draws
+    fn outcomes() -> [(&'static str, Option<Side>); 3] {
+        [("Left", Some(Side::Left)), ("Right", Some(Side::Right)), ("Draw", None)]
+    }
+
+    fn units(&self) -> impl Iterator<Item = (UnitName, UnitStats)> + '_ {
+        UnitName::iter().filter_map(|u| self.units.get(&u).map(|s| (u, *s)))
+    }
+
+    pub fn table(&self) -> String {
+        let n = self.results.len();
+        let mut table = String::new();
+
+        let first = self.results.first().map_or(0, |r| r.seed);
+        let last = self.results.last().map_or(0, |r| r.seed);
+        let _ =
+            writeln!(table, "{n} matches on {} (seeds {first}..={last})\n", self.map.to_title());
+
+        let _ = writeln!(table, "{:<8}{:>8}{:>10}", "Outcome", "Matches", "Rate");
+        for (name, winner) in Self::outcomes() {
+            let wins = self.wins(winner);
+            let _ = writeln!(table, "{name:<8}{wins:>8}{:>9.1}%", 100. * wins as f32 / n as f32);
+        }
+
+        let secs = self.avg_secs() as u64;
+        let _ = writeln!(table, "\nAverage match length: {:02}:{:02}\n", secs / 60, secs % 60);
+
+        let _ = writeln!(
+            table,
+            "{:<12}{:>12}{:>12}{:>14}{:>14}",
+            "Unit", "Dealt", "Taken", "Dealt/match", "Taken/match"
+        );
+        for (unit, stats) in self.units() {
+            let _ = writeln!(
+                table,
+                "{:<12}{:>12.0}{:>12.0}{:>14.1}{:>14.1}",
+                unit.to_name(),
+                stats.dealt,
+                stats.taken,
+                stats.dealt / n as f32,
+                stats.taken / n as f32
+            );
+        }
+
+        table
+    }
+
+    /// Report in long format, with one `metric,name,value` row per value
+    pub fn csv(&self) -> String {
+        let n = self.results.len();
+        let mut csv = String::from("metric,name,value\n");
+
+        let _ = writeln!(csv, "matches,all,{n}");
+        for (name, winner) in Self::outcomes() {
+            let wins = self.wins(winner);
+            let _ = writeln!(csv, "wins,{name},{wins}");
+            let _ = writeln!(csv, "win_rate,{name},{}", wins as f32 / n as f32);
+        }
+
+        let _ = writeln!(csv, "avg_secs,all,{}", self.avg_secs());
+
+        for (unit, stats) in self.units() {
+            let _ = writeln!(csv, "damage_dealt,{unit:?},{}", stats.dealt);
+            let _ = writeln!(csv, "damage_taken,{unit:?},{}", stats.taken);
+        }
+
+        csv
+    }
+}
+
+/// Read a map from the assets without an asset server
+fn load_map(name: MapName) -> Result<Map, String> {
+    let path = Path::new("assets").join(name.path());
+    let tiled_map = tiled::Loader::new()
+        .load_tmx_map(&path)
+        .map_err(|e| format!("Failed to load map {}: {e}", path.display()))?;
+    Map::from_tiled(name, Handle::default(), &tiled_map)
+        .map_err(|e| format!("Invalid map {}: {e}", path.display()))
+}
+
+/// Play all matches of the batch, calling `progress` after every match
+pub fn run_batch(
+    config: &BatchConfig,
+    mut progress: impl FnMut(&MatchResult),
+) -> Result<BatchReport, String> {
+    let map = load_map(config.map)?;
+
+    let mut report = BatchReport {
+        map: config.map,
+        results: vec![],
+        units: HashMap::new(),
+    };
+
+    for seed in (0..config.matches).map(|i| config.seed.wrapping_add(i)) {
+        let (result, stats) = run_match(config, seed, map.clone());
+        progress(&result);
+
+        for (unit, s) in stats {
+            let entry = report.units.entry(unit).or_default();
+            entry.dealt += s.dealt;
+            entry.taken += s.taken;
+        }
+
+        report.results.push(result);
+    }
+
+    Ok(report)
+}
+
+/// Headless app of a match between two AI players, ready to be updated
+fn new_match(config: &BatchConfig, seed: u64, map: Map) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((MinimalPlugins, StatesPlugin, SimulationPlugin))
+        // Advance exactly one simulation step per update, as fast as possible
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1. / SIMULATION_HZ,
+        )))
+        .init_resource::<DamageStats>()
+        .add_systems(FixedUpdate, record_damage.after(apply_damage_message));
+
+    let settings = Settings {
+        game_mode: GameMode::SinglePlayer,
+        color: PlayerColor::Blue,
+        enemy_color: PlayerColor::Red,
+        map: config.map,
+        ..default()
+    };
+
+    // Both players are AI, so neither of them is waiting for input
+    let [left, right] = config.pools.clone();
+    let mut players = Players {
+        me: Player::new(1, settings.color, Side::Left),
+        enemy: Player::new(2, settings.enemy_color, Side::Right),
+    };
+    players.me.pool = left;
+    players.enemy.pool = right;
+
+    let colors = [settings.color, settings.enemy_color];
+    let bases = map.clone();
+    app.world_mut()
+        .run_system_once(move |mut spawn_building_msg: MessageWriter<SpawnBuildingMsg>| {
+            spawn_bases(colors, &bases, &mut spawn_building_msg);
+        })
+        .expect("Failed to spawn the bases.");
+
+    app.insert_resource(Host)
+        .insert_resource(map)
+        .insert_resource(GameRng::new(seed))
+        .insert_resource(settings)
+        .insert_resource(players);
+
+    app.world_mut().resource_mut::<NextState<AppState>>().set(AppState::Game);
+
+    app.finish();
+    app.cleanup();
+
+    app
+}
+
+fn run_match(
+    config: &BatchConfig,
+    seed: u64,
+    map: Map,
+) -> (MatchResult, HashMap<UnitName, UnitStats>) {
+    let mut app = new_match(config, seed, map);
+
+    let max_steps = config.max_secs * SIMULATION_HZ as u64;
+    while **app.world().resource::<Step>() < max_steps {
+        app.update();
+
+        if *app.world().resource::<State<GameState>>() == GameState::EndGame {
+            break;
+        }
+    }
+
+    let world = app.world_mut();
+    let players = world.resource::<Players>().clone();
+    let alive: Vec<Side> = world
+        .query::<&Building>()
+        .iter(world)
+        .filter(|b| b.is_base && b.health > 0.)
+        .map(|b| players.get_by_color(b.color).side)
+        .collect();
+
+    let result = MatchResult {
+        seed,
+        winner: (alive.len() == 1).then(|| alive[0]),
+        secs: **world.resource::<Step>() as f32 / SIMULATION_HZ as f32,
+    };
+
+    (result, world.remove_resource::<DamageStats>().unwrap_or_default().0)
+}
+
+fn record_damage(
+    mut damage_dealt_msg: MessageReader<DamageDealtMsg>,
+    mut stats: ResMut<DamageStats>,
+) {
+    for msg in damage_dealt_msg.read() {
+        if let Some(source) = msg.source {
+            stats.entry(source).or_default().dealt += msg.damage;
+        }
+
+        if let Some(target) = msg.target {
+            stats.entry(target).or_default().taken += msg.damage;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::units::units::Unit;
+
+    /// Units on the map after `steps` simulation steps of a match
+    fn units_after(seed: u64, steps: u64) -> Vec<(UnitName, f32, Vec3)> {
+        let config = BatchConfig::default();
+        let mut app = new_match(&config, seed, load_map(config.map).unwrap());
+
+        while **app.world().resource::<Step>() < steps {
+            app.update();
+        }
+
+        let world = app.world_mut();
+        let mut units: Vec<_> = world
+            .query::<(&Transform, &Unit)>()
+            .iter(world)
+            .map(|(t, u)| (u.name, u.health, t.translation))
+            .collect();
+        units.sort_by(|a, b| a.2.x.total_cmp(&b.2.x).then(a.2.y.total_cmp(&b.2.y)));
+        units
+    }
+
+    #[test]
+    fn same_seed_gives_same_state() {
+        let steps = 90 * SIMULATION_HZ as u64;
+        let units = units_after(7, steps);
+
+        assert!(!units.is_empty());
+        assert_eq!(units, units_after(7, steps));
+        assert_ne!(units, units_after(8, steps));
+    }
+}
@@ -1,22 +1,24 @@
 use crate::core::audio::PlayAudioMsg;
-use crate::core::constants::MAX_BOOSTS;
+use crate::core::balance::balance;
+use crate::core::constants::RADIUS;
 use crate::core::map::map::{Lane, Map};
 use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::spatial::SpatialGrid;
 use crate::core::mechanics::spawn::{DespawnMsg, SpawnBuildingMsg, SpawnUnitMsg};
+use crate::core::mechanics::status::StatusEffects;
 use crate::core::menu::systems::Host;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::core::network::{ClientMessage, ClientSendMsg, ServerMessage, ServerSendMsg};
 use crate::core::player::{Player, Players, SelectedBoost, Side};
+use crate::core::rng::GameRng;
 use crate::core::settings::{GameMode, PlayerColor, Settings};
 use crate::core::states::GameState;
 use crate::core::units::buildings::{Building, BuildingName};
-use crate::core::units::units::{Action, Unit, UnitName};
-use crate::utils::{scale_duration, NameFromEnum};
+use crate::core::units::units::{Action, Unit, UnitName, Veterancy};
+use crate::utils::scale_duration;
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::TilePos;
 use itertools::Itertools;
-use rand::prelude::IteratorRandom;
-use rand::rng;
+use rand::prelude::{IndexedRandom, IteratorRandom};
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
@@ -28,6 +30,8 @@ pub struct CardCmp;
 pub struct ActivateBoostMsg {
     pub boost: Boost,
     pub color: PlayerColor,
+    /// Location of the map targeted by the boost
+    pub target: Option<Vec2>,
 }
 
 impl ActivateBoostMsg {
@@ -35,6 +39,7 @@ impl ActivateBoostMsg {
         Self {
             boost,
             color,
+            target: None,
         }
     }
 }
@@ -42,7 +47,11 @@ impl ActivateBoostMsg {
 #[derive(Resource, Default, Deref, DerefMut)]
 pub struct AfterBoostCount(pub usize);
 
-#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+/// Boost whose location on the map is being chosen by the player
+#[derive(Resource, Deref)]
+pub struct BoostTargeting(pub Boost);
+
+#[derive(EnumIter, Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Boost {
     ArmorGain,
     Arrows,
@@ -71,6 +80,7 @@ pub enum Boost {
     MinotaurRage,
     NoCollision,
     Penetration,
+    // Replaced by the tech tree, kept so older saves and replays still decode
     QueueBears,
     QueueGnolls,
     QueueGoblins,
@@ -93,6 +103,7 @@ pub enum Boost {
     Spiders,
     Tower,
     Warrior,
+    Ghosts,
 }
 
 impl Boost {
@@ -104,7 +115,7 @@ impl Boost {
             Boost::BlockRange => "Block all damage on units from enemy ranged units.",
             Boost::BuildingsBlock => "Block all damage dealt to your buildings.",
             Boost::BuildingsDefense => "Increase the damage of all units on buildings by 100%.",
-            Boost::Castle => "Upgrade your base to a castle.",
+            Boost::Castle => "Upgrade your base to a castle, or upgrade the castle's attack.",
             Boost::Clone => "Clones 8 random non-attacking units of yours (in position).",
             Boost::Conversion => "Converts 5 random enemy units to your side.",
             Boost::ConvertGoblins => "Transforms all your lancers into goblins.",
@@ -112,12 +123,15 @@ impl Boost {
             Boost::ConvertSharks => "Transforms all your ground archers into sharks.",
             Boost::DoubleQueue => "Two units are queued at the same time.",
             Boost::Frozen => "All enemy units who aren't attacking stop their movement.",
+            Boost::Ghosts => "Spawn 6 ghosts that fly from your base.",
             Boost::GnomesBasic => "Convert all basic (ground) enemy units into gnomes.",
             Boost::GnomesMagic => "Convert all magic (ground) enemy units into gnomes.",
-            Boost::InstantHealing => "Instantly heal all your units to their maximum health.",
+            Boost::InstantHealing => {
+                "Instantly heal all your units and cure their poison and burns."
+            },
             Boost::InstantArmy => "Immediately spawn 6 random units in the base.",
             Boost::Lancer => "Increase your lancer's damage by 60%.",
-            Boost::Lightning => "Reduce all unit's health by half",
+            Boost::Lightning => "Reduce the health of all units in the target area by half.",
             Boost::Longbow => "Increase the range of your archers by 50%.",
             Boost::MagicPower => "Increase all your unit's magic damage by 100%.",
             Boost::MagicSwap => "All your unit's physical damage become magic damage.",
@@ -140,54 +154,65 @@ impl Boost {
             Boost::SharkTower => "Convert all your units on buildings into sharks.",
             Boost::Siege => "Increase all damage to buildings by 50%.",
             Boost::Skulls => "Spawn 15 skulls randomly over the map.",
-            Boost::Snakes => "Spawn 20 snakes randomly over the map.",
+            Boost::Snakes => "Spawn 20 snakes in the target area.",
             Boost::SpawnTime => "Reduce all spawning times by 20%.",
             Boost::SpawnTrolls => "Spawn 3 trolls, each towards a path.",
             Boost::SpawnTurtles => "Spawn 3 turtles, each towards a path.",
-            Boost::Spiders => "Spawn 10 spiders randomly over the map.",
-            Boost::Tower => "Spawn a defense tower near the base.",
+            Boost::Spiders => "Spawn 10 spiders in the target area.",
+            Boost::Tower => "Spawn a defense tower near the base, or upgrade the towers' attack.",
             Boost::Warrior => "Increase your warrior's damage by 50%.",
         }
     }
 
+    /// Whether the boost is no longer in the game and can't be selected
+    pub fn is_deprecated(&self) -> bool {
+        matches!(
+            self,
+            Boost::QueueBears
+                | Boost::QueueGnolls
+                | Boost::QueueGoblins
+                | Boost::QueueHammerheads
+                | Boost::QueueMinotaurs
+                | Boost::QueueShamans
+                | Boost::QueueSharks
+                | Boost::QueueSkulls
+                | Boost::QueueTurtles
+        )
+    }
+
+    /// Whether the boost can be selected by the player on `side`, with `buildings`
     pub fn condition<'a>(
         &self,
         mut buildings: impl Iterator<Item = &'a Building>,
-        player: &Player,
+        map: &Map,
+        side: Side,
     ) -> bool {
         match self {
-            Boost::Castle => !buildings.any(|b| b.name == BuildingName::Castle),
-            Boost::Tower => buildings.filter(|b| b.name == BuildingName::Tower).count() < 2,
-            a if a.to_name().starts_with("Queue") => {
-                player.boosts.iter().filter(|b| b.name.to_name().starts_with("Queue")).count() == 0
+            b if b.is_deprecated() => false,
+            Boost::Castle => {
+                !buildings.any(|b| b.name == BuildingName::Castle && b.level == Building::MAX_LEVEL)
+            },
+            Boost::Tower => {
+                let max_towers = map.towers.get(&side).map_or(0, Vec::len);
+                let towers: Vec<_> = buildings.filter(|b| b.name == BuildingName::Tower).collect();
+                towers.len() < max_towers || towers.iter().any(|b| b.level < Building::MAX_LEVEL)
             },
             _ => true,
         }
     }
 
     pub fn duration(&self) -> u64 {
-        match self {
-            Boost::ArmorGain => 20,
-            Boost::Arrows => 40,
-            Boost::BlockRange => 15,
-            Boost::BuildingsBlock => 10,
-            Boost::BuildingsDefense => 25,
-            Boost::DoubleQueue => 20,
-            Boost::Frozen => 5,
-            Boost::Lancer => 40,
-            Boost::Longbow => 40,
-            Boost::MagicPower => 15,
-            Boost::MagicSwap => 40,
-            Boost::Meditation => 40,
-            Boost::NoCollision => 20,
-            Boost::Penetration => 30,
-            Boost::Run => 15,
-            Boost::Siege => 10,
-            Boost::SpawnTime => 50,
-            Boost::Warrior => 40,
-            b if b.to_name().starts_with("Queue") => 40,
-            _ => 0,
-        }
+        balance().boosts.get(self).map_or(0, |b| b.duration)
+    }
+
+    /// Multiplier (or flat bonus) applied by the boost
+    pub fn value(&self) -> f32 {
+        balance().boosts.get(self).map_or(1., |b| b.value)
+    }
+
+    /// Radius of the area of boosts cast on a location of the map
+    pub fn radius(&self) -> Option<f32> {
+        balance().boosts.get(self).and_then(|b| b.radius)
     }
 }
 
@@ -197,30 +222,33 @@ pub fn check_boost_timer(
     mut next_game_state: ResMut<NextState<GameState>>,
     mut settings: ResMut<Settings>,
     mut players: ResMut<Players>,
+    map: Res<Map>,
+    mut rng: ResMut<GameRng>,
     time: Res<Time>,
 ) {
     let time = scale_duration(time.delta(), settings.speed);
     settings.boost_timer.tick(time);
 
     if settings.boost_timer.is_finished() {
-        let me_full = players.me.boosts.len() >= MAX_BOOSTS;
-        let enemy_full = players.enemy.boosts.len() >= MAX_BOOSTS;
+        let me_full = players.me.boosts.len() >= balance().max_boosts;
+        let enemy_full = players.enemy.boosts.len() >= balance().max_boosts;
 
         match settings.game_mode {
             _ if me_full && enemy_full => (),
+            // Without a human player (e.g. in balance simulations), there is no selection
+            _ if !players.me.is_human() => {
+                for player in players.iter_mut().filter(|p| p.boosts.len() < balance().max_boosts) {
+                    select_ai_boost(player, &building_q, &map, &mut rng, &mut activate_boost_msg);
+                }
+            },
             GameMode::SinglePlayer if me_full => {
-                let boost = Boost::iter()
-                    .filter(|b| {
-                        b.condition(
-                            building_q.iter().filter(|b| b.color == players.enemy.color),
-                            &players.enemy,
-                        ) && !players.enemy.boosts.iter().map(|b| b.name).contains(b)
-                    })
-                    .choose(&mut rng())
-                    .unwrap();
-
-                players.enemy.boosts.push(SelectedBoost::new(boost).active());
-                activate_boost_msg.write(ActivateBoostMsg::new(boost, players.enemy.color));
+                select_ai_boost(
+                    &mut players.enemy,
+                    &building_q,
+                    &map,
+                    &mut rng,
+                    &mut activate_boost_msg,
+                );
             },
             GameMode::Multiplayer if me_full => next_game_state.set(GameState::AfterBoostSelection),
             _ => next_game_state.set(GameState::BoostSelection),
@@ -228,6 +256,32 @@ pub fn check_boost_timer(
     }
 }
 
+/// Pick and activate a random boost for an AI player
+fn select_ai_boost(
+    player: &mut Player,
+    building_q: &Query<&Building>,
+    map: &Map,
+    rng: &mut GameRng,
+    activate_boost_msg: &mut MessageWriter<ActivateBoostMsg>,
+) {
+    let boost = Boost::iter()
+        .filter(|b| {
+            player.pool.has_boost(*b)
+                && b.condition(
+                    building_q.iter().filter(|b| b.color == player.color),
+                    map,
+                    player.side,
+                )
+                && !player.boosts.iter().map(|b| b.name).contains(b)
+        })
+        .choose(rng);
+
+    if let Some(boost) = boost {
+        player.boosts.push(SelectedBoost::new(boost).active());
+        activate_boost_msg.write(ActivateBoostMsg::new(boost, player.color));
+    }
+}
+
 pub fn update_boosts(settings: Res<Settings>, mut players: ResMut<Players>, time: Res<Time>) {
     let me = players.me.color;
     for player in players.iter_mut().filter(|p| p.color == me || !p.is_human()) {
@@ -242,11 +296,14 @@ pub fn update_boosts(settings: Res<Settings>, mut players: ResMut<Players>, time
 }
 
 pub fn activate_boost_message(
-    mut unit_q: Query<(Entity, &Transform, &mut Sprite, &mut Unit)>,
+    mut unit_q: Query<(Entity, &Transform, &mut Unit)>,
+    mut status_q: Query<&mut StatusEffects>,
     mut building_q: Query<(Entity, &Transform, &mut Building)>,
     host: Option<Res<Host>>,
     players: Res<Players>,
     map: Res<Map>,
+    grid: Res<SpatialGrid>,
+    mut rng: ResMut<GameRng>,
     mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
     mut spawn_building_msg: MessageWriter<SpawnBuildingMsg>,
     mut despawn_msg: MessageWriter<DespawnMsg>,
@@ -262,7 +319,8 @@ pub fn activate_boost_message(
         if host.is_none() {
             play_audio_msg.write(PlayAudioMsg::new("horn"));
             #[cfg(not(target_arch = "wasm32"))]
-            client_send_msg.write(ClientSendMsg::new(ClientMessage::ActivateBoost(msg.boost)));
+            client_send_msg
+                .write(ClientSendMsg::new(ClientMessage::ActivateBoost(msg.boost, msg.target)));
         } else {
             if players.me.color == msg.color {
                 // Activates own boost
@@ -274,17 +332,34 @@ pub fn activate_boost_message(
                 server_send_msg.write(ServerSendMsg::new(ServerMessage::PlayWarning, None));
             }
 
-            let mut rng = rng();
+            // The AI casts targeted boosts on the enemy closest to its base
+            let radius = msg.boost.radius().unwrap_or_default() * RADIUS;
+            let target = msg.target.or_else(|| {
+                msg.boost.radius().map(|_| {
+                    let base = map.tile_to_world(map.bases[&player.side]).extend(0.);
+                    grid.closest(base, |e| e.unit().is_some_and(|u| u.color != player.color))
+                        .first()
+                        .map_or_else(
+                            || map.tile_to_world(map.bases[&player.side.opposite()]),
+                            |e| e.position.truncate(),
+                        )
+                })
+            });
+
             match msg.boost {
                 Boost::BearDefender => {
-                    for (_, unit_t, _, unit) in unit_q.iter().filter(|(_, _, _, u)| {
-                        u.color == player.color && u.name == UnitName::Priest
-                    }) {
+                    for (_, unit_t, unit) in unit_q
+                        .iter()
+                        .filter(|(_, _, u)| u.color == player.color && u.name == UnitName::Priest)
+                    {
                         spawn_unit_msg.write(SpawnUnitMsg {
                             color: player.color,
                             unit: UnitName::Bear,
                             position: Some(unit_t.translation.truncate()),
                             on_building: None,
+                            hero: false,
+                            veterancy: Veterancy::default(),
+                            cost: 0.,
                             lane: Some(unit.lane),
                             dust_effect: true,
                             entity: None,
@@ -292,39 +367,47 @@ pub fn activate_boost_message(
                     }
                 },
                 Boost::Castle => {
-                    if let Some((base_e, base_t, base)) =
-                        building_q.iter().find(|(_, _, b)| b.is_base && b.color == player.color)
+                    if let Some((base_e, base_t, mut base)) =
+                        building_q.iter_mut().find(|(_, _, b)| b.is_base && b.color == player.color)
                     {
-                        despawn_msg.write(DespawnMsg(base_e));
+                        if base.name == BuildingName::Castle {
+                            base.level = (base.level + 1).min(Building::MAX_LEVEL);
+                        } else {
+                            despawn_msg.write(DespawnMsg(base_e));
 
-                        spawn_building_msg.write(SpawnBuildingMsg {
-                            color: player.color,
-                            building: BuildingName::Castle,
-                            position: base_t.translation.truncate(),
-                            is_base: true,
-                            health: BuildingName::Castle.health() * base.health
-                                / base.name.health(),
-                            dust_effect: true,
-                            with_units: true,
-                            entity: None,
-                        });
+                            spawn_building_msg.write(SpawnBuildingMsg {
+                                color: player.color,
+                                building: BuildingName::Castle,
+                                position: base_t.translation.truncate(),
+                                is_base: true,
+                                health: BuildingName::Castle.health() * base.health
+                                    / base.name.health(),
+                                dust_effect: true,
+                                with_units: true,
+                                entity: None,
+                            });
+                        }
                     }
                 },
                 Boost::Clone => {
-                    for (_, unit_t, _, unit) in unit_q
+                    for (_, unit_t, unit) in unit_q
                         .iter()
-                        .filter(|(_, _, _, u)| {
+                        .filter(|(_, _, u)| {
                             u.color == player.color
                                 && u.on_building.is_none()
+                                && !u.hero
                                 && !matches!(u.action, Action::Attack(_))
                         })
-                        .sample(&mut rng, 8)
+                        .sample(&mut *rng, Boost::Clone.value() as usize)
                     {
                         spawn_unit_msg.write(SpawnUnitMsg {
                             color: player.color,
                             unit: unit.name,
                             position: Some(unit_t.translation.truncate()),
                             on_building: None,
+                            hero: false,
+                            veterancy: unit.veterancy,
+                            cost: 0.,
                             lane: Some(unit.lane),
                             dust_effect: true,
                             entity: None,
@@ -332,10 +415,12 @@ pub fn activate_boost_message(
                     }
                 },
                 Boost::Conversion => {
-                    for (e, _, _, mut u) in unit_q
+                    for (e, _, mut u) in unit_q
                         .iter_mut()
-                        .filter(|(_, _, _, u)| u.color != player.color && u.on_building.is_none())
-                        .sample(&mut rng, 5)
+                        .filter(|(_, _, u)| {
+                            u.color != player.color && u.on_building.is_none() && !u.hero
+                        })
+                        .sample(&mut *rng, Boost::Conversion.value() as usize)
                     {
                         effect_msg.write(EffectMsg::dust(e));
                         u.color = player.color;
@@ -348,20 +433,21 @@ pub fn activate_boost_message(
                         Boost::ConvertHammerheads => UnitName::Hammerhead,
                         _ => unreachable!(),
                     };
-                    for (e, _, mut s, mut u) in unit_q.iter_mut().filter(|(_, _, _, u)| {
-                        u.color == player.color && u.name == UnitName::Lancer
-                    }) {
+                    for (e, _, mut u) in unit_q
+                        .iter_mut()
+                        .filter(|(_, _, u)| u.color == player.color && u.name == UnitName::Lancer)
+                    {
                         effect_msg.write(EffectMsg::dust(e));
-                        s.custom_size = Some(Vec2::splat(unit.size()));
                         u.name = unit;
                         u.action = Action::default();
                     }
                 },
                 Boost::ConvertSharks => {
-                    for (e, _, _, mut u) in unit_q.iter_mut().filter(|(_, _, _, u)| {
+                    for (e, _, mut u) in unit_q.iter_mut().filter(|(_, _, u)| {
                         u.color == player.color
                             && u.name == UnitName::Archer
                             && u.on_building.is_none()
+                            && !u.hero
                     }) {
                         effect_msg.write(EffectMsg::dust(e));
                         u.name = UnitName::Shark;
@@ -369,9 +455,10 @@ pub fn activate_boost_message(
                     }
                 },
                 b @ Boost::GnomesBasic | b @ Boost::GnomesMagic => {
-                    for (e, _, mut s, mut u) in unit_q.iter_mut().filter(|(_, _, _, u)| {
+                    for (e, _, mut u) in unit_q.iter_mut().filter(|(_, _, u)| {
                         u.color != player.color
                             && u.on_building.is_none()
+                            && !u.hero
                             && if b == Boost::GnomesBasic {
                                 u.name.is_basic_unit()
                             } else {
@@ -379,36 +466,60 @@ pub fn activate_boost_message(
                             }
                     }) {
                         effect_msg.write(EffectMsg::dust(e));
-                        s.custom_size = Some(Vec2::splat(UnitName::Gnome.size()));
                         u.name = UnitName::Gnome;
                         u.health = u.health / u.name.health() * UnitName::Gnome.health();
                         u.action = Action::default();
                     }
                 },
-                Boost::InstantHealing => unit_q
-                    .iter_mut()
-                    .filter(|(_, _, _, u)| u.color == player.color)
-                    .for_each(|(_, _, _, mut u)| u.health = u.name.health()),
+                Boost::InstantHealing => {
+                    for (e, _, mut u) in
+                        unit_q.iter_mut().filter(|(_, _, u)| u.color == player.color)
+                    {
+                        u.health = u.max_health(player);
+                        if let Ok(mut statuses) = status_q.get_mut(e) {
+                            statuses.remove(|s| s.is_damage_over_time());
+                        }
+                    }
+                },
                 Boost::InstantArmy => {
-                    for unit in UnitName::iter().sample(&mut rng, 6) {
+                    for unit in
+                        UnitName::iter().sample(&mut *rng, Boost::InstantArmy.value() as usize)
+                    {
                         spawn_unit_msg.write(SpawnUnitMsg {
                             color: player.color,
                             unit,
                             position: None,
                             on_building: None,
+                            hero: false,
+                            veterancy: Veterancy::default(),
+                            cost: 0.,
                             lane: None,
                             dust_effect: true,
                             entity: None,
                         });
                     }
                 },
-                Boost::Lightning => unit_q.iter_mut().for_each(|(_, _, _, mut u)| u.health *= 0.5),
+                Boost::Lightning => {
+                    if let Some(target) = target {
+                        for (entry, _) in grid.units_within(target.extend(0.), radius) {
+                            if let Ok((e, _, mut u)) = unit_q.get_mut(entry.entity) {
+                                effect_msg.write(EffectMsg::impact(e));
+                                u.health *= Boost::Lightning.value();
+                            }
+                        }
+                    } else {
+                        // Without a radius in the balance, the boost hits the whole map
+                        unit_q
+                            .iter_mut()
+                            .for_each(|(_, _, mut u)| u.health *= Boost::Lightning.value());
+                    }
+                },
                 Boost::MinotaurRage => {
                     let enemies = unit_q
                         .iter()
-                        .filter(|(_, _, _, u)| u.color != player.color && !u.name.is_basic_unit())
+                        .filter(|(_, _, u)| u.color != player.color && !u.name.is_basic_unit())
                         .count();
-                    for _ in 0..(enemies / 3).max(1) {
+                    for _ in 0..(enemies / (Boost::MinotaurRage.value() as usize).max(1)).max(1) {
                         spawn_unit_msg.write(SpawnUnitMsg::new(player.color, UnitName::Minotaur));
                     }
                 },
@@ -419,8 +530,8 @@ pub fn activate_boost_message(
                 Boost::Respawn => {
                     unit_q
                         .iter()
-                        .filter(|(_, _, _, u)| u.color == player.color && u.on_building.is_some())
-                        .for_each(|(e, _, _, _)| {
+                        .filter(|(_, _, u)| u.color == player.color && u.on_building.is_some())
+                        .for_each(|(e, _, _)| {
                             despawn_msg.write(DespawnMsg(e));
                         });
 
@@ -431,6 +542,9 @@ pub fn activate_boost_message(
                                 unit: UnitName::Archer,
                                 position: Some(t.translation.truncate() + pos),
                                 on_building: Some(e),
+                                hero: false,
+                                veterancy: Veterancy::default(),
+                                cost: 0.,
                                 lane: None,
                                 dust_effect: true,
                                 entity: None,
@@ -438,25 +552,53 @@ pub fn activate_boost_message(
                         }
                     }
                 },
+                Boost::Ghosts => {
+                    for _ in 0..Boost::Ghosts.value() as usize {
+                        spawn_unit_msg.write(SpawnUnitMsg::new(player.color, UnitName::Ghost));
+                    }
+                },
                 b @ Boost::Skulls | b @ Boost::Snakes | b @ Boost::Spiders => {
-                    let (amount, unit) = match b {
-                        Boost::Skulls => (20, UnitName::Skull),
-                        Boost::Snakes => (20, UnitName::Snake),
-                        Boost::Spiders => (10, UnitName::Spider),
+                    let unit = match b {
+                        Boost::Skulls => UnitName::Skull,
+                        Boost::Snakes => UnitName::Snake,
+                        Boost::Spiders => UnitName::Spider,
                         _ => unreachable!(),
                     };
+                    let amount = b.value() as usize;
 
-                    for (lane, tile) in map
+                    let tiles = map
                         .lanes
                         .iter()
-                        .flat_map(|(l, v)| v[3..v.len() - 3].iter().map(|t| (*l, *t)))
-                        .sample(&mut rng, amount)
-                    {
+                        .enumerate()
+                        .flat_map(|(i, v)| v[3..v.len() - 3].iter().map(move |t| (Lane(i), *t)))
+                        .map(|(lane, tile)| (lane, map.tile_to_world(tile)));
+
+                    let positions: Vec<(Lane, Vec2)> = if let Some(target) = target {
+                        // Spawn over the lanes in the area, or on the lane closest to it
+                        let distance = |p: &Vec2| p.distance(target);
+                        let mut area: Vec<_> =
+                            tiles.clone().filter(|(_, p)| distance(p) <= radius).collect();
+                        if area.is_empty() {
+                            area.extend(
+                                tiles.min_by(|(_, a), (_, b)| distance(a).total_cmp(&distance(b))),
+                            );
+                        }
+
+                        (0..amount).filter_map(|_| area.choose(&mut *rng).copied()).collect()
+                    } else {
+                        // Boosts without a radius in the balance spawn randomly over the map
+                        tiles.sample(&mut *rng, amount)
+                    };
+
+                    for (lane, position) in positions {
                         spawn_unit_msg.write(SpawnUnitMsg {
                             color: player.color,
                             unit,
-                            position: Some(Map::tile_to_world(tile)),
+                            position: Some(position),
                             on_building: None,
+                            hero: false,
+                            veterancy: Veterancy::default(),
+                            cost: 0.,
                             lane: Some(lane),
                             dust_effect: true,
                             entity: None,
@@ -466,8 +608,8 @@ pub fn activate_boost_message(
                 Boost::SharkTower => {
                     unit_q
                         .iter_mut()
-                        .filter(|(_, _, _, u)| u.color == player.color && u.on_building.is_some())
-                        .for_each(|(e, _, _, mut u)| {
+                        .filter(|(_, _, u)| u.color == player.color && u.on_building.is_some())
+                        .for_each(|(e, _, mut u)| {
                             effect_msg.write(EffectMsg::dust(e));
                             u.name = UnitName::Shark;
                             u.action = Action::default();
@@ -480,44 +622,50 @@ pub fn activate_boost_message(
                         _ => unreachable!(),
                     };
 
-                    for path in Lane::iter() {
+                    for lane in map.iter_lanes() {
                         spawn_unit_msg.write(SpawnUnitMsg {
                             color: player.color,
                             unit,
                             position: None,
                             on_building: None,
-                            lane: Some(path),
+                            hero: false,
+                            veterancy: Veterancy::default(),
+                            cost: 0.,
+                            lane: Some(lane),
                             dust_effect: false,
                             entity: None,
                         });
                     }
                 },
                 Boost::Tower => {
+                    let is_tower =
+                        |b: &Building| b.color == player.color && b.name == BuildingName::Tower;
                     let current_positions: Vec<Vec2> = building_q
                         .iter()
-                        .filter_map(|(_, t, b)| {
-                            (b.color == player.color && b.name == BuildingName::Tower)
-                                .then_some(t.translation.truncate())
-                        })
+                        .filter_map(|(_, t, b)| is_tower(b).then_some(t.translation.truncate()))
                         .collect();
 
-                    let possible_positions = match player.side {
-                        Side::Left => [
-                            Map::tile_to_world(TilePos::new(7, 0)),
-                            Map::tile_to_world(TilePos::new(2, 3)),
-                        ],
-                        Side::Right => [
-                            Map::tile_to_world(TilePos::new(23, 0)),
-                            Map::tile_to_world(TilePos::new(28, 4)),
-                        ],
-                    };
+                    // Choose one of the tower positions of the map that's still free
+                    let max_towers = map.towers.get(&player.side).map_or(0, Vec::len);
+                    let position = (current_positions.len() < max_towers)
+                        .then(|| {
+                            map.towers[&player.side]
+                                .iter()
+                                .map(|t| map.tile_to_world(*t))
+                                .filter(|p| !current_positions.contains(p))
+                                .choose(&mut *rng)
+                        })
+                        .flatten();
 
-                    // Choose one of the two locations randomly that are not present in current positions
-                    let position = possible_positions
-                        .into_iter()
-                        .filter(|p| !current_positions.contains(p))
-                        .choose(&mut rng)
-                        .expect("No free tower position.");
+                    // With all the towers built (or none in the map), upgrade them instead
+                    let Some(position) = position else {
+                        for (_, _, mut tower) in
+                            building_q.iter_mut().filter(|(_, _, b)| is_tower(b))
+                        {
+                            tower.level = (tower.level + 1).min(Building::MAX_LEVEL);
+                        }
+                        continue;
+                    };
 
                     spawn_building_msg.write(SpawnBuildingMsg {
                         color: player.color,
@@ -545,3 +693,27 @@ pub fn after_boost_check(
         next_game_state.set(GameState::Playing);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs_tiled::prelude::TilePos;
+
+    #[test]
+    fn towers_are_capped_by_the_map() {
+        let tower = |level| Building {
+            level,
+            ..Building::new(BuildingName::Tower, PlayerColor::Blue, false, 100.)
+        };
+
+        let mut map = Map::default();
+        map.towers.insert(Side::Left, vec![TilePos::new(1, 1), TilePos::new(2, 2)]);
+
+        assert!(Boost::Tower.condition([tower(1)].iter(), &map, Side::Left));
+        assert!(Boost::Tower.condition([tower(3), tower(1)].iter(), &map, Side::Left));
+        assert!(!Boost::Tower.condition([tower(3), tower(3)].iter(), &map, Side::Left));
+
+        // Maps without tower positions never offer the boost
+        assert!(!Boost::Tower.condition([].iter(), &map, Side::Right));
+    }
+}
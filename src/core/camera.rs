@@ -37,6 +37,7 @@ pub fn move_camera(
     mut motion_ev: MessageReader<MouseMotion>,
     mouse: Res<ButtonInput<MouseButton>>,
     window: Single<(Entity, &Window)>,
+    map: Res<Map>,
 ) {
     let (camera, global_t, mut camera_t, mut projection) = camera_q.into_inner();
     let (window_e, window) = *window;
@@ -93,7 +94,7 @@ pub fn move_camera(
     let view_size = projection.area.max - projection.area.min;
 
     // Clamp camera position within bounds
-    let size = Map::MAP_SIZE.as_vec2() * Map::TILE_SIZE as f32;
+    let size = map.view().size();
     let map_center = Map::POSITION.truncate();
 
     position = position.lerp(
@@ -1,5 +1,5 @@
 use bevy::color::Color;
-use bevy::prelude::Vec2;
+use bevy::prelude::{KeyCode, Vec2};
 
 /// Menu
 pub const SUBTITLE_TEXT_SIZE: f32 = 10.;
@@ -22,23 +22,31 @@ pub const WATER_COLOR: Color = Color::srgb_u8(71, 171, 169);
 pub const MAP_Z: f32 = 0.;
 pub const BUILDINGS_Z: f32 = 1.;
 pub const UNITS_Z: f32 = 2.;
+pub const FLYING_Z: f32 = 2.5;
 pub const ARROW_Z: f32 = 3.;
 pub const EFFECT_Z: f32 = 4.;
+pub const TARGET_COLOR: Color = Color::srgba(1., 0.3, 0.2, 0.8);
 
 /// Game settings
-pub const MAX_QUEUE_LENGTH: usize = 10;
 pub const MIN_GAME_SPEED: f32 = 0.25;
 pub const MAX_GAME_SPEED: f32 = 16.;
 pub const FRAME_RATE: u64 = 100;
+pub const SIMULATION_HZ: f64 = 60.;
+pub const REPLAY_SEEK_SECS: u64 = 10;
+pub const MAX_SEEK_STEPS: u64 = 240;
 pub const CAPPED_DELTA_SECS_SPEED: f32 = 0.05;
 pub const UPDATE_TIMER: u64 = 50;
-pub const STRATEGY_TIMER: u64 = 5;
-pub const BOOST_TIMER: f32 = 30.;
-pub const MAX_BOOSTS: usize = 4;
+pub const ABILITY_KEYS: [KeyCode; 2] = [KeyCode::KeyE, KeyCode::KeyR];
 
 /// Units
 pub const UNIT_DEFAULT_SIZE: f32 = 192.;
 pub const RADIUS: f32 = UNIT_DEFAULT_SIZE * UNIT_SCALE * 0.5;
 pub const UNIT_SCALE: f32 = 0.5;
+pub const HERO_SCALE: f32 = 0.65;
 pub const BUILDING_SCALE: f32 = 0.7;
 pub const HEALTH_SIZE: Vec2 = Vec2::new(71., 11.);
+pub const POISON_COLOR: Color = Color::srgb_u8(154, 80, 196);
+pub const RANK_SIZE: Vec2 = Vec2::new(24., 24.);
+/// Tint of the rank badge, from the lowest to the highest rank
+pub const RANK_COLORS: [Color; 3] =
+    [Color::srgb_u8(205, 127, 50), Color::srgb_u8(192, 192, 192), Color::srgb_u8(255, 215, 0)];
@@ -1,104 +1,336 @@
 use crate::core::constants::MAP_Z;
+use crate::core::player::Side;
+use crate::utils::NameFromEnum;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::TilePos;
-use pathfinding::prelude::astar;
+use bevy_ecs_tiled::prelude::{tiled, TilePos, TiledMapAsset};
+use pathfinding::prelude::{astar, bfs_reach};
 use serde::{Deserialize, Serialize};
 use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-#[derive(EnumIter, Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
-pub enum Lane {
-    Top,
-    Mid,
-    Bot,
+/// Index of a lane in the map, counting from the top
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub struct Lane(pub usize);
+
+/// How a unit travels over the map
+#[derive(EnumIter, Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Movement {
+    /// Walks over the walkable tiles
+    #[default]
+    Land,
+    /// Swims over the water tiles
+    Water,
+    /// Walks and swims over both
+    Amphibious,
+    /// Flies over everything, straight to the enemy base
+    Air,
 }
 
-impl Lane {
-    /// Y-position of the tile that is used as waypoint (middle) of the path to follow
-    pub fn waypoint(&self) -> TilePos {
-        match self {
-            Lane::Top => TilePos::new(14, 2),
-            Lane::Mid => TilePos::new(14, 6),
-            Lane::Bot => TilePos::new(14, 10),
-        }
+/// Maps that can be selected to play on
+#[derive(EnumIter, Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum MapName {
+    #[default]
+    Island,
+    Duel,
+    Crossroads,
+}
+
+impl MapName {
+    pub fn path(&self) -> String {
+        format!("map/{}.tmx", self.to_lowername())
     }
 }
 
 /// Metadata required to draw the map
-#[derive(Resource, Debug)]
+///
+/// The data is read from the Tiled map:
+///  - Tile layer `walkable`: Every non-empty tile can be walked on by units.
+///  - Tile layer `water` (optional): Every non-empty tile can be sailed by naval
+///    units. Without this layer, the water is every non-walkable tile connected to
+///    the edge of the map.
+///  - Object layer `lanes`: Point objects named `base` and `tower` (with a `side`
+///    property), `waypoint` for the middle of every lane and `sea waypoint` for the
+///    middle of every sea lane. Lanes are ordered from top to bottom by the position
+///    of their waypoint.
+///
+/// Naval and amphibious units follow the sea lanes, if the map has any.
+#[derive(Resource, Clone, Debug, Default)]
 pub struct Map {
-    pub lanes: HashMap<Lane, Vec<TilePos>>,
+    pub name: MapName,
+    pub handle: Handle<TiledMapAsset>,
+    pub size: UVec2,
+    pub walkable: Vec<bool>,
+    pub water: Vec<bool>,
+    pub bases: HashMap<Side, TilePos>,
+    pub towers: HashMap<Side, Vec<TilePos>>,
+    pub waypoints: Vec<TilePos>,
+    pub sea_waypoints: Vec<TilePos>,
+    pub lanes: Vec<Vec<TilePos>>,
+    /// Lanes of the naval and amphibious units
+    pub sea_lanes: HashMap<Movement, Vec<Vec<TilePos>>>,
+    /// Next tile to travel to from every tile, per movement, lane and side
+    pub flow_fields: HashMap<Movement, Vec<HashMap<Side, Vec<Option<TilePos>>>>>,
 }
 
-impl Default for Map {
-    fn default() -> Self {
-        let start = Self::STARTING_POSITIONS[0];
-        let end = Self::STARTING_POSITIONS[1];
+impl Map {
+    pub const POSITION: Vec3 = Vec3::new(0., -300., MAP_Z);
+    pub const TILE_SIZE: u32 = 64;
+    pub const MIN_LANES: usize = 2;
+    pub const MAX_LANES: usize = 5;
+
+    pub const WALKABLE_LAYER: &'static str = "walkable";
+    pub const WATER_LAYER: &'static str = "water";
+    pub const LANES_LAYER: &'static str = "lanes";
+
+    pub fn from_tiled(
+        name: MapName,
+        handle: Handle<TiledMapAsset>,
+        tiled_map: &tiled::Map,
+    ) -> Result<Self, String> {
+        let size = UVec2::new(tiled_map.width, tiled_map.height);
+
+        let walkable_layer = tiled_map
+            .layers()
+            .find(|l| l.name == Self::WALKABLE_LAYER)
+            .and_then(|l| l.as_tile_layer())
+            .ok_or_else(|| format!("Map has no tile layer {}.", Self::WALKABLE_LAYER))?;
+
+        let walkable: Vec<bool> = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| (x, y)))
+            .map(|(x, y)| walkable_layer.get_tile(x as i32, y as i32).is_some())
+            .collect();
+
+        let water = match tiled_map
+            .layers()
+            .find(|l| l.name == Self::WATER_LAYER)
+            .and_then(|l| l.as_tile_layer())
+        {
+            Some(water_layer) => (0..size.y)
+                .flat_map(|y| (0..size.x).map(move |x| (x, y)))
+                .map(|(x, y)| water_layer.get_tile(x as i32, y as i32).is_some())
+                .collect(),
+            None => Self::ocean(size, &walkable),
+        };
+
+        let mut bases = HashMap::new();
+        let mut towers: HashMap<Side, Vec<TilePos>> = HashMap::new();
+        let mut waypoints = vec![];
+        let mut sea_waypoints = vec![];
+
+        let objects = tiled_map
+            .layers()
+            .find(|l| l.name == Self::LANES_LAYER)
+            .and_then(|l| l.as_object_layer())
+            .ok_or_else(|| format!("Map has no object layer {}.", Self::LANES_LAYER))?;
+
+        for object in objects.objects() {
+            let tile = TilePos::new(
+                (object.x / tiled_map.tile_width as f32) as u32,
+                (object.y / tiled_map.tile_height as f32) as u32,
+            );
+
+            let property = |name: &str| match object.properties.get(name) {
+                Some(tiled::PropertyValue::StringValue(value)) => Ok(value.clone()),
+                _ => Err(format!("Object {} has no property {name}.", object.name)),
+            };
+
+            let side = || {
+                let side = property("side")?;
+                Side::iter()
+                    .find(|s| format!("{s:?}") == side)
+                    .ok_or_else(|| format!("Invalid side for object {}.", object.name))
+            };
+
+            match object.name.as_str() {
+                "base" => {
+                    bases.insert(side()?, tile);
+                },
+                "tower" => towers.entry(side()?).or_default().push(tile),
+                "waypoint" => waypoints.push(tile),
+                "sea waypoint" => sea_waypoints.push(tile),
+                name => return Err(format!("Unknown object {name} in the map.")),
+            }
+        }
+
+        if !(Self::MIN_LANES..=Self::MAX_LANES).contains(&waypoints.len()) {
+            return Err(format!(
+                "Map must have between {} and {} lanes, found {}.",
+                Self::MIN_LANES,
+                Self::MAX_LANES,
+                waypoints.len()
+            ));
+        }
+
+        let base = |side: Side| {
+            bases.get(&side).copied().ok_or_else(|| format!("Map has no base for side {side:?}."))
+        };
+        let (start, end) = (base(Side::Left)?, base(Side::Right)?);
+
+        waypoints.sort_by_key(|t| (t.y, t.x));
+        sea_waypoints.sort_by_key(|t| (t.y, t.x));
+
+        let mut map = Self {
+            name,
+            handle,
+            size,
+            walkable,
+            water,
+            bases,
+            towers,
+            waypoints,
+            sea_waypoints,
+            lanes: vec![],
+            sea_lanes: HashMap::new(),
+            flow_fields: HashMap::new(),
+        };
+
+        map.lanes = map
+            .waypoints
+            .iter()
+            .map(|&waypoint| map.find_lane(start, waypoint, end, Movement::Land))
+            .collect::<Result<_, _>>()?;
+
+        let mut naval_lanes = vec![];
+        let mut amphibious_lanes = vec![];
+        for &waypoint in &map.sea_waypoints {
+            if !map.is_water(waypoint) {
+                return Err(format!("Sea waypoint {waypoint:?} is not on the water."));
+            }
+
+            // Naval units start and end at the water closest to the bases
+            let reachable: Vec<TilePos> =
+                bfs_reach(waypoint, |t| map.get_neighbors(*t, Movement::Water)).collect();
+            let harbor = |base: TilePos| {
+                let pos = map.tile_to_world(base);
+                reachable
+                    .iter()
+                    .min_by(|a, b| {
+                        let da = map.tile_to_world(**a).distance(pos);
+                        let db = map.tile_to_world(**b).distance(pos);
+                        da.total_cmp(&db)
+                    })
+                    .copied()
+                    .ok_or_else(|| format!("No water reachable from sea waypoint {waypoint:?}."))
+            };
+
+            let (from, to) = (harbor(start)?, harbor(end)?);
+            naval_lanes.push(map.find_lane(from, waypoint, to, Movement::Water)?);
+            amphibious_lanes.push(map.find_lane(start, waypoint, end, Movement::Amphibious)?);
+        }
+
+        map.sea_lanes.insert(Movement::Water, naval_lanes);
+        map.sea_lanes.insert(Movement::Amphibious, amphibious_lanes);
+
+        let movements: Vec<Movement> =
+            Movement::iter().filter(|m| *m != Movement::Air && map.movement(*m) == *m).collect();
+        for movement in movements {
+            let field = map.compute_flow_fields(movement);
+            map.flow_fields.insert(movement, field);
+        }
 
-        let lanes = Lane::iter()
-            .map(|lane| {
-                // Compute two segments: start → waypoint → end
-                let mut firs_segment = Self::find_path(start, lane.waypoint());
-                let mut second_segment = Self::find_path(lane.waypoint(), end);
+        Ok(map)
+    }
 
-                // Remove the waypoint (overlap) from second segment
-                second_segment.remove(0);
-                firs_segment.extend(second_segment);
+    /// Non-walkable tiles connected to the edge of the map
+    fn ocean(size: UVec2, walkable: &[bool]) -> Vec<bool> {
+        let is_water = |t: &TilePos| !walkable[(t.y * size.x + t.x) as usize];
 
-                (lane, firs_segment)
+        let mut water = vec![false; walkable.len()];
+        let mut stack: Vec<TilePos> = (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| TilePos::new(x, y)))
+            .filter(|t| {
+                (t.x == 0 || t.y == 0 || t.x == size.x - 1 || t.y == size.y - 1) && is_water(t)
             })
             .collect();
 
-        Self {
-            lanes,
+        while let Some(tile) = stack.pop() {
+            let idx = (tile.y * size.x + tile.x) as usize;
+            if water[idx] {
+                continue;
+            }
+
+            water[idx] = true;
+            for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+                let (x, y) = (tile.x as i32 + dx, tile.y as i32 + dy);
+                if x >= 0 && y >= 0 && x < size.x as i32 && y < size.y as i32 {
+                    let next = TilePos::new(x as u32, y as u32);
+                    if is_water(&next) {
+                        stack.push(next);
+                    }
+                }
+            }
         }
+
+        water
     }
-}
 
-impl Map {
-    pub const POSITION: Vec3 = Vec3::new(0., -300., MAP_Z);
-    pub const TILE_SIZE: u32 = 64;
-    pub const MAP_SIZE: UVec2 = UVec2::new(30, 16);
-
-    // Rect that the map occupies in world coordinates
-    pub const MAP_VIEW: Rect = Rect {
-        min: Vec2::new(
-            -(Self::MAP_SIZE.x as f32) * Self::TILE_SIZE as f32 * 0.5,
-            -(Self::MAP_SIZE.y as f32) * Self::TILE_SIZE as f32 * 0.5,
-        ),
-        max: Vec2::new(
-            Self::MAP_SIZE.x as f32 * Self::TILE_SIZE as f32 * 0.5,
-            Self::MAP_SIZE.y as f32 * Self::TILE_SIZE as f32 * 0.5,
-        ),
-    };
-
-    pub const STARTING_POSITIONS: [TilePos; 2] = [TilePos::new(3, 0), TilePos::new(27, 0)];
-
-    const WALKABLE_BITS: [u32; 16] = [
-        0b000100000000000000000000000100,
-        0b001111111000011110000001111110,
-        0b001111111101111110000011111110,
-        0b001111111111000011000111001110,
-        0b001111000000111101111100111110,
-        0b000111111111111110000011111110,
-        0b000000110000111111111111110000,
-        0b000111111011011111111001111100,
-        0b000000001111100110000110111100,
-        0b000011111111111001111111111000,
-        0b000011111111111101111111000000,
-        0b000111100011001111111111100000,
-        0b000111100000000111111011100000,
-        0b000000000000000111110000000000,
-        0b000000000000000111100000000000,
-        0b000000000000000000000000000000,
-    ];
-
-    pub fn starting_positions() -> Vec<Vec2> {
-        Self::STARTING_POSITIONS.into_iter().map(Self::tile_to_world).collect()
-    }
-
-    pub fn get_neighbors(pos: TilePos) -> Vec<TilePos> {
+    /// Path from `start` to `end` passing through `waypoint`
+    fn find_lane(
+        &self,
+        start: TilePos,
+        waypoint: TilePos,
+        end: TilePos,
+        movement: Movement,
+    ) -> Result<Vec<TilePos>, String> {
+        // Compute two segments: start → waypoint → end
+        let mut first_segment = self.find_path(start, waypoint, movement)?;
+        let mut second_segment = self.find_path(waypoint, end, movement)?;
+
+        // Remove the waypoint (overlap) from second segment
+        second_segment.remove(0);
+        first_segment.extend(second_segment);
+        Ok(first_segment)
+    }
+
+    /// Precompute the next tile to travel to from every tile of the map, for units
+    /// with `movement` of every side following every lane
+    fn compute_flow_fields(&self, movement: Movement) -> Vec<HashMap<Side, Vec<Option<TilePos>>>> {
+        // Lanes share most of their tiles, so cache the steps by (tile, target)
+        let mut steps: HashMap<(TilePos, TilePos), Option<TilePos>> = HashMap::new();
+
+        (0..self.lanes_of(movement).len())
+            .map(|i| {
+                Side::iter()
+                    .map(|side| {
+                        let path = self.lane(&Lane(i), side, movement);
+
+                        let field = (0..self.size.y)
+                            .flat_map(|y| (0..self.size.x).map(move |x| TilePos::new(x, y)))
+                            .map(|tile| {
+                                if !self.is_navigable(tile, movement)
+                                    || tile == *path.last().unwrap()
+                                {
+                                    return None;
+                                }
+
+                                let target = self.lane_target(tile, &path);
+                                *steps.entry((tile, target)).or_insert_with(|| {
+                                    self.try_find_path(tile, target, movement)
+                                        .and_then(|p| p.get(1).copied())
+                                })
+                            })
+                            .collect();
+
+                        (side, field)
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Rect that the map occupies in world coordinates
+    pub fn view(&self) -> Rect {
+        let size = self.size.as_vec2() * Self::TILE_SIZE as f32;
+        Rect::from_center_size(Vec2::ZERO, size)
+    }
+
+    /// World positions of the bases, sorted by side
+    pub fn starting_positions(&self) -> Vec<Vec2> {
+        Side::iter().map(|s| self.tile_to_world(self.bases[&s])).collect()
+    }
+
+    pub fn get_neighbors(&self, pos: TilePos, movement: Movement) -> Vec<TilePos> {
         let moves = [(-1, 0), (1, 0), (0, -1), (0, 1), (-1, -1), (-1, 1), (1, -1), (1, 1)];
 
         moves
@@ -108,13 +340,13 @@ impl Map {
                 let y = pos.y as i32 + dy;
 
                 // Check map bounds
-                if x < 0 || y < 0 || x >= Self::MAP_SIZE.x as i32 || y >= Self::MAP_SIZE.y as i32 {
+                if x < 0 || y < 0 || x >= self.size.x as i32 || y >= self.size.y as i32 {
                     return None;
                 }
 
                 let new_pos = TilePos::new(x as u32, y as u32);
 
-                if !Self::is_walkable(new_pos) {
+                if !self.is_navigable(new_pos, movement) {
                     return None;
                 }
 
@@ -122,7 +354,7 @@ impl Map {
                 if dx != 0 && dy != 0 {
                     let pos1 = TilePos::new((pos.x as i32 + dx) as u32, pos.y); // Horizontal
                     let pos2 = TilePos::new(pos.x, (pos.y as i32 + dy) as u32); // Vertical
-                    if !Self::is_walkable(pos1) || !Self::is_walkable(pos2) {
+                    if !self.is_navigable(pos1, movement) || !self.is_navigable(pos2, movement) {
                         return None;
                     }
                 }
@@ -132,30 +364,171 @@ impl Map {
             .collect()
     }
 
-    pub fn is_walkable(pos: TilePos) -> bool {
-        Self::WALKABLE_BITS[pos.y as usize] & (1 << (Self::MAP_SIZE.x - 1 - pos.x)) != 0
+    pub fn is_walkable(&self, pos: TilePos) -> bool {
+        pos.x < self.size.x
+            && pos.y < self.size.y
+            && self.walkable[(pos.y * self.size.x + pos.x) as usize]
+    }
+
+    pub fn is_water(&self, pos: TilePos) -> bool {
+        pos.x < self.size.x
+            && pos.y < self.size.y
+            && self.water[(pos.y * self.size.x + pos.x) as usize]
+    }
+
+    /// Whether there's water within one tile of a building at `position` with `size`
+    pub fn is_coastal(&self, position: Vec3, size: Vec2) -> bool {
+        let extent = size * 0.5 + Self::TILE_SIZE as f32;
+        let min = self.world_to_tile(&(position + Vec3::new(-extent.x, extent.y, 0.)));
+        let max = self.world_to_tile(&(position + Vec3::new(extent.x, -extent.y, 0.)));
+
+        (min.y..=max.y).any(|y| (min.x..=max.x).any(|x| self.is_water(TilePos::new(x, y))))
+    }
+
+    pub fn is_navigable(&self, pos: TilePos, movement: Movement) -> bool {
+        match movement {
+            Movement::Land => self.is_walkable(pos),
+            Movement::Water => self.is_water(pos),
+            Movement::Amphibious => self.is_walkable(pos) || self.is_water(pos),
+            Movement::Air => pos.x < self.size.x && pos.y < self.size.y,
+        }
     }
 
-    pub fn find_path(start: TilePos, end: TilePos) -> Vec<TilePos> {
+    pub fn try_find_path(
+        &self,
+        start: TilePos,
+        end: TilePos,
+        movement: Movement,
+    ) -> Option<Vec<TilePos>> {
         astar(
             &start,
-            |pos| Self::get_neighbors(*pos).into_iter().map(|pos| (pos, 1)).collect::<Vec<_>>(),
+            |pos| {
+                self.get_neighbors(*pos, movement)
+                    .into_iter()
+                    .map(|pos| (pos, 1))
+                    .collect::<Vec<_>>()
+            },
             |pos| (start.x as i32 - pos.x as i32).abs() + (start.y as i32 - pos.y as i32).abs(),
             |pos| *pos == end,
         )
         .map(|(path, _)| path)
-        .unwrap_or_else(|| panic!("Unable to find a path from {start:?} to {end:?}."))
     }
 
-    pub fn lane(&self, path: &Lane) -> Vec<TilePos> {
-        self.lanes.get(path).unwrap().clone()
+    pub fn find_path(
+        &self,
+        start: TilePos,
+        end: TilePos,
+        movement: Movement,
+    ) -> Result<Vec<TilePos>, String> {
+        self.try_find_path(start, end, movement)
+            .ok_or_else(|| format!("Unable to find a path from {start:?} to {end:?}."))
+    }
+
+    pub fn n_lanes(&self) -> usize {
+        self.lanes.len()
+    }
+
+    pub fn iter_lanes(&self) -> impl Iterator<Item = Lane> {
+        (0..self.n_lanes()).map(Lane)
+    }
+
+    /// Movement used to travel over this map, where naval and amphibious units
+    /// walk the land lanes if the map has no sea lanes
+    pub fn movement(&self, movement: Movement) -> Movement {
+        match movement {
+            Movement::Water | Movement::Amphibious
+                if self.sea_lanes.get(&movement).is_none_or(|l| l.is_empty()) =>
+            {
+                Movement::Land
+            },
+            m => m,
+        }
+    }
+
+    /// Lanes followed by units with `movement`
+    pub fn lanes_of(&self, movement: Movement) -> &[Vec<TilePos>] {
+        match self.movement(movement) {
+            Movement::Land | Movement::Air => &self.lanes,
+            m => &self.sea_lanes[&m],
+        }
+    }
+
+    /// Fall back to the last lane if the map was reloaded with fewer lanes
+    fn lane_index(&self, lane: &Lane, movement: Movement) -> usize {
+        lane.0.min(self.lanes_of(movement).len() - 1)
+    }
+
+    /// Tiles of a lane in the order that units of `side` travel them
+    pub fn lane(&self, lane: &Lane, side: Side, movement: Movement) -> Vec<TilePos> {
+        let mut path = self.lanes_of(movement)[self.lane_index(lane, movement)].clone();
+
+        // Reverse paths for the enemy
+        if side == Side::Right {
+            path.reverse();
+        }
+
+        path
+    }
+
+    /// Return the tile after the lane tile that is closest to `tile`
+    fn lane_target(&self, tile: TilePos, path: &[TilePos]) -> TilePos {
+        let pos = self.tile_to_world(tile);
+
+        *path
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| {
+                let da = self.tile_to_world(**a).distance(pos);
+                let db = self.tile_to_world(**b).distance(pos);
+                da.partial_cmp(&db).unwrap()
+            })
+            .and_then(|(i, _)| path.get(i + 1).or_else(|| path.last()))
+            .unwrap()
+    }
+
+    /// Return the next tile to travel to for a unit of `side` with `movement`
+    /// following `lane`, or None if the unit reached the end of the lane
+    pub fn next_tile(
+        &self,
+        tile: TilePos,
+        lane: &Lane,
+        side: Side,
+        movement: Movement,
+    ) -> Option<TilePos> {
+        let movement = self.movement(movement);
+        let idx = self.lane_index(lane, movement);
+        let path = &self.lanes_of(movement)[idx];
+        let end = match side {
+            Side::Left => path.last(),
+            Side::Right => path.first(),
+        };
+
+        if tile == *end.unwrap() {
+            return None;
+        }
+
+        let precomputed = if tile.x < self.size.x && tile.y < self.size.y {
+            self.flow_fields[&movement][idx][&side][(tile.y * self.size.x + tile.x) as usize]
+        } else {
+            None
+        };
+
+        // Tiles that aren't navigable aren't precomputed. Units stranded there
+        // (e.g., after a conversion) swim or walk back to their lane
+        precomputed.or_else(|| {
+            let path = self.lane(lane, side, movement);
+            let target = self.lane_target(tile, &path);
+
+            self.try_find_path(tile, target, movement)
+                .or_else(|| self.try_find_path(tile, target, Movement::Amphibious))
+                .and_then(|p| p.get(1).copied())
+                .or(Some(target))
+        })
     }
 
-    pub fn tile_to_world(tile: TilePos) -> Vec2 {
-        let map_origin = Vec2::new(
-            Self::POSITION.x + Self::MAP_VIEW.min.x,
-            Self::POSITION.y + Self::MAP_VIEW.max.y,
-        );
+    pub fn tile_to_world(&self, tile: TilePos) -> Vec2 {
+        let view = self.view();
+        let map_origin = Vec2::new(Self::POSITION.x + view.min.x, Self::POSITION.y + view.max.y);
 
         Vec2::new(
             map_origin.x + Self::TILE_SIZE as f32 * (tile.x as f32 + 0.5),
@@ -163,11 +536,9 @@ impl Map {
         )
     }
 
-    pub fn world_to_tile(pos: &Vec3) -> TilePos {
-        let map_origin = Vec2::new(
-            Self::POSITION.x + Self::MAP_VIEW.min.x,
-            Self::POSITION.y + Self::MAP_VIEW.max.y,
-        );
+    pub fn world_to_tile(&self, pos: &Vec3) -> TilePos {
+        let view = self.view();
+        let map_origin = Vec2::new(Self::POSITION.x + view.min.x, Self::POSITION.y + view.max.y);
 
         let x = (pos.x - map_origin.x) / Self::TILE_SIZE as f32;
         let y = (map_origin.y - pos.y) / Self::TILE_SIZE as f32;
@@ -175,3 +546,101 @@ impl Map {
         TilePos::new(x as u32, y as u32)
     }
 }
+
+/// Every map in the game, indexed by name
+#[derive(Resource, Default, Deref, DerefMut)]
+pub struct Maps(pub HashMap<MapName, Map>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::path::Path;
+
+    /// Load a shipped map after editing its file with `edit`
+    fn load(name: MapName, edit: impl Fn(String) -> String) -> Result<Map, String> {
+        let path = Path::new("assets").join(name.path());
+        let file = edit(std::fs::read_to_string(&path).unwrap());
+        let mut loader = tiled::Loader::with_reader(move |_: &Path| {
+            Ok::<_, std::io::Error>(Cursor::new(file.clone()))
+        });
+
+        Map::from_tiled(name, Handle::default(), &loader.load_tmx_map(path).unwrap())
+    }
+
+    /// Keep only the first `keep` waypoints of a map file
+    fn keep_waypoints(mut file: String, keep: usize) -> String {
+        let mut from = 0;
+        for _ in 0..keep {
+            from += file[from..].find("name=\"waypoint\"").unwrap() + 1;
+        }
+
+        while let Some(i) = file[from..].find("name=\"waypoint\"").map(|i| i + from) {
+            let start = file[..i].rfind("<object").unwrap();
+            let end = i + file[i..].find("</object>").unwrap() + "</object>".len();
+            file.replace_range(start..end, "");
+        }
+
+        file
+    }
+
+    #[test]
+    fn lanes_connect_the_bases() {
+        for name in MapName::iter() {
+            let map = load(name, |f| f).unwrap();
+
+            assert_eq!(map.lanes.len(), map.waypoints.len());
+            for (path, waypoint) in map.lanes.iter().zip(&map.waypoints) {
+                assert_eq!(path.first(), Some(&map.bases[&Side::Left]));
+                assert_eq!(path.last(), Some(&map.bases[&Side::Right]));
+                assert!(path.contains(waypoint));
+                assert!(path.iter().all(|t| map.is_walkable(*t)));
+            }
+        }
+    }
+
+    #[test]
+    fn lanes_are_sorted_from_top_to_bottom() {
+        let map = load(MapName::Crossroads, |f| f).unwrap();
+
+        assert_eq!(map.n_lanes(), 5);
+        assert!(map.waypoints.windows(2).all(|w| w[0].y <= w[1].y));
+    }
+
+    #[test]
+    fn single_lane_is_rejected() {
+        let err = load(MapName::Island, |f| keep_waypoints(f, 1)).unwrap_err();
+        assert!(err.contains("between 2 and 5 lanes, found 1"), "{err}");
+    }
+
+    #[test]
+    fn missing_walkable_layer_is_rejected() {
+        let err = load(MapName::Island, |f| f.replace("name=\"walkable\"", "name=\"ground\""))
+            .unwrap_err();
+        assert!(err.contains("no tile layer walkable"), "{err}");
+    }
+
+    #[test]
+    fn missing_lanes_layer_is_rejected() {
+        let err =
+            load(MapName::Island, |f| f.replace("name=\"lanes\"", "name=\"objects\"")).unwrap_err();
+        assert!(err.contains("no object layer lanes"), "{err}");
+    }
+
+    #[test]
+    fn unknown_object_is_rejected() {
+        let err = load(MapName::Island, |f| f.replacen("name=\"tower\"", "name=\"castle\"", 1))
+            .unwrap_err();
+        assert!(err.contains("Unknown object castle"), "{err}");
+    }
+
+    #[test]
+    fn sea_waypoint_on_land_is_rejected() {
+        // The waypoints of the land lanes are on land
+        let err = load(MapName::Crossroads, |f| {
+            f.replacen("name=\"waypoint\"", "name=\"sea waypoint\"", 1)
+        })
+        .unwrap_err();
+        assert!(err.contains("is not on the water"), "{err}");
+    }
+}
@@ -2,23 +2,68 @@ use crate::core::assets::WorldAssets;
 use crate::core::audio::PlayAudioMsg;
 use crate::core::camera::MainCamera;
 use crate::core::constants::MAX_ZOOM;
-use crate::core::map::map::Map;
+use crate::core::map::map::{Map, MapName, Maps};
 use crate::core::map::ui::systems::UiCmp;
 use crate::core::map::utils::UiScaleLens;
 use crate::core::player::Players;
 use crate::core::units::buildings::Building;
+use crate::utils::NameFromEnum;
 use bevy::prelude::*;
-use bevy_ecs_tiled::prelude::{TiledMap, TilemapAnchor};
+use bevy_ecs_tiled::prelude::{TiledMap, TiledMapAsset, TilemapAnchor};
 use bevy_tweening::{RepeatCount, RepeatStrategy, Tween, TweenAnim};
 use std::time::Duration;
+use strum::IntoEnumIterator;
 
 #[derive(Component)]
 pub struct MapCmp;
 
+pub fn load_maps(mut maps: ResMut<Maps>, assets: Res<AssetServer>) {
+    for name in MapName::iter() {
+        maps.insert(
+            name,
+            Map {
+                name,
+                handle: assets.load(name.path()),
+                ..default()
+            },
+        );
+    }
+}
+
+/// Read the maps' metadata once the Tiled maps are (re)loaded
+pub fn update_maps(
+    mut asset_msg: MessageReader<AssetEvent<TiledMapAsset>>,
+    mut maps: ResMut<Maps>,
+    mut map: ResMut<Map>,
+    tiled_maps: Res<Assets<TiledMapAsset>>,
+) {
+    for msg in asset_msg.read() {
+        for m in maps.values_mut() {
+            if msg.is_loaded_with_dependencies(&m.handle) || msg.is_modified(&m.handle) {
+                if let Some(tiled_map) = tiled_maps.get(&m.handle) {
+                    // Keep the previous version of the map if the new one is invalid
+                    match Map::from_tiled(m.name, m.handle.clone(), &tiled_map.map) {
+                        Ok(new_map) => *m = new_map,
+                        Err(e) => {
+                            error!("Failed to read map {}: {e}", m.name.to_name());
+                            continue;
+                        },
+                    }
+
+                    // Update the map in use as well
+                    if map.name == m.name {
+                        *map = m.clone();
+                    }
+                }
+            }
+        }
+    }
+}
+
 pub fn draw_map(
     mut commands: Commands,
     mut camera_q: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
-    assets: Res<AssetServer>,
+    map: Res<Map>,
 ) {
     let (mut camera_t, mut projection) = camera_q.single_mut().unwrap();
     camera_t.translation = Vec3::new(0., 0., 1.);
@@ -28,7 +73,7 @@ pub fn draw_map(
     }
 
     commands.spawn((
-        TiledMap(assets.load("map/map.tmx")),
+        TiledMap(map.handle.clone()),
         TilemapAnchor::Center,
         Transform::from_translation(Map::POSITION),
         MapCmp,
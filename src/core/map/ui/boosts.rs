@@ -2,12 +2,16 @@ use crate::core::assets::WorldAssets;
 use crate::core::audio::PlayAudioMsg;
 use crate::core::boosts::{ActivateBoostMsg, AfterBoostCount, Boost, CardCmp};
 use crate::core::constants::BUTTON_TEXT_SIZE;
+use crate::core::map::map::Map;
 use crate::core::map::systems::MapCmp;
 use crate::core::map::ui::systems::UiCmp;
 use crate::core::map::utils::UiScaleLens;
 use crate::core::menu::utils::{add_root_node, add_text};
 use crate::core::player::{Player, Players, SelectedBoost};
+use crate::core::replay::{PlayerInput, Replay};
+use crate::core::rng::GameRng;
 use crate::core::settings::{GameMode, Settings};
+use crate::core::simulation::Step;
 use crate::core::states::GameState;
 use crate::core::units::buildings::Building;
 use crate::core::utils::cursor;
@@ -17,7 +21,6 @@ use bevy::window::SystemCursorIcon;
 use bevy_tweening::{Tween, TweenAnim};
 use itertools::Itertools;
 use rand::prelude::IteratorRandom;
-use rand::rng;
 use std::time::Duration;
 use strum::IntoEnumIterator;
 
@@ -26,6 +29,8 @@ pub fn setup_boost_selection(
     building_q: Query<&Building>,
     settings: Res<Settings>,
     players: Res<Players>,
+    map: Res<Map>,
+    mut rng: ResMut<GameRng>,
     mut play_audio_ev: MessageWriter<PlayAudioMsg>,
     assets: Res<WorldAssets>,
     window: Single<&Window>,
@@ -36,17 +41,17 @@ pub fn setup_boost_selection(
     let boosts = |p: &Player, q: &Query<&Building>| -> Vec<Boost> {
         Boost::iter()
             .filter(|b| {
-                b.condition(q.iter().filter(|b| b.color == players.me.color), p)
+                b.condition(q.iter().filter(|b| b.color == p.color), &map, p.side)
                     && !p.boosts.iter().map(|b| b.name).contains(b)
             })
             .collect()
     };
 
-    let own_boosts = boosts(&players.me, &building_q).into_iter().sample(&mut rng(), 3);
+    let own_boosts = boosts(&players.me, &building_q).into_iter().sample(&mut *rng, 3);
 
     // Select a random boost for the NPC
     let enemy_boost = if settings.game_mode == GameMode::SinglePlayer {
-        boosts(&players.enemy, &building_q).into_iter().choose(&mut rng()).unwrap()
+        boosts(&players.enemy, &building_q).into_iter().choose(&mut *rng).unwrap()
     } else {
         Boost::ArmorGain // Random boost (never used)
     };
@@ -135,6 +140,8 @@ pub fn setup_boost_selection(
                             trigger: On<Pointer<Click>>,
                             settings: Res<Settings>,
                             mut players: ResMut<Players>,
+                            mut replay: Option<ResMut<Replay>>,
+                            step: Res<Step>,
                             mut boost_count: ResMut<AfterBoostCount>,
                             mut activate_boost_msg: MessageWriter<ActivateBoostMsg>,
                             mut play_audio_msg: MessageWriter<PlayAudioMsg>,
@@ -144,6 +151,11 @@ pub fn setup_boost_selection(
 
                                 players.me.boosts.push(SelectedBoost::new(boost));
 
+                                if let Some(replay) = &mut replay {
+                                    let enemy_boost = (settings.game_mode == GameMode::SinglePlayer).then_some(enemy_boost);
+                                    replay.record(*step, PlayerInput::SelectBoost(boost, enemy_boost));
+                                }
+
                                 if settings.game_mode == GameMode::SinglePlayer {
                                     players.enemy.boosts.push(SelectedBoost::new(enemy_boost).active());
                                     activate_boost_msg.write(ActivateBoostMsg::new(enemy_boost, players.enemy.color));
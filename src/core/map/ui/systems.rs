@@ -1,14 +1,21 @@
 use crate::core::assets::WorldAssets;
 use crate::core::audio::PlayAudioMsg;
-use crate::core::boosts::{ActivateBoostMsg, Boost};
-use crate::core::constants::{MAX_BOOSTS, MAX_QUEUE_LENGTH};
+use crate::core::balance::balance;
+use crate::core::boosts::{ActivateBoostMsg, Boost, BoostTargeting};
+use crate::core::camera::MainCamera;
+use crate::core::constants::{RADIUS, TARGET_COLOR};
+use crate::core::map::map::{Lane, Map};
 use crate::core::map::systems::MapCmp;
+use crate::core::mechanics::heroes::UseAbilityMsg;
 use crate::core::mechanics::queue::QueueUnitMsg;
+use crate::core::mechanics::tech::UnlockUnitMsg;
 use crate::core::menu::utils::add_text;
-use crate::core::player::{Players, Side, Strategy};
+use crate::core::player::{LaneSelection, Players, Side, Strategy};
+use crate::core::replay::{PlayerInput, Replay};
 use crate::core::settings::{PlayerColor, Settings};
+use crate::core::simulation::Step;
 use crate::core::states::GameState;
-use crate::core::units::units::{Action, Unit, UnitName};
+use crate::core::units::units::{population, Unit, UnitName};
 use crate::core::utils::cursor;
 use crate::utils::NameFromEnum;
 use bevy::picking::hover::PickingInteraction;
@@ -30,8 +37,8 @@ pub struct StrategyAdvanceBannerCmp;
 #[derive(Component)]
 pub struct TextAdvanceBannerCmp;
 
-#[derive(Component)]
-pub struct DirectionCmp;
+#[derive(Component, Deref)]
+pub struct DirectionCmp(pub Lane);
 
 #[derive(Component)]
 pub struct BoostBoxCmp {
@@ -108,13 +115,54 @@ pub struct UnitInfoPanelCmp;
 #[derive(Component, Deref)]
 pub struct UnitInfoCmp(pub UnitName);
 
+#[derive(Component)]
+pub struct TechTreePanelCmp;
+
+#[derive(Component, Deref)]
+pub struct TechCardCmp(pub UnitName);
+
+#[derive(Component, Deref)]
+pub struct TechLabelCmp(pub UnitName);
+
 #[derive(Component)]
 pub struct SpeedCmp;
 
+#[derive(Component)]
+pub struct PopulationCmp;
+
+#[derive(Component)]
+pub struct GoldCmp;
+
+#[derive(Component)]
+pub struct HeroLabelCmp;
+
+#[derive(Component, Deref)]
+pub struct AbilityButtonCmp(pub usize);
+
+#[derive(Component, Deref)]
+pub struct AbilityCooldownCmp(pub usize);
+
+#[derive(Component, Deref, DerefMut)]
+pub struct NoticeCmp(pub Timer);
+
+#[derive(Message)]
+pub struct NoticeMsg {
+    pub text: String,
+}
+
+impl NoticeMsg {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+        }
+    }
+}
+
 pub fn draw_ui(
     mut commands: Commands,
     players: Res<Players>,
     settings: Res<Settings>,
+    map: Res<Map>,
     window: Single<&Window>,
     assets: Res<WorldAssets>,
 ) {
@@ -235,7 +283,8 @@ pub fn draw_ui(
         .with_children(|parent| {
             for side in Side::iter() {
                 let player = players.get_by_side(side);
-                for i in 0..MAX_BOOSTS {
+                let max_boosts = balance().max_boosts;
+                for i in 0..max_boosts {
                     parent
                         .spawn((
                             Node {
@@ -249,7 +298,7 @@ pub fn draw_ui(
                             ImageNode::new(assets.image("selected boost")),
                             GlobalZIndex(1),
                             Visibility::Hidden,
-                            BoostBoxCmp::new(if player.side == Side::Left { i } else { MAX_BOOSTS - 1 - i }, player.color),
+                            BoostBoxCmp::new(if player.side == Side::Left { i } else { max_boosts - 1 - i }, player.color),
                             children![
                                 (
                                     Node {
@@ -324,8 +373,11 @@ pub fn draw_ui(
                         )
                         .observe(
                             |event: On<Pointer<Click>>,
+                             mut commands: Commands,
                              box_q: Query<&BoostBoxCmp>,
                              mut players: ResMut<Players>,
+                             mut replay: Option<ResMut<Replay>>,
+                             step: Res<Step>,
                              game_state: Res<State<GameState>>,
                              mut play_audio_msg: MessageWriter<PlayAudioMsg>,
                              mut activate_boost_msg: MessageWriter<ActivateBoostMsg>| {
@@ -334,12 +386,25 @@ pub fn draw_ui(
                                         let color = players.me.color;
                                             if color == bbox.color {
                                                 if let Some(boost) = players.me.boosts.get_mut(bbox.n) {
-                                                if !boost.active {
+                                                // Targeted boosts are activated once their location is chosen
+                                                if !boost.active && boost.name.radius().is_some() {
+                                                    commands.insert_resource(BoostTargeting(boost.name));
+                                                    play_audio_msg.write(PlayAudioMsg::new("button"));
+                                                    return;
+                                                }
+
+                                                let input = if !boost.active {
                                                     boost.active = true;
                                                     activate_boost_msg.write(ActivateBoostMsg::new(boost.name, color));
+                                                    PlayerInput::ActivateBoost(color, boost.name, None)
                                                 } else {
                                                     // Finish the boost early
                                                     boost.timer.finish();
+                                                    PlayerInput::FinishBoost(boost.name)
+                                                };
+
+                                                if let Some(replay) = &mut replay {
+                                                    replay.record(*step, input);
                                                 }
                                             }
                                         }
@@ -353,41 +418,65 @@ pub fn draw_ui(
             }
         });
 
-    // Draw direction
+    // Draw direction: one arrow per lane, faded when the lane is disabled
     commands
         .spawn((
             Node {
                 top: percent(5.),
                 left: percent(2.),
-                width: percent(9.),
-                height: percent(8.),
-                ..default()
-            },
-            ImageNode {
-                image: assets.image(players.me.direction.image()),
-                flip_x: players.me.side == Side::Right,
-                flip_y: players.me.direction.flip_y(),
+                width: percent(5.),
+                height: percent(3. * map.n_lanes() as f32),
+                flex_direction: FlexDirection::Column,
                 ..default()
             },
-            DirectionCmp,
             UiCmp,
             MapCmp,
         ))
-        .observe(cursor::<Over>(SystemCursorIcon::Pointer))
-        .observe(cursor::<Out>(SystemCursorIcon::Default))
-        .observe(
-            |event: On<Pointer<Click>>,
-             mut players: ResMut<Players>,
-             mut play_audio_msg: MessageWriter<PlayAudioMsg>| {
-                if event.button == PointerButton::Primary {
-                    players.me.direction = players.me.direction.next();
-                    play_audio_msg.write(PlayAudioMsg::new("click"));
-                } else if event.button == PointerButton::Secondary {
-                    players.me.direction = players.me.direction.previous();
-                    play_audio_msg.write(PlayAudioMsg::new("click"));
-                };
-            },
-        );
+        .with_children(|parent| {
+            for lane in map.iter_lanes() {
+                parent
+                    .spawn((
+                        Node {
+                            width: percent(100.),
+                            height: percent(100. / map.n_lanes() as f32),
+                            margin: UiRect::vertical(percent(2.)),
+                            ..default()
+                        },
+                        ImageNode {
+                            image: assets.image("mid arrow"),
+                            color: Color::WHITE.with_alpha(direction_alpha(&players, lane)),
+                            flip_x: players.me.side == Side::Right,
+                            ..default()
+                        },
+                        DirectionCmp(lane),
+                    ))
+                    .observe(cursor::<Over>(SystemCursorIcon::Pointer))
+                    .observe(cursor::<Out>(SystemCursorIcon::Default))
+                    .observe(
+                        |event: On<Pointer<Click>>,
+                         direction_q: Query<&DirectionCmp>,
+                         mut players: ResMut<Players>,
+                         map: Res<Map>,
+                         mut play_audio_msg: MessageWriter<PlayAudioMsg>| {
+                            let lane = **direction_q.get(event.entity).unwrap();
+                            let direction = &mut players.me.direction;
+
+                            if event.button == PointerButton::Primary {
+                                direction.toggle(lane, map.n_lanes());
+                                play_audio_msg.write(PlayAudioMsg::new("click"));
+                            } else if event.button == PointerButton::Secondary {
+                                // Select only this lane, or any lane if it already was
+                                *direction = if *direction == LaneSelection::only(&[lane]) {
+                                    LaneSelection::any()
+                                } else {
+                                    LaneSelection::only(&[lane])
+                                };
+                                play_audio_msg.write(PlayAudioMsg::new("click"));
+                            };
+                        },
+                    );
+            }
+        });
 
     // Draw shop
     let texture = assets.texture("small ribbons");
@@ -756,7 +845,7 @@ pub fn draw_ui(
                 ),
             ));
 
-            for i in 0..MAX_QUEUE_LENGTH {
+            for i in 0..balance().max_queue {
                 parent
                     .spawn((
                         Node {
@@ -812,11 +901,20 @@ pub fn draw_ui(
                             .observe(
                                 |event: On<Pointer<Click>>,
                                  btn_q: Query<&QueueButtonCmp>,
-                                 mut players: ResMut<Players>| {
+                                 mut players: ResMut<Players>,
+                                 mut replay: Option<ResMut<Replay>>,
+                                 step: Res<Step>| {
                                     // Remove unit from queue if clicked
                                     if event.button == PointerButton::Primary {
                                         if let Ok(button) = btn_q.get(event.entity) {
-                                            players.me.queue.remove(**button);
+                                            players.me.dequeue(**button);
+
+                                            if let Some(replay) = &mut replay {
+                                                replay.record(
+                                                    *step,
+                                                    PlayerInput::RemoveQueuedUnit(**button),
+                                                );
+                                            }
                                         }
                                     }
                                 },
@@ -1003,17 +1101,7 @@ pub fn draw_ui(
                                                 } else {
                                                     "Healing speed"
                                                 },
-                                                format!(
-                                                    "{:.1}",
-                                                    10. / unit.frames(
-                                                        if unit.physical_damage() > 0. {
-                                                            Action::Attack(Entity::PLACEHOLDER)
-                                                        } else {
-                                                            Action::Heal(Entity::PLACEHOLDER)
-                                                        }
-                                                    )
-                                                        as f32
-                                                ),
+                                                format!("{:.2}", unit.attack_speed()),
                                             ),
                                             ("Armor", unit.armor().to_string()),
                                             ("Magic resist", unit.magic_resist().to_string()),
@@ -1077,6 +1165,99 @@ pub fn draw_ui(
                 });
         });
 
+    // Spawn tech tree banner
+    commands
+        .spawn((
+            Node {
+                display: Display::None,
+                position_type: PositionType::Absolute,
+                width: percent(50.),
+                height: percent(66.),
+                left: percent(25.),
+                top: percent(15.),
+                flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
+                align_content: AlignContent::Center,
+                justify_content: JustifyContent::Center,
+                padding: UiRect {
+                    top: percent(3.),
+                    left: percent(6.),
+                    right: percent(6.),
+                    bottom: percent(8.),
+                },
+                ..default()
+            },
+            ImageNode::new(assets.image("banner")),
+            GlobalZIndex(10),
+            TechTreePanelCmp,
+            UiCmp,
+            MapCmp,
+        ))
+        .with_children(|parent| {
+            for unit in UnitName::iter().filter(|u| u.unlock().is_some()) {
+                parent
+                    .spawn((
+                        Node {
+                            width: percent(30.),
+                            height: percent(32.),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            margin: UiRect::all(percent(0.5)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::NONE),
+                        TechCardCmp(unit),
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                height: percent(50.),
+                                aspect_ratio: Some(1.0),
+                                ..default()
+                            },
+                            ImageNode::new(assets.image(format!(
+                                "{}-{}",
+                                players.me.color.to_name(),
+                                unit.to_name()
+                            ))),
+                            Pickable::IGNORE,
+                        ));
+
+                        parent.spawn((
+                            TextColor(Color::BLACK),
+                            add_text(unit.to_title(), "bold", 9., &assets, &window),
+                            Pickable::IGNORE,
+                        ));
+
+                        parent.spawn((
+                            TextColor(Color::BLACK),
+                            TextLayout::new_with_justify(Justify::Center),
+                            add_text("", "medium", 6., &assets, &window),
+                            Pickable::IGNORE,
+                            TechLabelCmp(unit),
+                        ));
+                    })
+                    .observe(cursor::<Over>(SystemCursorIcon::Pointer))
+                    .observe(cursor::<Out>(SystemCursorIcon::Default))
+                    .observe(
+                        move |event: On<Pointer<Click>>,
+                              mut players: ResMut<Players>,
+                              mut unlock_unit_msg: MessageWriter<UnlockUnitMsg>,
+                              mut play_audio_msg: MessageWriter<PlayAudioMsg>| {
+                            if event.button == PointerButton::Primary {
+                                if players.me.can_queue(unit) {
+                                    players.me.tech.selected = Some(unit);
+                                } else {
+                                    unlock_unit_msg.write(UnlockUnitMsg::new(players.me.id, unit));
+                                }
+
+                                play_audio_msg.write(PlayAudioMsg::new("button"));
+                            }
+                        },
+                    );
+            }
+        });
+
     // Draw speed indicator
     commands.spawn((
         Node {
@@ -1091,12 +1272,150 @@ pub fn draw_ui(
         UiCmp,
         MapCmp,
     ));
+
+    // Draw population indicator
+    commands.spawn((
+        Node {
+            bottom: Val::Px(25.),
+            left: Val::Px(10.),
+            position_type: PositionType::Absolute,
+            ..default()
+        },
+        add_text(format!("0/{} population", balance().population), "bold", 12., &assets, &window),
+        Pickable::IGNORE,
+        PopulationCmp,
+        UiCmp,
+        MapCmp,
+    ));
+
+    // Draw gold indicator
+    if settings.economy {
+        commands.spawn((
+            Node {
+                bottom: Val::Px(45.),
+                left: Val::Px(10.),
+                position_type: PositionType::Absolute,
+                ..default()
+            },
+            add_text(
+                format!("{:.0} gold", players.me.gold().floor()),
+                "bold",
+                12.,
+                &assets,
+                &window,
+            ),
+            Pickable::IGNORE,
+            GoldCmp,
+            UiCmp,
+            MapCmp,
+        ));
+    }
+    // Draw hero panel
+    if let Some(hero) = &players.me.hero {
+        commands
+            .spawn((
+                Node {
+                    bottom: Val::Px(10.),
+                    right: Val::Px(10.),
+                    height: Val::Px(60.),
+                    position_type: PositionType::Absolute,
+                    flex_direction: FlexDirection::Row,
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.),
+                    ..default()
+                },
+                UiCmp,
+                MapCmp,
+            ))
+            .with_children(|parent| {
+                parent
+                    .spawn((
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        Pickable::IGNORE,
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            Node {
+                                width: Val::Px(40.),
+                                height: Val::Px(40.),
+                                ..default()
+                            },
+                            ImageNode::new(assets.image(format!(
+                                "{}-{}",
+                                players.me.color.to_name(),
+                                hero.name.unit().to_name()
+                            ))),
+                            Pickable::IGNORE,
+                        ));
+
+                        parent.spawn((
+                            add_text(hero.name.to_title(), "bold", 10., &assets, &window),
+                            Pickable::IGNORE,
+                            HeroLabelCmp,
+                        ));
+                    });
+
+                for (i, ability) in hero.name.abilities().iter().enumerate() {
+                    parent
+                        .spawn((
+                            Node {
+                                width: Val::Px(50.),
+                                height: Val::Px(50.),
+                                ..default()
+                            },
+                            ImageNode::new(assets.image(ability.image())),
+                            AbilityButtonCmp(i),
+                            children![(
+                                Node {
+                                    bottom: percent(2.),
+                                    right: percent(9.),
+                                    position_type: PositionType::Absolute,
+                                    ..default()
+                                },
+                                add_text("", "bold", 12., &assets, &window),
+                                Pickable::IGNORE,
+                                AbilityCooldownCmp(i),
+                            )],
+                        ))
+                        .observe(cursor::<Over>(SystemCursorIcon::Pointer))
+                        .observe(cursor::<Out>(SystemCursorIcon::Default))
+                        .observe(
+                            move |event: On<Pointer<Click>>,
+                                  players: Res<Players>,
+                                  game_state: Res<State<GameState>>,
+                                  mut play_audio_msg: MessageWriter<PlayAudioMsg>,
+                                  mut use_ability_msg: MessageWriter<UseAbilityMsg>| {
+                                if event.button == PointerButton::Primary
+                                    && *game_state.get() == GameState::Playing
+                                {
+                                    use_ability_msg.write(UseAbilityMsg::new(players.me.color, i));
+                                } else {
+                                    play_audio_msg.write(PlayAudioMsg::new("error"));
+                                }
+                            },
+                        );
+                }
+            });
+    }
+}
+
+/// Transparency of a lane's arrow in the direction widget
+fn direction_alpha(players: &Players, lane: Lane) -> f32 {
+    if players.me.direction.contains(lane) {
+        1.
+    } else {
+        0.3
+    }
 }
 
 /// Updates the advance banner, shop, direction and strategy
 pub fn update_ui(
     unit_q: Query<(&Transform, &Unit)>,
-    mut direction_q: Query<&mut ImageNode, With<DirectionCmp>>,
+    mut direction_q: Query<(&mut ImageNode, &DirectionCmp)>,
     mut advance_q: Query<(Entity, &mut Node, &AdvanceBannerCmp)>,
     mut image_q: Query<
         &mut ImageNode,
@@ -1173,9 +1492,7 @@ pub fn update_ui(
     // Update the shop
     for (btn_e, mut node, mut image, mut btn) in &mut btn_q {
         if !btn.is_basic {
-            node.display = if let Some(unit) =
-                UnitName::iter().find(|u| !u.is_basic_unit() && players.me.can_queue(*u))
-            {
+            node.display = if let Some(unit) = players.me.tech.selected {
                 image.image =
                     assets.image(format!("{}-{}", players.me.color.to_name(), unit.to_name()));
                 btn.unit = unit;
@@ -1193,9 +1510,8 @@ pub fn update_ui(
     }
 
     // Update the direction
-    for mut image in &mut direction_q {
-        image.image = assets.image(players.me.direction.image());
-        image.flip_y = players.me.direction.flip_y()
+    for (mut image, lane) in &mut direction_q {
+        image.color.set_alpha(direction_alpha(&players, **lane));
     }
 
     // Update the strategies
@@ -1344,11 +1660,173 @@ pub fn update_ui2(
     }
 }
 
+pub fn update_population_ui(
+    mut text: Single<&mut Text, With<PopulationCmp>>,
+    unit_q: Query<&Unit>,
+    players: Res<Players>,
+) {
+    text.0 = format!(
+        "{}/{} population",
+        population(unit_q.iter(), players.me.color),
+        balance().population
+    );
+}
+
+pub fn update_hero_ui(
+    mut label_q: Query<&mut Text, With<HeroLabelCmp>>,
+    mut button_q: Query<(&mut ImageNode, &AbilityButtonCmp)>,
+    mut cooldown_q: Query<(&mut Text, &AbilityCooldownCmp), Without<HeroLabelCmp>>,
+    players: Res<Players>,
+) {
+    let Some(hero) = &players.me.hero else {
+        return;
+    };
+
+    for mut text in &mut label_q {
+        text.0 = if hero.alive {
+            format!("Lvl {}", hero.level)
+        } else {
+            format!("{:.0}s", hero.respawn.ceil())
+        };
+    }
+
+    // Dim the abilities that can't be used yet
+    for (mut image, button) in &mut button_q {
+        image.color = if hero.is_ready(**button) {
+            Color::WHITE
+        } else {
+            Color::srgba(1., 1., 1., 0.4)
+        };
+    }
+
+    for (mut text, cooldown) in &mut cooldown_q {
+        let remaining = hero.cooldowns[**cooldown];
+        text.0 = if remaining > 0. {
+            format!("{:.0}s", remaining.ceil())
+        } else {
+            "".to_owned()
+        };
+    }
+}
+
+pub fn update_gold_ui(mut text: Single<&mut Text, With<GoldCmp>>, players: Res<Players>) {
+    text.0 = format!("{:.0} gold", players.me.gold().floor());
+}
+
 pub fn setup_unit_info(info: Single<&mut Node, With<UnitInfoPanelCmp>>) {
     let mut node = info.into_inner();
     node.display = Display::Flex;
 }
 
+pub fn setup_tech_tree(tech: Single<&mut Node, With<TechTreePanelCmp>>) {
+    let mut node = tech.into_inner();
+    node.display = Display::Flex;
+}
+
+/// Updates the state and requirements of every unit in the tech tree
+pub fn update_tech_ui(
+    mut card_q: Query<(&mut BackgroundColor, &TechCardCmp)>,
+    mut label_q: Query<(&mut Text, &TechLabelCmp)>,
+    players: Res<Players>,
+    settings: Res<Settings>,
+) {
+    let me = &players.me;
+
+    for (mut bg, card) in &mut card_q {
+        bg.0 = if me.tech.selected == Some(**card) {
+            Color::srgba(1., 1., 1., 0.4)
+        } else if me.can_unlock(**card, settings.economy) {
+            Color::srgba(0.3, 0.8, 0.3, 0.3)
+        } else {
+            Color::NONE
+        };
+    }
+
+    for (mut text, label) in &mut label_q {
+        let Some(unlock) = label.unlock() else {
+            continue;
+        };
+
+        text.0 = if me.can_queue(**label) {
+            "Unlocked".to_owned()
+        } else {
+            let mut lines = vec![];
+            if !unlock.requires.is_empty() {
+                lines.push(format!(
+                    "Requires: {}",
+                    unlock.requires.iter().map(|u| u.to_title()).join(", ")
+                ));
+            }
+            if unlock.secs > 0. {
+                lines.push(format!("Time: {:.0}/{:.0}s", me.elapsed.min(unlock.secs), unlock.secs));
+            }
+            if unlock.kills > 0 {
+                lines.push(format!("Kills: {}/{}", me.kills.min(unlock.kills), unlock.kills));
+            }
+            if settings.economy && unlock.gold > 0. {
+                lines.push(format!("Gold: {:.0}", unlock.gold));
+            }
+
+            lines.join("\n")
+        };
+    }
+}
+
+pub fn hide_tech_tree(tech: Single<&mut Node, With<TechTreePanelCmp>>) {
+    let mut node = tech.into_inner();
+    node.display = Display::None;
+}
+
+/// Draw the area of the boost being targeted and cast it on the clicked location
+pub fn target_boost(
+    mut commands: Commands,
+    ui_q: Query<&PickingInteraction, With<Node>>,
+    camera: Single<(&Camera, &GlobalTransform), With<MainCamera>>,
+    window: Single<&Window>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    targeting: Res<BoostTargeting>,
+    mut players: ResMut<Players>,
+    mut replay: Option<ResMut<Replay>>,
+    step: Res<Step>,
+    mut gizmos: Gizmos,
+    mut activate_boost_msg: MessageWriter<ActivateBoostMsg>,
+) {
+    let (camera, global_t) = *camera;
+    let color = players.me.color;
+
+    // Cancel the targeting with the right mouse button
+    let boost = players.me.boosts.iter_mut().find(|b| b.name == **targeting && !b.active);
+    let Some(boost) = boost.filter(|_| !mouse.just_released(MouseButton::Right)) else {
+        commands.remove_resource::<BoostTargeting>();
+        return;
+    };
+
+    let Some(position) =
+        window.cursor_position().and_then(|p| camera.viewport_to_world_2d(global_t, p).ok())
+    else {
+        return;
+    };
+
+    let radius = boost.name.radius().unwrap_or_default() * RADIUS;
+    gizmos.circle_2d(Isometry2d::from_translation(position), radius, TARGET_COLOR);
+
+    if mouse.just_released(MouseButton::Left)
+        && !ui_q.iter().any(|i| *i != PickingInteraction::None)
+    {
+        boost.active = true;
+        activate_boost_msg.write(ActivateBoostMsg {
+            target: Some(position),
+            ..ActivateBoostMsg::new(boost.name, color)
+        });
+
+        if let Some(replay) = &mut replay {
+            replay.record(*step, PlayerInput::ActivateBoost(color, boost.name, Some(position)));
+        }
+
+        commands.remove_resource::<BoostTargeting>();
+    }
+}
+
 pub fn click_on_map(
     ui_q: Query<&PickingInteraction, Or<(With<Node>, With<Unit>)>>,
     mouse: Res<ButtonInput<MouseButton>>,
@@ -1365,3 +1843,43 @@ pub fn hide_unit_info(info: Single<&mut Node, With<UnitInfoPanelCmp>>) {
     let mut node = info.into_inner();
     node.display = Display::None;
 }
+
+pub fn notice_message(
+    mut commands: Commands,
+    notice_q: Query<Entity, With<NoticeCmp>>,
+    mut notice_msg: MessageReader<NoticeMsg>,
+    window: Single<&Window>,
+    assets: Res<WorldAssets>,
+) {
+    for msg in notice_msg.read() {
+        // Only show the most recent notice
+        notice_q.iter().for_each(|e| commands.entity(e).despawn());
+
+        commands.spawn((
+            Node {
+                bottom: percent(20.),
+                width: percent(100.),
+                position_type: PositionType::Absolute,
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            Pickable::IGNORE,
+            NoticeCmp(Timer::from_seconds(3., TimerMode::Once)),
+            UiCmp,
+            MapCmp,
+            children![(add_text(&msg.text, "bold", 12., &assets, &window), Pickable::IGNORE)],
+        ));
+    }
+}
+
+pub fn update_notices(
+    mut commands: Commands,
+    mut notice_q: Query<(Entity, &mut NoticeCmp)>,
+    time: Res<Time<Real>>,
+) {
+    for (notice_e, mut timer) in &mut notice_q {
+        if timer.tick(time.delta()).just_finished() {
+            commands.entity(notice_e).despawn();
+        }
+    }
+}
@@ -1,14 +1,20 @@
+use crate::core::balance::balance;
 use crate::core::boosts::Boost;
 use crate::core::constants::RADIUS;
 use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::spatial::SpatialGrid;
 use crate::core::mechanics::spawn::{DespawnMsg, SpawnArrowMsg};
+use crate::core::mechanics::status::{Stats, Status, StatusEffect, StatusEffects};
 use crate::core::player::{Player, Players, Strategy};
-use crate::core::settings::PlayerColor;
+use crate::core::rng::GameRng;
+use crate::core::settings::{PlayerColor, Settings};
 use crate::core::states::GameState;
-use crate::core::units::buildings::Building;
+use crate::core::units::buildings::{Building, BuildingAttack};
 use crate::core::units::units::{Action, Unit, UnitName};
+use crate::utils::scale_duration;
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
-use bevy_tweening::{CycleCompletedEvent, TweenAnim};
+use rand::RngExt;
 use serde::{Deserialize, Serialize};
 use std::f32::consts::FRAC_PI_4;
 use std::time::Duration;
@@ -58,18 +64,38 @@ impl Projectile {
             Projectile::Magic => ProjectileMode::Straight,
         }
     }
+
+    /// Number of units the projectile can hit before it stops
+    pub fn pierce(&self) -> usize {
+        match self {
+            Projectile::Harpoon => 3,
+            _ => 1,
+        }
+    }
 }
 
 #[derive(Component, Clone, Debug, Serialize, Deserialize)]
 pub struct Arrow {
     pub color: PlayerColor,
+    /// Unit that shot the projectile (`None` for buildings)
+    pub unit: Option<UnitName>,
+    /// Entity of the unit that shot the projectile
+    pub shooter: Option<Entity>,
     pub projectile: Projectile,
     pub damage: f32,
+    /// Damage before mitigation dealt to the enemies around the target
+    pub splash: Option<Damage>,
     pub start: Vec2,
     pub destination: Vec2,
     pub total_distance: f32,
     pub traveled: f32,
     pub despawn_timer: Timer,
+    /// Entities already hit by the projectile
+    pub hits: Vec<Entity>,
+    /// The target dodged the projectile, which can't hit anything anymore
+    pub missed: bool,
+    /// The (parabolic) projectile reached its destination and lies on the ground
+    pub landed: bool,
 }
 
 impl Arrow {
@@ -78,6 +104,7 @@ impl Arrow {
 
     pub fn new(
         color: PlayerColor,
+        unit: Option<UnitName>,
         projectile: Projectile,
         damage: f32,
         start: Vec2,
@@ -85,13 +112,19 @@ impl Arrow {
     ) -> Self {
         Arrow {
             color,
+            unit,
+            shooter: None,
             projectile,
             damage,
+            splash: None,
             start,
             destination,
             total_distance: start.distance(destination),
             traveled: 0.,
             despawn_timer: Timer::new(Duration::from_secs(Self::ON_GROUND_SECS), TimerMode::Once),
+            hits: vec![],
+            missed: false,
+            landed: false,
         }
     }
 }
@@ -100,197 +133,612 @@ impl Arrow {
 pub struct ApplyDamageMsg {
     pub entity: Entity,
     pub damage: f32,
+    /// Damage before the defenses of the target, used instead of `damage` if set
+    pub attack: Option<Damage>,
+    /// Unit that dealt the damage
+    pub source: Option<UnitName>,
+    /// Entity of the unit that dealt the damage, which gains veterancy from it
+    pub attacker: Option<Entity>,
 }
 
 impl ApplyDamageMsg {
-    pub fn new(entity: Entity, damage: f32) -> Self {
+    pub fn new(
+        entity: Entity,
+        damage: f32,
+        source: Option<UnitName>,
+        attacker: Option<Entity>,
+    ) -> Self {
         ApplyDamageMsg {
             entity,
             damage,
+            attack: None,
+            source,
+            attacker,
         }
     }
+
+    /// Damage that is mitigated by the defenses of the target when applied
+    pub fn attack(
+        entity: Entity,
+        attack: Damage,
+        source: Option<UnitName>,
+        attacker: Option<Entity>,
+    ) -> Self {
+        ApplyDamageMsg {
+            attack: Some(attack),
+            ..Self::new(entity, 0., source, attacker)
+        }
+    }
+}
+
+/// Damage that was taken by a unit or building (`target` is `None`)
+#[derive(Message)]
+pub struct DamageDealtMsg {
+    pub source: Option<UnitName>,
+    pub target: Option<UnitName>,
+    pub damage: f32,
+}
+
+/// Damage left after the defense (armor or magic resist) of the target
+fn mitigate(damage: f32, defense: f32) -> f32 {
+    damage * (10. / (10. + defense))
+}
+
+/// Damage of an attack before the defenses of its target
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Damage {
+    pub physical: f32,
+    pub magic: f32,
+    /// Armor ignored by the attack
+    pub armor_pen: f32,
+    /// Magic resist ignored by the attack
+    pub magic_pen: f32,
+    /// Multiplier of the damage against buildings
+    pub siege: f32,
+    /// Ranged attacks are blocked by the BlockRange boost
+    pub ranged: bool,
 }
 
-fn calculate_damage(
-    unit: &Unit,
-    armor: f32,
-    magic_resist: f32,
-    is_building: bool,
-    attacker: &Player,
-    defender: &Player,
-) -> f32 {
-    let mut attack_damage = unit.name.physical_damage();
-    let mut magic_damage = unit.name.magic_damage();
-
-    if attacker.has_boost(Boost::MagicSwap) {
-        magic_damage += attack_damage;
-        attack_damage = 0.;
+impl Damage {
+    pub fn new(physical: f32, magic: f32) -> Self {
+        Self {
+            physical,
+            magic,
+            armor_pen: 0.,
+            magic_pen: 0.,
+            siege: 1.,
+            ranged: false,
+        }
+    }
+
+    /// Damage of an attack of `unit`, including the boosts of its player
+    pub fn from_unit(unit: &Unit, attacker: &Player) -> Self {
+        let mut physical = unit.name.physical_damage();
+        let mut magic = unit.name.magic_damage();
+
+        if attacker.has_boost(Boost::MagicSwap) {
+            magic += physical;
+            physical = 0.;
+        }
+
+        if attacker.has_boost(Boost::MagicPower) {
+            magic *= Boost::MagicPower.value();
+        }
+
+        let mut multiplier = match unit.name {
+            UnitName::Warrior if attacker.has_boost(Boost::Warrior) => Boost::Warrior.value(),
+            UnitName::Lancer if attacker.has_boost(Boost::Lancer) => Boost::Lancer.value(),
+            UnitName::Archer if attacker.has_boost(Boost::Arrows) => Boost::Arrows.value(),
+            _ => 1.,
+        };
+
+        multiplier *= unit.multiplier(attacker);
+
+        if unit.on_building.is_some() && attacker.has_boost(Boost::BuildingsDefense) {
+            multiplier *= Boost::BuildingsDefense.value();
+        }
+
+        Self {
+            physical: physical * multiplier,
+            magic: magic * multiplier,
+            armor_pen: unit.name.armor_pen()
+                + if attacker.has_boost(Boost::Penetration) {
+                    Boost::Penetration.value()
+                } else {
+                    0.
+                },
+            magic_pen: unit.name.magic_pen(),
+            siege: if attacker.has_boost(Boost::Siege) {
+                Boost::Siege.value()
+            } else {
+                1.
+            },
+            ranged: !unit.name.is_melee(),
+        }
+    }
+
+    /// Damage of an attack of a building
+    pub fn from_building(attack: &BuildingAttack) -> Self {
+        Self {
+            ranged: true,
+            ..Self::new(attack.physical_damage, attack.magic_damage)
+        }
     }
 
-    if attacker.has_boost(Boost::MagicPower) {
-        magic_damage *= 2.;
+    /// Multiply the physical and magic damage by `factor`
+    pub fn scale(self, factor: f32) -> Self {
+        Self {
+            physical: self.physical * factor,
+            magic: self.magic * factor,
+            ..self
+        }
     }
 
-    let effective_armor = (armor
-        - unit.name.armor_pen()
-        - if attacker.has_boost(Boost::Penetration) {
-            5.
+    /// Damage taken by a target of `defender` with `armor` and `magic_resist`
+    pub fn taken(
+        &self,
+        armor: f32,
+        magic_resist: f32,
+        is_building: bool,
+        defender: &Player,
+    ) -> f32 {
+        let effective_armor = (armor - self.armor_pen).max(0.);
+        let effective_mr = (magic_resist - self.magic_pen).max(0.);
+
+        let mut damage =
+            mitigate(self.physical, effective_armor) + mitigate(self.magic, effective_mr);
+
+        damage *= if defender.has_boost(Boost::ArmorGain) && !is_building {
+            Boost::ArmorGain.value()
         } else {
-            0.
-        })
-    .max(0.);
-    let effective_mr = (magic_resist - unit.name.magic_pen()).max(0.);
+            1.0
+        };
 
-    let mitigate = |dmg, def| dmg * (10. / (10. + def));
+        damage = damage.max(balance().min_damage);
 
-    let physical_taken = mitigate(attack_damage, effective_armor);
-    let magical_taken = mitigate(magic_damage, effective_mr);
+        if is_building {
+            damage *= self.siege;
 
-    let mut damage = physical_taken + magical_taken;
+            if defender.has_boost(Boost::BuildingsBlock) {
+                damage = 0.;
+            }
+        } else if self.ranged && defender.has_boost(Boost::BlockRange) {
+            damage = 0.;
+        }
 
-    damage *= match unit.name {
-        UnitName::Warrior if attacker.has_boost(Boost::Warrior) => 1.5,
-        UnitName::Lancer if attacker.has_boost(Boost::Lancer) => 1.6,
-        UnitName::Archer if attacker.has_boost(Boost::Arrows) => 1.3,
-        _ => 1.,
-    };
+        damage
+    }
+}
 
-    damage *= if defender.has_boost(Boost::ArmorGain) && !is_building {
-        0.7
+/// Position to aim a projectile from `start` at a unit in `position`, and
+/// whether the unit dodges it
+///
+/// Projectiles lead their target, unless it's fast enough to dodge them. Dodged
+/// projectiles fly to where the target was and don't hit anything.
+fn lead_target(start: Vec2, position: Vec2, target: &Unit, rng: &mut GameRng) -> (Vec2, bool) {
+    let speed = target.velocity.length();
+    let dodge = balance()
+        .miss_speed
+        .is_some_and(|miss| speed > miss && rng.random_bool((1. - miss / speed) as f64));
+
+    if dodge {
+        (position, true)
     } else {
-        1.0
+        (position + target.velocity * start.distance(position) / Arrow::SPEED, false)
+    }
+}
+
+/// Damage the enemies around `position` with the splash of `source`
+///
+/// The damage decreases linearly with the distance, losing the splash's
+/// falloff at its edge, and is mitigated by the defenses of every enemy.
+/// The main target (`target`) is skipped.
+pub fn apply_splash(
+    source: UnitName,
+    attacker: Option<Entity>,
+    color: PlayerColor,
+    position: Vec3,
+    target: Entity,
+    damage: Damage,
+    grid: &SpatialGrid,
+    apply_damage_msg: &mut MessageWriter<ApplyDamageMsg>,
+) {
+    let Some(splash) = source.splash() else {
+        return;
     };
 
-    if unit.on_building.is_some() && attacker.has_boost(Boost::BuildingsDefense) {
-        damage *= 2.0;
-    }
+    let radius = splash.radius * RADIUS;
+    for entry in grid.within(position, radius) {
+        if entry.entity == target
+            || entry.color() == color
+            || (entry.building().is_some() && !splash.buildings)
+            || (source.is_melee() && entry.unit().is_some_and(|u| u.name.is_flying()))
+        {
+            continue;
+        }
 
-    damage = damage.max(5.);
+        let falloff = splash.falloff * entry.distance(position) / radius;
+        apply_damage_msg.write(ApplyDamageMsg::attack(
+            entry.entity,
+            damage.scale(1. - falloff),
+            Some(source),
+            attacker,
+        ));
+    }
+}
 
-    if is_building {
-        if attacker.has_boost(Boost::Siege) {
-            damage *= 1.5;
+pub fn update_actions(
+    mut unit_q: Query<(Entity, &Transform, &mut Unit)>,
+    building_q: Query<&Building>,
+    players: Res<Players>,
+) {
+    // Collect positions and health for all units
+    let units: HashMap<Entity, (Vec3, Unit)> =
+        unit_q.iter().map(|(e, t, u)| (e, (t.translation, *u))).collect();
+
+    // Get the entities of the units under attack
+    let attacked: Vec<Entity> = unit_q
+        .iter()
+        .filter_map(|(_, _, u)| {
+            if let Action::Attack(e) = u.action {
+                Some(e)
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    for (unit_e, unit_t, mut unit) in &mut unit_q {
+        let player = players.get_by_color(unit.color);
+
+        // If the unit is being attacked and the strategy is Guard, enter guard mode
+        if unit.action != Action::Guard {
+            if unit.name.can_guard()
+                && player.strategy == Strategy::Guard
+                && attacked.contains(&unit_e)
+            {
+                unit.action = Action::Guard;
+            }
+        } else if !attacked.contains(&unit_e) || player.strategy != Strategy::Guard {
+            // Go back to default when no longer under attack
+            unit.action = Action::Idle;
         }
 
-        if defender.has_boost(Boost::BuildingsBlock) {
-            damage = 0.;
+        // Check that the action receiver still exists and is in range, else go back to idle
+        let in_range =
+            |pos: &Vec3| unit_t.translation.distance(*pos) <= unit.range(player) * RADIUS;
+        let action = match unit.action {
+            Action::Attack(e) => {
+                if building_q.get(e).is_ok() || units.get(&e).is_some_and(|(pos, _)| in_range(pos))
+                {
+                    unit.action
+                } else {
+                    Action::Idle // Target doesn't exist anymore or is out of range
+                }
+            },
+            Action::Heal(e) => units
+                .get(&e)
+                .filter(|(pos, target)| in_range(pos) && target.health < target.max_health(player))
+                .map(|_| unit.action)
+                .unwrap_or(Action::Idle),
+            _ => unit.action,
+        };
+
+        if action != unit.action {
+            unit.action = action;
         }
-    } else if !unit.name.is_melee() && defender.has_boost(Boost::BlockRange) {
-        damage = 0.;
     }
-
-    damage
 }
 
 pub fn resolve_attack(
-    mut commands: Commands,
-    entity_q: Query<(Entity, &Transform, Option<&Unit>), Or<(With<Unit>, With<Building>)>>,
-    mut unit_q: Query<(Entity, &Transform, &mut Sprite, &Unit)>,
+    mut unit_q: Query<(Entity, &Transform, &mut Unit, &mut StatusEffects)>,
+    building_q: Query<&Transform, With<Building>>,
+    grid: Res<SpatialGrid>,
+    settings: Res<Settings>,
     players: Res<Players>,
-    mut cycle_completed_msg: MessageReader<CycleCompletedEvent>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
     mut spawn_arrow_msg: MessageWriter<SpawnArrowMsg>,
     mut apply_damage_msg: MessageWriter<ApplyDamageMsg>,
+    mut effect_msg: MessageWriter<EffectMsg>,
 ) {
-    // Apply damage after the attacking animation finished
-    for msg in cycle_completed_msg.read() {
-        if let Ok((unit_e, unit_t, mut unit_s, unit)) = unit_q.get_mut(msg.anim_entity) {
-            let attacker = players.get_by_color(unit.color);
-            let defender = players.get_by_side(attacker.side.opposite());
-
-            match unit.action {
-                Action::Attack(e) | Action::Heal(e) => {
-                    if let Ok((target_e, target_t, target)) = entity_q.get(e) {
-                        let (armor, mr, is_building) = if let Some(target) = target {
-                            let mut armor = target.name.armor();
-                            let mut mr = target.name.magic_resist();
-
-                            if target.action == Action::Guard {
-                                armor *= 2.;
-                                mr *= 2.;
-                            }
-
-                            if defender.strategy == Strategy::Berserk
-                                && target.on_building.is_none()
-                            {
-                                armor /= 2.;
-                                mr /= 2.;
-                            }
-
-                            (armor, mr, false)
-                        } else {
-                            (0., 0., true) // Buildings have no armor nor magic resist
-                        };
-
-                        let damage = if unit.name == UnitName::Priest {
-                            unit.name.physical_damage()
-                                * if attacker.has_boost(Boost::Meditation) {
-                                    1.7
-                                } else {
-                                    1.0
-                                }
-                        } else {
-                            calculate_damage(unit, armor, mr, is_building, attacker, defender)
-                        };
-
-                        if let Some(projectile) = unit.name.projectile() {
-                            // These units don't apply damage but spawn projectiles at the end of the animation
-                            spawn_arrow_msg.write(SpawnArrowMsg {
-                                color: unit.color,
-                                projectile,
-                                damage,
-                                start: Vec2::new(
-                                    unit_t.translation.x
-                                        + 0.25
-                                            * RADIUS
-                                            * if target_t.translation.x < unit_t.translation.x {
-                                                -1.
-                                            } else {
-                                                1.
-                                            },
-                                    unit_t.translation.y + 0.25 * RADIUS,
-                                ),
-                                destination: target_t.translation.truncate(),
-                                entity: None,
-                            });
+    // Advance the attack cycles and collect the units that finish one
+    let mut attacks = vec![];
+    for (unit_e, _, mut unit, statuses) in &mut unit_q {
+        if !matches!(unit.action, Action::Attack(_) | Action::Heal(_)) {
+            if unit.attack_timer > 0. {
+                unit.attack_timer = 0.;
+            }
+            continue;
+        }
+
+        let stats = Stats::new(&unit, &statuses, players.get_by_color(unit.color));
+        unit.attack_timer += time.delta_secs() * settings.speed * stats.attack_speed;
+
+        // Attack as many times as the cycle fits in the step, for fast units at high speed
+        let duration = unit.name.attack_duration().as_secs_f32();
+        while duration > 0. && unit.attack_timer >= duration {
+            unit.attack_timer -= duration;
+            attacks.push(unit_e);
+        }
+    }
+
+    // Apply damage after the attack cycle finished
+    for unit_e in attacks {
+        let Ok((_, unit_t, unit, _)) = unit_q.get(unit_e) else {
+            continue;
+        };
+
+        let attacker = players.get_by_color(unit.color);
+        let defender = players.get_by_side(attacker.side.opposite());
+
+        let (Action::Attack(e) | Action::Heal(e)) = unit.action else {
+            continue;
+        };
+
+        let (target_t, target) = if let Ok((_, target_t, target, statuses)) = unit_q.get(e) {
+            (target_t, Some((target, statuses)))
+        } else if let Ok(target_t) = building_q.get(e) {
+            (target_t, None)
+        } else {
+            continue;
+        };
+
+        let (armor, mr, is_building) = if let Some((target, statuses)) = target {
+            let stats = Stats::new(target, statuses, defender);
+            (stats.armor, stats.magic_resist, false)
+        } else {
+            (0., 0., true) // Buildings have no armor nor magic resist
+        };
+
+        let damage = if unit.name == UnitName::Priest {
+            unit.name.physical_damage()
+                * unit.multiplier(attacker)
+                * if attacker.has_boost(Boost::Meditation) {
+                    Boost::Meditation.value()
+                } else {
+                    1.0
+                }
+        } else {
+            Damage::from_unit(unit, attacker).taken(armor, mr, is_building, defender)
+        };
+
+        if let Some(projectile) = unit.name.projectile() {
+            // These units don't apply damage but spawn projectiles at the end of the cycle
+            let start = Vec2::new(
+                unit_t.translation.x
+                    + 0.25
+                        * RADIUS
+                        * if target_t.translation.x < unit_t.translation.x {
+                            -1.
                         } else {
-                            apply_damage_msg.write(ApplyDamageMsg::new(target_e, damage));
-                        }
-                    }
-                },
-                Action::Guard if unit.name == UnitName::Turtle => {
-                    // Turtles stay in the shell during guard
-                    if let Some(atlas) = &mut unit_s.texture_atlas {
-                        atlas.index = 5;
-                        commands.entity(unit_e).remove::<TweenAnim>();
-                    }
-                },
-                _ => (),
+                            1.
+                        },
+                unit_t.translation.y + 0.25 * RADIUS,
+            );
+
+            let mut destination = target_t.translation.truncate();
+            let mut missed = false;
+            if let Some((target, _)) = target {
+                (destination, missed) = lead_target(start, destination, target, &mut rng);
+            }
+
+            if projectile.pierce() > 1 {
+                // Piercing projectiles keep flying through the target up to the unit's range
+                let distance = start.distance(destination).max(unit.range(attacker) * RADIUS);
+                destination = start + (destination - start).normalize_or_zero() * distance;
+            }
+
+            spawn_arrow_msg.write(SpawnArrowMsg {
+                color: unit.color,
+                unit: Some(unit.name),
+                shooter: Some(unit_e),
+                projectile,
+                damage,
+                splash: unit.name.splash().map(|_| Damage::from_unit(unit, attacker)),
+                start,
+                destination,
+                missed,
+                entity: None,
+            });
+        } else {
+            apply_damage_msg.write(ApplyDamageMsg::new(e, damage, Some(unit.name), Some(unit_e)));
+
+            // Heavy units cleave the enemies around their target
+            if damage > 0. && unit.name.splash().is_some() {
+                apply_splash(
+                    unit.name,
+                    Some(unit_e),
+                    unit.color,
+                    target_t.translation,
+                    e,
+                    Damage::from_unit(unit, attacker),
+                    &grid,
+                    &mut apply_damage_msg,
+                );
+                effect_msg.write(EffectMsg::impact(e));
             }
+
+            // Poisonous units add a stack of poison on every hit that deals damage
+            let source = unit.name;
+            if damage > 0. && source.poison().is_some() {
+                if let Ok((_, _, _, mut statuses)) = unit_q.get_mut(e) {
+                    statuses.add_poison(source);
+                }
+            }
+        }
+    }
+}
+
+pub fn resolve_building_attack(
+    mut building_q: Query<(&Transform, &mut Building)>,
+    unit_q: Query<(&Unit, &StatusEffects)>,
+    grid: Res<SpatialGrid>,
+    settings: Res<Settings>,
+    players: Res<Players>,
+    mut rng: ResMut<GameRng>,
+    time: Res<Time>,
+    mut spawn_arrow_msg: MessageWriter<SpawnArrowMsg>,
+) {
+    for (building_t, mut building) in &mut building_q {
+        let Some(attack) = building.attack() else {
+            continue;
+        };
+
+        // Keep the attack ready until an enemy comes in range
+        let duration = 1. / attack.attack_speed;
+        building.attack_timer =
+            (building.attack_timer + time.delta_secs() * settings.speed).min(duration);
+        if building.attack_timer < duration {
+            continue;
         }
+
+        let Some((target, target_unit)) = grid
+            .units_within(building_t.translation, attack.range * RADIUS)
+            .filter(|(_, u)| u.color != building.color)
+            .min_by(|(a, _), (b, _)| {
+                a.distance(building_t.translation).total_cmp(&b.distance(building_t.translation))
+            })
+        else {
+            continue;
+        };
+
+        let Ok((_, statuses)) = unit_q.get(target.entity) else {
+            continue;
+        };
+
+        let defender = players.get_by_color(target_unit.color);
+        let stats = Stats::new(target_unit, statuses, defender);
+        let damage =
+            Damage::from_building(&attack).taken(stats.armor, stats.magic_resist, false, defender);
+
+        // Shoot from the top of the building
+        let start =
+            building_t.translation.truncate() + Vec2::Y * building.name.world_size().y * 0.3;
+
+        let (destination, missed) =
+            lead_target(start, target.position.truncate(), target_unit, &mut rng);
+
+        building.attack_timer = 0.;
+        spawn_arrow_msg.write(SpawnArrowMsg {
+            color: building.color,
+            unit: None,
+            shooter: None,
+            projectile: attack.projectile,
+            damage,
+            splash: None,
+            start,
+            destination,
+            missed,
+            entity: None,
+        });
     }
 }
 
 pub fn apply_damage_message(
     mut commands: Commands,
-    mut unit_q: Query<(Entity, &mut Unit)>,
+    mut unit_q: Query<(Entity, &Transform, &mut Unit, &mut StatusEffects)>,
     mut building_q: Query<(Entity, &mut Building)>,
     mut apply_damage_msg: MessageReader<ApplyDamageMsg>,
+    mut damage_dealt_msg: MessageWriter<DamageDealtMsg>,
     mut despawn_msg: MessageWriter<DespawnMsg>,
     mut effect_msg: MessageWriter<EffectMsg>,
+    mut players: ResMut<Players>,
+    settings: Res<Settings>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
+    // Color, position and weight of the units killed during this step
+    let mut deaths = vec![];
+
+    // Attacker, damage (or healing) dealt and whether it killed its target
+    let mut credits = vec![];
+
     for msg in apply_damage_msg.read() {
-        if let Ok((unit_e, mut unit)) = unit_q.get_mut(msg.entity) {
-            unit.health = (unit.health - msg.damage).clamp(0., unit.name.health());
+        if let Ok((unit_e, unit_t, mut unit, mut statuses)) = unit_q.get_mut(msg.entity) {
+            let damage = msg.attack.map_or(msg.damage, |attack| {
+                let defender = players.get_by_color(unit.color);
+                let stats = Stats::new(&unit, &statuses, defender);
+                attack.taken(stats.armor, stats.magic_resist, false, defender)
+            });
+
+            // Shields absorb damage, but not healing
+            let damage = if damage > 0. && statuses.has(Status::Shield) {
+                statuses.absorb(damage)
+            } else {
+                damage
+            };
+
+            // Attacks (not damage over time) apply the status of their unit
+            if let Some(source) = msg.source.filter(|_| damage > 0. && msg.attacker.is_some()) {
+                if let Some(hit) = source.on_hit() {
+                    statuses.add(
+                        StatusEffect::new(hit.status, hit.value, hit.duration).with_source(source),
+                    );
+                }
+            }
+
+            let health = unit.health;
+            let max_health = unit.max_health(players.get_by_color(unit.color));
+            unit.health = (unit.health - damage).clamp(0., max_health);
+            if unit.health < health {
+                damage_dealt_msg.write(DamageDealtMsg {
+                    source: msg.source,
+                    target: Some(unit.name),
+                    damage: health - unit.health,
+                });
+            }
+
+            if let Some(attacker) = msg.attacker.filter(|_| unit.health != health) {
+                credits.push((attacker, (health - unit.health).abs(), unit.health == 0.));
+            }
+
             if unit.health == 0. {
                 despawn_msg.write(DespawnMsg(unit_e));
+
+                // The enemy counts the kill and earns a bounty for it in economy games
+                if health > 0. {
+                    for player in players.iter_mut().filter(|p| p.color != unit.color) {
+                        player.kills += 1;
+                        if settings.economy {
+                            player.gold_earned += unit.name.cost() * balance().economy.bounty;
+                        }
+                    }
+
+                    // Killed heroes respawn after a while
+                    if unit.hero {
+                        if let Some(hero) = players.get_by_color_mut(unit.color).hero.as_mut() {
+                            hero.alive = false;
+                            hero.respawn = balance().hero.respawn;
+                        }
+                    }
+
+                    deaths.push((unit.color, unit_t.translation, unit.name.weight()));
+                }
             }
         }
 
         if let Ok((building_e, mut building)) = building_q.get_mut(msg.entity) {
             // First skip buildings that already started the explosion animations
             if building.health > 0. {
-                building.health = (building.health - msg.damage).clamp(0., building.name.health());
+                // Buildings have no armor nor magic resist
+                let damage = msg.attack.map_or(msg.damage, |attack| {
+                    attack.taken(0., 0., true, players.get_by_color(building.color))
+                });
+
+                let health = building.health;
+                building.health = (building.health - damage).clamp(0., building.name.health());
+                if building.health < health {
+                    damage_dealt_msg.write(DamageDealtMsg {
+                        source: msg.source,
+                        target: None,
+                        damage: health - building.health,
+                    });
+
+                    if let Some(attacker) = msg.attacker {
+                        credits.push((attacker, health - building.health, false));
+                    }
+                }
+
                 if building.health == 0. {
                     commands.entity(building_e).insert(BuildingDestroyCmp::default());
                     effect_msg.write(EffectMsg::explosion(building_e));
@@ -302,4 +750,199 @@ pub fn apply_damage_message(
             }
         }
     }
+
+    // Units gain veterancy from the damage they deal and the enemies they kill
+    for (attacker, damage, kill) in credits {
+        let Ok((_, _, mut unit, _)) = unit_q.get_mut(attacker) else {
+            continue;
+        };
+
+        let player = players.get_by_color(unit.color);
+        let (rank, max_health) = (unit.veterancy.rank(), unit.max_health(player));
+
+        unit.veterancy.damage += damage;
+        if kill {
+            unit.veterancy.kills += 1;
+        }
+
+        if !unit.hero && unit.veterancy.rank() > rank {
+            // Keep the health fraction of the unit when promoted
+            unit.health *= unit.max_health(player) / max_health;
+            effect_msg.write(EffectMsg::dust(attacker));
+        }
+    }
+
+    // Heroes gain experience from the enemies killed around them
+    for (color, position, xp) in deaths {
+        let Some((hero_e, hero_color)) = unit_q
+            .iter()
+            .find(|(_, t, u, _)| {
+                u.hero
+                    && u.color != color
+                    && t.translation.truncate().distance(position.truncate())
+                        <= balance().hero.radius * RADIUS
+            })
+            .map(|(e, _, u, _)| (e, u.color))
+        else {
+            continue;
+        };
+
+        if let Some(hero) = players.get_by_color_mut(hero_color).hero.as_mut() {
+            let multiplier = hero.multiplier();
+            if hero.gain_xp(xp) {
+                // Keep the health fraction of the hero when leveling up
+                if let Ok((_, _, mut unit, _)) = unit_q.get_mut(hero_e) {
+                    unit.health *= hero.multiplier() / multiplier;
+                }
+                effect_msg.write(EffectMsg::dust(hero_e));
+            }
+        }
+    }
+}
+
+pub fn destroy_buildings(
+    mut building_q: Query<(Entity, &mut BuildingDestroyCmp)>,
+    mut despawn_msg: MessageWriter<DespawnMsg>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    // Despawn buildings when their explosion finished
+    for (building_e, mut destroy) in &mut building_q {
+        destroy.tick(scale_duration(time.delta(), settings.speed));
+
+        if destroy.just_finished() {
+            despawn_msg.write(DespawnMsg(building_e));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::map::map::{Lane, Map};
+    use crate::core::mechanics::spatial::{SpatialEntry, SpatialKind};
+    use crate::core::player::{SelectedBoost, Side};
+    use crate::core::rng::GameRng;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn player(boosts: &[Boost]) -> Player {
+        let mut player = Player::new(1, PlayerColor::Blue, Side::Left);
+        player.boosts = boosts.iter().map(|b| SelectedBoost::new(*b).active()).collect();
+        player
+    }
+
+    #[test]
+    fn taken_is_mitigated_by_defenses() {
+        let defender = player(&[]);
+        let damage = Damage::new(100., 60.);
+
+        assert_eq!(damage.taken(0., 0., false, &defender), 160.);
+        assert_eq!(damage.taken(10., 20., false, &defender), 50. + 20.);
+
+        let penetrating = Damage {
+            armor_pen: 15.,
+            magic_pen: 5.,
+            ..damage
+        };
+        assert_eq!(penetrating.taken(10., 20., false, &defender), 100. + 24.);
+    }
+
+    #[test]
+    fn taken_has_a_minimum() {
+        let min_damage = balance().min_damage;
+        let damage = Damage::new(1., 0.);
+
+        assert_eq!(damage.taken(1000., 0., false, &player(&[])), min_damage);
+    }
+
+    #[test]
+    fn taken_by_buildings_uses_siege() {
+        let damage = Damage {
+            siege: 2.,
+            ..Damage::new(100., 0.)
+        };
+
+        assert_eq!(damage.taken(0., 0., true, &player(&[])), 200.);
+        assert_eq!(damage.taken(0., 0., false, &player(&[])), 100.);
+        assert_eq!(damage.taken(0., 0., true, &player(&[Boost::BuildingsBlock])), 0.);
+    }
+
+    #[test]
+    fn taken_is_blocked_by_boosts() {
+        let blocking = player(&[Boost::BlockRange]);
+        let melee = Damage::new(100., 0.);
+        let ranged = Damage {
+            ranged: true,
+            ..melee
+        };
+
+        assert_eq!(ranged.taken(0., 0., false, &blocking), 0.);
+        assert_eq!(melee.taken(0., 0., false, &blocking), 100.);
+        assert_eq!(
+            melee.taken(0., 0., false, &player(&[Boost::ArmorGain])),
+            100. * Boost::ArmorGain.value()
+        );
+    }
+
+    #[test]
+    fn splash_falls_off_and_skips_allies() {
+        let source = UnitName::Troll;
+        let splash = source.splash().unwrap();
+
+        let me = player(&[]);
+        let enemy = Player::new(2, PlayerColor::Red, Side::Right);
+        let (map, mut rng) = (Map::default(), GameRng::new(0));
+
+        let mut world = World::new();
+        world.init_resource::<Messages<ApplyDamageMsg>>();
+
+        let mut grid = SpatialGrid::default();
+        let mut spawn = |player: &Player, distance: f32| {
+            let entity = world.spawn_empty().id();
+            grid.insert(SpatialEntry {
+                entity,
+                position: Vec3::new(distance * splash.radius * RADIUS, 0., 0.),
+                kind: SpatialKind::Unit(Unit::new(
+                    UnitName::Warrior,
+                    player,
+                    Some(Lane(0)),
+                    None,
+                    &map,
+                    &mut rng,
+                )),
+            });
+            entity
+        };
+
+        let target = spawn(&enemy, 0.);
+        let close = spawn(&enemy, 0.);
+        let edge = spawn(&enemy, 0.5);
+        spawn(&enemy, 2.);
+        spawn(&me, 0.);
+
+        let damage = Damage::new(100., 0.);
+        world
+            .run_system_once(move |mut apply_damage_msg: MessageWriter<ApplyDamageMsg>| {
+                apply_splash(
+                    source,
+                    None,
+                    me.color,
+                    Vec3::ZERO,
+                    target,
+                    damage,
+                    &grid,
+                    &mut apply_damage_msg,
+                );
+            })
+            .unwrap();
+
+        let mut hits: Vec<(Entity, f32)> = world
+            .resource_mut::<Messages<ApplyDamageMsg>>()
+            .drain()
+            .map(|msg| (msg.entity, msg.attack.unwrap().physical))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        assert_eq!(hits, [(close, 100.), (edge, 100. * (1. - splash.falloff * 0.5))]);
+    }
 }
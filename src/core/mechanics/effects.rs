@@ -4,12 +4,13 @@ use crate::core::constants::{BUILDING_SCALE, EFFECT_Z, FRAME_RATE, UNIT_SCALE};
 use crate::core::map::systems::MapCmp;
 use crate::core::map::utils::SpriteFrameLens;
 use crate::core::menu::systems::Host;
+use crate::core::rng::GameRng;
 use crate::core::units::buildings::Building;
 use crate::core::units::units::Unit;
 use crate::utils::NameFromEnum;
 use bevy::prelude::*;
 use bevy_tweening::{CycleCompletedEvent, Delay, Tween, TweenAnim};
-use rand::{rng, RngExt};
+use rand::RngExt;
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 #[cfg(not(target_arch = "wasm32"))]
@@ -25,6 +26,8 @@ pub struct EffectCmp;
 pub enum Effect {
     Dust,
     Explosion,
+    /// Splash of an area attack on its target
+    Impact,
 }
 
 #[derive(Message)]
@@ -47,6 +50,13 @@ impl EffectMsg {
             entity,
         }
     }
+
+    pub fn impact(entity: Entity) -> Self {
+        Self {
+            effect: Effect::Impact,
+            entity,
+        }
+    }
 }
 
 #[derive(Message)]
@@ -65,6 +75,7 @@ pub fn effect_message(
     mut play_audio_msg: MessageWriter<PlayAudioMsg>,
     mut effect_msg: MessageReader<EffectMsg>,
     mut deferred_msg: MessageWriter<DeferredEffectMsg>,
+    mut rng: ResMut<GameRng>,
     assets: Res<WorldAssets>,
 ) {
     for msg in effect_msg.read() {
@@ -103,11 +114,20 @@ pub fn effect_message(
             play_audio_msg.write(PlayAudioMsg::new("explosion"));
         }
 
-        let mut rng = rng();
+        // Impacts are a single splash centered on the target
+        let (particles, radius) = if msg.effect == Effect::Impact {
+            (1, 0.)
+        } else {
+            (particles, radius)
+        };
+
+        let rng = rng.visuals();
 
         for _ in 0..particles {
-            let atlas =
-                assets.atlas(format!("{}{}", msg.effect.to_lowername(), rng.random_range(1..3)));
+            let atlas = assets.atlas(match msg.effect {
+                Effect::Impact => msg.effect.to_lowername(),
+                _ => format!("{}{}", msg.effect.to_lowername(), rng.random_range(1..3)),
+            });
 
             commands.spawn((
                 Sprite {
@@ -118,8 +138,8 @@ pub fn effect_message(
                 Transform {
                     translation: (translation.truncate()
                         + Vec2::new(
-                            rng.random_range(-radius * size.x..radius * size.x),
-                            rng.random_range(-radius * size.y..radius * size.y),
+                            rng.random_range(-radius * size.x..=radius * size.x),
+                            rng.random_range(-radius * size.y..=radius * size.y),
                         ))
                     .extend(EFFECT_Z),
                     scale: Vec3::splat(rng.random_range(scale..scale + 0.5)),
@@ -127,7 +147,7 @@ pub fn effect_message(
                 },
                 TweenAnim::new(
                     Delay::new(Duration::from_millis(match msg.effect {
-                        Effect::Dust => 1,
+                        Effect::Dust | Effect::Impact => 1,
                         Effect::Explosion => rng.random_range(1..1500),
                     }))
                     .then(
@@ -0,0 +1,200 @@
+use crate::core::audio::PlayAudioMsg;
+use crate::core::constants::RADIUS;
+use crate::core::mechanics::combat::{ApplyDamageMsg, Damage};
+use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::spatial::SpatialGrid;
+use crate::core::mechanics::spawn::SpawnUnitMsg;
+use crate::core::mechanics::status::{Status, StatusEffect, StatusEffects};
+use crate::core::menu::systems::Host;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::core::network::{ClientMessage, ClientSendMsg};
+use crate::core::player::Players;
+use crate::core::replay::{PlayerInput, Replay};
+use crate::core::settings::{PlayerColor, Settings};
+use crate::core::simulation::Step;
+use crate::core::units::heroes::Ability;
+use crate::core::units::units::Unit;
+use bevy::prelude::*;
+
+#[derive(Message)]
+pub struct UseAbilityMsg {
+    pub color: PlayerColor,
+    /// Index of the ability of the hero
+    pub ability: usize,
+}
+
+impl UseAbilityMsg {
+    pub fn new(color: PlayerColor, ability: usize) -> Self {
+        Self {
+            color,
+            ability,
+        }
+    }
+}
+
+/// Respawn the heroes, advance their cooldowns and let the AI use their abilities
+pub fn update_heroes(
+    unit_q: Query<(&Transform, &Unit)>,
+    grid: Res<SpatialGrid>,
+    mut players: ResMut<Players>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+    mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
+    mut use_ability_msg: MessageWriter<UseAbilityMsg>,
+) {
+    let delta = time.delta_secs() * settings.speed;
+
+    for player in players.iter_mut() {
+        let is_human = player.is_human();
+        let Some(hero) = player.hero.as_mut() else {
+            continue;
+        };
+
+        hero.cooldowns.iter_mut().for_each(|c| *c = (*c - delta).max(0.));
+
+        if !hero.alive {
+            hero.respawn -= delta;
+            if hero.respawn <= 0. {
+                hero.alive = true;
+                hero.respawn = 0.;
+                spawn_unit_msg.write(SpawnUnitMsg {
+                    hero: true,
+                    dust_effect: true,
+                    ..SpawnUnitMsg::new(player.color, hero.name.unit())
+                });
+            }
+        } else if !is_human {
+            // The AI uses the abilities as soon as there are enemies around the hero
+            let Some((hero_t, _)) = unit_q.iter().find(|(_, u)| u.hero && u.color == player.color)
+            else {
+                continue;
+            };
+
+            for (i, ability) in hero.name.abilities().iter().enumerate() {
+                if hero.is_ready(i)
+                    && grid
+                        .units_within(hero_t.translation, ability.balance().radius * RADIUS)
+                        .any(|(_, u)| u.color != player.color)
+                {
+                    use_ability_msg.write(UseAbilityMsg::new(player.color, i));
+                }
+            }
+        }
+    }
+}
+
+pub fn use_ability_message(
+    mut unit_q: Query<(Entity, &Transform, &Unit, &mut StatusEffects)>,
+    host: Option<Res<Host>>,
+    grid: Res<SpatialGrid>,
+    mut players: ResMut<Players>,
+    mut replay: Option<ResMut<Replay>>,
+    step: Res<Step>,
+    mut use_ability_msg: MessageReader<UseAbilityMsg>,
+    mut apply_damage_msg: MessageWriter<ApplyDamageMsg>,
+    mut effect_msg: MessageWriter<EffectMsg>,
+    mut play_audio_msg: MessageWriter<PlayAudioMsg>,
+    #[cfg(not(target_arch = "wasm32"))] mut client_send_msg: MessageWriter<ClientSendMsg>,
+) {
+    for msg in use_ability_msg.read() {
+        let is_me = players.me.color == msg.color;
+        let player = players.get_by_color_mut(msg.color);
+        let is_ready = player.hero.as_ref().is_some_and(|h| h.is_ready(msg.ability));
+
+        if host.is_none() {
+            // The client only checks the cooldown, the host simulates the ability
+            if is_ready {
+                #[cfg(not(target_arch = "wasm32"))]
+                client_send_msg.write(ClientSendMsg::new(ClientMessage::UseAbility(msg.ability)));
+            } else {
+                play_audio_msg.write(PlayAudioMsg::new("error"));
+            }
+            continue;
+        }
+
+        // The abilities of the AI are played back by the simulation
+        if player.is_human() {
+            if let Some(replay) = &mut replay {
+                replay.record(*step, PlayerInput::UseAbility(msg.color, msg.ability));
+            }
+        }
+
+        let hero_unit = unit_q.iter().find(|(_, _, u, _)| u.hero && u.color == msg.color);
+        let (Some(hero), Some((hero_e, hero_t, hero_unit, _)), true) =
+            (player.hero.as_mut(), hero_unit, is_ready)
+        else {
+            if is_me {
+                play_audio_msg.write(PlayAudioMsg::new("error"));
+            }
+            continue;
+        };
+
+        let ability = hero.name.abilities()[msg.ability];
+        let balance = ability.balance();
+        hero.cooldowns[msg.ability] = balance.cooldown;
+
+        let (position, source) = (hero_t.translation, hero_unit.name);
+        let targets: Vec<Entity> = grid
+            .units_within(position, balance.radius * RADIUS)
+            .filter(|(_, u)| {
+                (u.color == msg.color) == ability.on_allies()
+                    && u.on_building.is_none()
+                    && !(ability == Ability::Stomp && u.name.is_flying())
+            })
+            .map(|(e, _)| e.entity)
+            .collect();
+
+        for target in targets {
+            let Ok((_, _, unit, mut statuses)) = unit_q.get_mut(target) else {
+                continue;
+            };
+
+            match ability {
+                Ability::Rally => {
+                    statuses.add(StatusEffect::new(Status::Haste, balance.value, balance.duration));
+                },
+                Ability::Stomp => {
+                    statuses.add(StatusEffect::new(Status::Stun, 0., balance.duration));
+                    apply_damage_msg.write(ApplyDamageMsg::attack(
+                        target,
+                        Damage::new(balance.value, 0.),
+                        Some(source),
+                        Some(hero_e),
+                    ));
+                    effect_msg.write(EffectMsg::impact(target));
+                },
+                Ability::Volley => {
+                    apply_damage_msg.write(ApplyDamageMsg::attack(
+                        target,
+                        Damage {
+                            ranged: true,
+                            ..Damage::new(balance.value, 0.)
+                        },
+                        Some(source),
+                        Some(hero_e),
+                    ));
+                    effect_msg.write(EffectMsg::impact(target));
+                },
+                Ability::Snare => {
+                    statuses.add(StatusEffect::new(Status::Slow, balance.value, balance.duration));
+                },
+                Ability::Heal => {
+                    let heal = balance.value * unit.max_health(players.get_by_color(unit.color));
+                    apply_damage_msg.write(ApplyDamageMsg::new(
+                        target,
+                        -heal,
+                        Some(source),
+                        Some(hero_e),
+                    ));
+                },
+                Ability::Shield => {
+                    statuses.add(StatusEffect::new(
+                        Status::Shield,
+                        balance.value,
+                        balance.duration,
+                    ));
+                },
+            }
+        }
+    }
+}
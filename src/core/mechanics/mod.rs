@@ -1,5 +1,9 @@
 pub mod combat;
 pub mod effects;
+pub mod heroes;
 pub mod movement;
 pub mod queue;
+pub mod spatial;
 pub mod spawn;
+pub mod status;
+pub mod tech;
@@ -1,171 +1,166 @@
 use crate::core::boosts::Boost;
 use crate::core::constants::*;
-use crate::core::map::map::Map;
-use crate::core::mechanics::combat::{ApplyDamageMsg, Arrow, ProjectileMode};
+use crate::core::map::map::{Map, Movement};
+use crate::core::mechanics::combat::{apply_splash, ApplyDamageMsg, Arrow, ProjectileMode};
+use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::spatial::{SpatialEntry, SpatialGrid};
 use crate::core::mechanics::spawn::DespawnMsg;
-use crate::core::player::{Players, Side, Strategy};
-use crate::core::settings::{PlayerColor, Settings};
-use crate::core::units::buildings::Building;
+use crate::core::mechanics::status::{Stats, StatusEffects};
+use crate::core::player::{Players, Strategy};
+use crate::core::settings::Settings;
+use crate::core::units::buildings::BuildingName;
 use crate::core::units::units::{Action, Unit, UnitName};
 use crate::utils::scale_duration;
 use bevy::prelude::*;
 use bevy_ecs_tiled::prelude::TilePos;
-use std::collections::{HashMap, HashSet};
-
-/// Get all tiles at <= `distance` from `pos`
-fn get_tiles_at_distance(pos: &TilePos, d: u32) -> HashSet<TilePos> {
-    (pos.x.saturating_sub(d)..=pos.x + d)
-        .flat_map(|x| (pos.y.saturating_sub(d)..=pos.y + d).map(move |y| TilePos::new(x, y)))
-        .collect()
-}
-
-/// Return the next tile to walk to, which is the one after the closest lane tile
-fn get_target_tile(tile: TilePos, path: &[TilePos]) -> TilePos {
-    let pos = Map::tile_to_world(tile);
-    let closest = path
-        .iter()
-        .enumerate()
-        .min_by(|(_, a), (_, b)| {
-            let da = Map::tile_to_world(**a).distance(pos);
-            let db = Map::tile_to_world(**b).distance(pos);
-            da.partial_cmp(&db).unwrap()
-        })
-        .and_then(|(i, _)| path.get(i + 1).or_else(|| path.last()))
-        .unwrap();
-
-    Map::find_path(tile, *closest)[1]
-}
+use std::f32::consts::TAU;
 
 fn move_unit(
     unit_e: Entity,
     unit: &mut Unit,
     unit_t: &mut Transform,
-    unit_s: &mut Sprite,
-    unit_pos: &HashMap<TilePos, Vec<(Entity, Vec3, Unit)>>,
-    building_pos: &HashMap<TilePos, Vec<(Entity, Vec3, Building)>>,
+    unit_s: Option<&mut Sprite>,
+    statuses: &StatusEffects,
+    grid: &SpatialGrid,
     settings: &Settings,
     map: &Map,
     players: &Players,
     time: &Time,
 ) {
-    let tile = Map::world_to_tile(&unit_t.translation);
-    let mut lane = map.lane(&unit.lane);
+    let tile = map.world_to_tile(&unit_t.translation);
 
     let player = players.get_by_color(unit.color);
-    let enemy = players.get_by_side(player.side.opposite());
 
-    // Reverse paths for the enemy
-    if player.side == Side::Right {
-        lane.reverse();
-    }
+    let terrain = map.movement(unit.name.movement());
 
-    if tile == *lane.last().unwrap() {
+    let target_tile = if terrain == Movement::Air {
+        // Flying units go straight for the enemy base
+        Some(map.bases[&player.side.opposite()]).filter(|t| *t != tile)
+    } else {
+        map.next_tile(tile, &unit.lane, player.side, terrain)
+    };
+
+    let Some(target_tile) = target_tile else {
         unit.action = Action::Idle;
         return;
-    }
-
-    let target_tile = get_target_tile(tile, &lane);
-    let target_pos = Map::tile_to_world(target_tile).extend(unit_t.translation.z);
+    };
+    let target_pos = map.tile_to_world(target_tile).extend(unit_t.translation.z);
     let target_delta = (target_pos - unit_t.translation).normalize();
 
     let mut separation = Vec3::ZERO;
 
-    // Only check units in the surrounding
-    for tile in get_tiles_at_distance(&tile, 5) {
-        if let Some(units) = unit_pos.get(&tile) {
-            'enemy: for (other_e, other_pos, other) in units {
-                let delta = unit_t.translation - other_pos;
-                let dist = delta.length();
-
-                // Distance between the units to perform melee attacks
-                let separation_radius = (unit.name.world_size() + other.name.world_size()) * 0.15;
-
-                let range = if unit.name.is_melee() {
-                    separation_radius
-                } else {
-                    unit.range(player) * RADIUS
-                };
-
-                // Skip if self or too far to interact
-                if unit_e == *other_e || dist > range {
-                    continue;
-                }
+    // Only check units within interaction distance
+    let reach = (unit.range(player) * RADIUS).max(unit.name.world_size());
+    'enemy: for (other, other_unit) in grid.units_within(unit_t.translation, reach) {
+        let (other_e, other_pos) = (other.entity, other.position);
 
-                // Possible interactions are:
-                // - Priest with unhealthy ally -> heal
-                // - Combat unit with enemy -> attack
-                // - Else: resolve separation force
-                unit.action = match (unit.name, unit.color == other.color) {
-                    (UnitName::Priest, true) if other.health < other.name.health() => {
-                        Action::Heal(*other_e)
-                    },
-                    (u, false)
-                        if u.can_attack()
-                            && (!u.is_melee() || dist <= separation_radius)
-                            && player.strategy != Strategy::March =>
-                    {
-                        Action::Attack(*other_e)
-                    },
-                    _ => {
-                        if dist <= separation_radius
-                            && other.on_building.is_none()
-                            && !player.has_boost(Boost::NoCollision)
-                        {
-                            // Handle units on top of each other
-                            let delta_norm = if dist < 0.01 {
-                                // Use a deterministic direction based on entity to ensure consistent separation
-                                let angle = unit_e.to_bits() as f32 * 10.;
-                                Vec3::new(angle.cos(), angle.sin(), 0.)
-                            } else {
-                                delta.normalize()
-                            };
-
-                            let strength = (separation_radius - dist).powi(3) / (separation_radius);
-
-                            // Calculate a "sideways" vector (perpendicular to movement)
-                            let perpendicular = Vec3::new(-target_delta.y, target_delta.x, 0.);
-
-                            // Determine which side of the path the other unit is on
-                            let sign = if delta_norm.dot(perpendicular) >= 0. {
-                                1.
-                            } else {
-                                -1.
-                            };
-
-                            // Apply force: mostly perpendicular to bypass, slightly away to avoid collision
-                            separation += perpendicular * sign + delta_norm * strength;
-                        }
-
-                        continue 'enemy;
-                    },
-                };
-
-                return; // The unit's action is non-run -> skip movement
-            }
-        }
+        let delta = unit_t.translation - other_pos;
+        let dist = delta.length();
 
-        if let Some(buildings) = building_pos.get(&tile) {
-            for (building_e, building_pos, building) in buildings {
-                let dist = unit_t.translation.distance(*building_pos);
+        // Distance between the units to perform melee attacks
+        let separation_radius = (unit.name.world_size() + other_unit.name.world_size()) * 0.15;
 
-                let range = if unit.name.is_melee() {
-                    (unit.name.world_size() + building.name.world_size().x) * 0.4
-                } else {
-                    unit.range(player) * RADIUS
-                };
+        let range = if unit.name.is_melee() {
+            separation_radius
+        } else {
+            unit.range(player) * RADIUS
+        };
+
+        // Skip if self or too far to interact
+        if unit_e == other_e || dist > range {
+            continue;
+        }
 
-                if unit.name.can_attack() && building.color != unit.color && dist <= range {
-                    unit.action = Action::Attack(*building_e);
-                    return;
+        // Possible interactions are:
+        // - Priest with unhealthy ally -> heal
+        // - Combat unit with enemy -> attack
+        // - Else: resolve separation force
+        unit.action = match (unit.name, unit.color == other_unit.color) {
+            (UnitName::Priest, true) if other_unit.health < other_unit.max_health(player) => {
+                Action::Heal(other_e)
+            },
+            (u, false)
+                if u.can_attack()
+                    && (!u.is_melee()
+                        || (dist <= separation_radius && !other_unit.name.is_flying()))
+                    && player.strategy != Strategy::March =>
+            {
+                Action::Attack(other_e)
+            },
+            _ => {
+                // Flying units don't collide with units on the ground
+                if dist <= separation_radius
+                    && other_unit.on_building.is_none()
+                    && unit.name.is_flying() == other_unit.name.is_flying()
+                    && !player.has_boost(Boost::NoCollision)
+                {
+                    // Handle units on top of each other
+                    let delta_norm = if dist < 0.01 {
+                        // Use a deterministic direction based on the unit to ensure consistent separation
+                        let angle = unit.id as f32 / u64::MAX as f32 * TAU;
+                        Vec3::new(angle.cos(), angle.sin(), 0.)
+                    } else {
+                        delta.normalize()
+                    };
+
+                    let strength = (separation_radius - dist).powi(3) / (separation_radius);
+
+                    // Calculate a "sideways" vector (perpendicular to movement)
+                    let perpendicular = Vec3::new(-target_delta.y, target_delta.x, 0.);
+
+                    // Determine which side of the path the other unit is on
+                    let sign = if delta_norm.dot(perpendicular) >= 0. {
+                        1.
+                    } else {
+                        -1.
+                    };
+
+                    // Apply force: mostly perpendicular to bypass, slightly away to avoid collision
+                    separation += perpendicular * sign + delta_norm * strength;
                 }
-            }
+
+                continue 'enemy;
+            },
+        };
+
+        return; // The unit's action is non-run -> skip movement
+    }
+
+    // Castles are the widest buildings
+    let building_reach = if unit.name.is_melee() {
+        (unit.name.world_size() + BuildingName::Castle.world_size().x) * 0.4
+    } else {
+        unit.range(player) * RADIUS
+    };
+
+    for (building, building_cmp) in grid.buildings_within(unit_t.translation, building_reach) {
+        let dist = unit_t.translation.distance(building.position);
+
+        let range = if unit.name.is_melee() {
+            (unit.name.world_size() + building_cmp.name.world_size().x) * 0.4
+        } else {
+            unit.range(player) * RADIUS
+        };
+
+        // Naval units can only reach the buildings on the coast
+        if unit.name.can_attack()
+            && building_cmp.color != unit.color
+            && dist <= range
+            && (terrain != Movement::Water
+                || map.is_coastal(building.position, building_cmp.name.world_size()))
+        {
+            unit.action = Action::Attack(building.entity);
+            return;
         }
     }
 
     // Units on buildings don't move
     if unit.on_building.is_some() {
         return;
-    } else if enemy.has_boost(Boost::Frozen) {
+    }
+
+    let speed = Stats::new(unit, statuses, player).speed;
+    if speed == 0. {
         unit.action = Action::Idle;
         return;
     }
@@ -182,26 +177,18 @@ fn move_unit(
 
     let mut next_pos = unit_t.translation
         + movement.normalize()
-            * unit.name.speed()
-            * if player.has_boost(Boost::Run) {
-                2.
-            } else {
-                1.
-            }
-            * if player.strategy == Strategy::March {
-                1.5
-            } else {
-                1.
-            }
+            * speed
             * settings.speed
             * time.delta_secs().min(CAPPED_DELTA_SECS_SPEED);
 
-    let next_tile = Map::world_to_tile(&next_pos);
+    let next_tile = map.world_to_tile(&next_pos);
 
-    if tile == next_tile || Map::is_walkable(next_tile) {
-        // Check if the tile below is walkable. If not, restrict movement to the top part
-        if !Map::is_walkable(TilePos::new(next_tile.x, next_tile.y + 1)) {
-            let bottom_limit = Map::tile_to_world(next_tile).y - Map::TILE_SIZE as f32 * 0.5
+    // Units stranded off their terrain can move anywhere to get back
+    if tile == next_tile || map.is_navigable(next_tile, terrain) || !map.is_navigable(tile, terrain)
+    {
+        // Check if the tile below is navigable. If not, restrict movement to the top part
+        if !map.is_navigable(TilePos::new(next_tile.x, next_tile.y + 1), terrain) {
+            let bottom_limit = map.tile_to_world(next_tile).y - Map::TILE_SIZE as f32 * 0.5
                 + unit.name.world_size() * 0.25;
 
             if next_pos.y < bottom_limit {
@@ -210,11 +197,13 @@ fn move_unit(
         }
 
         // Change the direction the unit is facing
-        unit_s.flip_x = if next_tile.x != tile.x {
-            next_tile.x < tile.x
-        } else {
-            players.me.color != unit.color
-        };
+        if let Some(unit_s) = unit_s {
+            unit_s.flip_x = if next_tile.x != tile.x {
+                next_tile.x < tile.x
+            } else {
+                players.me.color != unit.color
+            };
+        }
 
         unit_t.translation = next_pos;
         unit.action = Action::Run;
@@ -223,16 +212,49 @@ fn move_unit(
     }
 }
 
+/// Apply the damage of a projectile to the entity it hit
+fn hit_target(
+    arrow: &mut Arrow,
+    other: &SpatialEntry,
+    grid: &SpatialGrid,
+    apply_damage_msg: &mut MessageWriter<ApplyDamageMsg>,
+    effect_msg: &mut MessageWriter<EffectMsg>,
+) {
+    arrow.hits.push(other.entity);
+    apply_damage_msg.write(ApplyDamageMsg::new(
+        other.entity,
+        arrow.damage,
+        arrow.unit,
+        arrow.shooter,
+    ));
+
+    // Magic projectiles explode on impact
+    if let (Some(unit), Some(splash)) = (arrow.unit, arrow.splash) {
+        apply_splash(
+            unit,
+            arrow.shooter,
+            arrow.color,
+            other.position,
+            other.entity,
+            splash,
+            grid,
+            apply_damage_msg,
+        );
+        effect_msg.write(EffectMsg::impact(other.entity));
+    }
+}
+
 fn move_arrow(
     arrow_e: Entity,
     arrow: &mut Arrow,
     arrow_t: &mut Transform,
-    arrow_s: &mut Sprite,
+    arrow_s: Option<&mut Sprite>,
     apply_damage_msg: &mut MessageWriter<ApplyDamageMsg>,
     despawn_msg: &mut MessageWriter<DespawnMsg>,
-    positions: &HashMap<TilePos, Vec<(Entity, PlayerColor, Vec3)>>,
+    effect_msg: &mut MessageWriter<EffectMsg>,
+    grid: &SpatialGrid,
     settings: &Settings,
-    images: &Assets<Image>,
+    images: Option<&Assets<Image>>,
     time: &Time,
 ) {
     // Resolve arrow hitting an enemy
@@ -246,15 +268,32 @@ fn move_arrow(
     if progress >= 1.0 {
         match arrow.projectile.mode() {
             ProjectileMode::Parabolic => {
-                if let Some(image) = images.get(&arrow_s.image) {
-                    // Hide the point to look as if the arrow is stuck in the ground
-                    arrow_s.rect = Some(Rect {
-                        min: Vec2::ZERO,
-                        max: Vec2::new(image.width() as f32 * 0.65, image.height() as f32),
-                    });
-
-                    // Place ground arrows behind units and buildings
-                    arrow_t.translation.z = BUILDINGS_Z - 0.1;
+                // Arrows only hit an enemy at the spot where they land
+                if !arrow.landed {
+                    arrow.landed = true;
+
+                    let position = arrow.destination.extend(ARROW_Z);
+                    if let Some(other) = grid
+                        .nearest_enemy(position, arrow.color, RADIUS * 0.4)
+                        .filter(|_| !arrow.missed && arrow.projectile.pierce() == 1)
+                    {
+                        hit_target(arrow, other, grid, apply_damage_msg, effect_msg);
+                        despawn_msg.write(DespawnMsg(arrow_e));
+                        return;
+                    }
+                }
+
+                // Place ground arrows behind units and buildings
+                arrow_t.translation.z = BUILDINGS_Z - 0.1;
+
+                // Hide the point to look as if the arrow is stuck in the ground
+                if let (Some(arrow_s), Some(images)) = (arrow_s, images) {
+                    if let Some(image) = images.get(&arrow_s.image) {
+                        arrow_s.rect = Some(Rect {
+                            min: Vec2::ZERO,
+                            max: Vec2::new(image.width() as f32 * 0.65, image.height() as f32),
+                        });
+                    }
                 }
 
                 arrow.despawn_timer.tick(scale_duration(time.delta(), settings.speed));
@@ -283,23 +322,36 @@ fn move_arrow(
     // Set new position with arc
     arrow_t.translation = pos.extend(ARROW_Z);
 
-    // Check if the arrow hit someone (in this or adjacent tiles)
-    let tile = Map::world_to_tile(&arrow_t.translation);
-    for tile in get_tiles_at_distance(&tile, 2) {
-        if let Some(units) = positions.get(&tile) {
-            for (other_e, other_color, other_pos) in units {
-                if *other_color != arrow.color
-                    && arrow_t.translation.distance(*other_pos) < RADIUS * 0.4
-                {
-                    apply_damage_msg.write(ApplyDamageMsg::new(*other_e, arrow.damage));
-                    despawn_msg.write(DespawnMsg(arrow_e));
-                    return;
-                }
+    // Straight and piercing projectiles hit the enemies along their path
+    let on_path =
+        arrow.projectile.mode() == ProjectileMode::Straight || arrow.projectile.pierce() > 1;
+    if on_path && !arrow.missed {
+        let other = grid
+            .within(arrow_t.translation, RADIUS * 0.4)
+            .filter(|e| e.color() != arrow.color && !arrow.hits.contains(&e.entity))
+            .min_by(|a, b| {
+                a.distance(arrow_t.translation).total_cmp(&b.distance(arrow_t.translation))
+            });
+
+        if let Some(other) = other {
+            hit_target(arrow, other, grid, apply_damage_msg, effect_msg);
+
+            // Buildings stop every projectile
+            if other.building().is_some() || arrow.hits.len() >= arrow.projectile.pierce() {
+                despawn_msg.write(DespawnMsg(arrow_e));
+                return;
             }
         }
     }
 
-    if arrow.projectile.mode() == ProjectileMode::Parabolic {
+    if arrow.projectile.mode() == ProjectileMode::Straight {
+        // Point non-animated projectiles in the direction they fly
+        if !arrow.projectile.animation() {
+            let direction = arrow.destination - arrow.start;
+            arrow_t.rotation =
+                Quat::from_rotation_z(direction.y.atan2(direction.x) + arrow.projectile.angle());
+        }
+    } else {
         // Calculate velocity direction for rotation (take a small step ahead to determine angle)
         let next_progress = ((arrow.traveled + 1.) / arrow.total_distance).min(1.);
         let next_horizontal = arrow.start.lerp(arrow.destination, next_progress);
@@ -316,62 +368,50 @@ fn move_arrow(
 }
 
 pub fn apply_movement(
-    mut unit_q: Query<(Entity, &mut Transform, &mut Sprite, &mut Unit)>,
-    building_q: Query<(Entity, &Transform, &Building), Without<Unit>>,
-    mut arrow_q: Query<
-        (Entity, &mut Transform, &mut Sprite, &mut Arrow),
-        (Without<Unit>, Without<Building>),
-    >,
+    mut unit_q: Query<(Entity, &mut Transform, Option<&mut Sprite>, &mut Unit, &StatusEffects)>,
+    mut arrow_q: Query<(Entity, &mut Transform, Option<&mut Sprite>, &mut Arrow), Without<Unit>>,
     mut apply_damage_msg: MessageWriter<ApplyDamageMsg>,
     mut despawn_msg: MessageWriter<DespawnMsg>,
+    mut effect_msg: MessageWriter<EffectMsg>,
+    grid: Res<SpatialGrid>,
     settings: Res<Settings>,
     map: Res<Map>,
     players: Res<Players>,
-    images: Res<Assets<Image>>,
+    images: Option<Res<Assets<Image>>>,
     time: Res<Time>,
 ) {
-    // Build spatial hashmap: tile -> positions + unit
-    let unit_pos: HashMap<TilePos, Vec<(Entity, Vec3, Unit)>> =
-        unit_q.iter().fold(HashMap::new(), |mut acc, (e, t, _, u)| {
-            let tile = Map::world_to_tile(&t.translation);
-            acc.entry(tile).or_default().push((e, t.translation, *u));
-            acc
-        });
-
-    let building_pos: HashMap<TilePos, Vec<(Entity, Vec3, Building)>> =
-        building_q.iter().fold(HashMap::new(), |mut acc, (e, t, b)| {
-            let tile = Map::world_to_tile(&t.translation);
-            acc.entry(tile).or_default().push((e, t.translation, *b));
-            acc
-        });
-
-    let any_pos: HashMap<TilePos, Vec<(Entity, PlayerColor, Vec3)>> = unit_pos
-        .iter()
-        .flat_map(|(t, u)| u.iter().map(|(e, p, u)| (*t, *e, u.color, *p)))
-        .chain(
-            building_pos.iter().flat_map(|(t, b)| b.iter().map(|(e, p, b)| (*t, *e, b.color, *p))),
-        )
-        .fold(HashMap::new(), |mut acc, (t, e, c, p)| {
-            acc.entry(t).or_default().push((e, c, p));
-            acc
-        });
+    let delta = settings.speed * time.delta_secs().min(CAPPED_DELTA_SECS_SPEED);
 
     // Move units
-    for (unit_e, mut unit_t, mut unit_s, mut unit) in
-        unit_q.iter_mut().filter(|(_, _, _, u)| matches!(u.action, Action::Idle | Action::Run))
-    {
+    for (unit_e, mut unit_t, mut unit_s, mut unit, statuses) in &mut unit_q {
+        // Units converted into or from a flying unit change their height
+        if unit_t.translation.z != unit.name.z() {
+            unit_t.translation.z = unit.name.z();
+        }
+
+        if !matches!(unit.action, Action::Idle | Action::Run) {
+            unit.velocity = Vec2::ZERO;
+            continue;
+        }
+
+        let position = unit_t.translation;
         move_unit(
             unit_e,
             &mut unit,
             &mut unit_t,
-            &mut unit_s,
-            &unit_pos,
-            &building_pos,
+            unit_s.as_deref_mut(),
+            statuses,
+            &grid,
             &settings,
             &map,
             &players,
             &time,
         );
+
+        // Remember the velocity to let projectiles lead the unit
+        if delta > 0. {
+            unit.velocity = (unit_t.translation - position).truncate() / delta;
+        }
     }
 
     // Move arrows
@@ -380,12 +420,13 @@ pub fn apply_movement(
             arrow_e,
             &mut arrow,
             &mut arrow_t,
-            &mut arrow_s,
+            arrow_s.as_deref_mut(),
             &mut apply_damage_msg,
             &mut despawn_msg,
-            &any_pos,
+            &mut effect_msg,
+            &grid,
             &settings,
-            &images,
+            images.as_deref(),
             &time,
         )
     }
@@ -1,19 +1,21 @@
 use crate::core::audio::PlayAudioMsg;
+use crate::core::balance::balance;
 use crate::core::boosts::Boost;
-use crate::core::constants::MAX_QUEUE_LENGTH;
 use crate::core::mechanics::spawn::SpawnUnitMsg;
 use crate::core::menu::systems::Host;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::core::network::{ClientMessage, ClientSendMsg};
-use crate::core::player::{Players, QueuedUnit};
+use crate::core::player::Players;
+use crate::core::replay::{PlayerInput, Replay};
+use crate::core::rng::GameRng;
 use crate::core::settings::Settings;
-use crate::core::units::units::UnitName;
+use crate::core::simulation::Step;
+use crate::core::units::units::{population, Unit, UnitName};
 use crate::core::utils::ClientId;
 use crate::utils::scale_duration;
 use bevy::prelude::*;
 use rand::distr::weighted::WeightedIndex;
 use rand::distr::Distribution;
-use rand::rng;
 use strum::IntoEnumIterator;
 
 #[derive(Message)]
@@ -35,24 +37,34 @@ pub fn queue_message(
     mut queue_unit_msg: MessageReader<QueueUnitMsg>,
     mut play_audio_msg: MessageWriter<PlayAudioMsg>,
     mut players: ResMut<Players>,
+    mut replay: Option<ResMut<Replay>>,
+    settings: Res<Settings>,
+    step: Res<Step>,
 ) {
     for msg in queue_unit_msg.read() {
+        if let Some(replay) = &mut replay {
+            replay.record(*step, PlayerInput::QueueUnit(msg.id, msg.unit));
+        }
+
         let player = players.get_by_id_mut(msg.id);
 
-        if player.queue.len() < MAX_QUEUE_LENGTH {
-            player.queue.push_back(QueuedUnit::new(msg.unit, msg.unit.spawn_duration()));
-        } else if player.is_human() {
+        // Reject units when the queue is full or the player can't afford them
+        let queued =
+            player.queue.len() < balance().max_queue && player.enqueue(msg.unit, settings.economy);
+
+        if !queued && player.is_human() {
             play_audio_msg.write(PlayAudioMsg::new("error"));
         }
     }
 }
 
 pub fn queue_resolve(
+    unit_q: Query<&Unit>,
     mut players: ResMut<Players>,
     host: Option<Res<Host>>,
-    mut queue_unit_msg: MessageWriter<QueueUnitMsg>,
     mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
     #[cfg(not(target_arch = "wasm32"))] mut client_send_msg: MessageWriter<ClientSendMsg>,
+    mut rng: ResMut<GameRng>,
     settings: Res<Settings>,
     time: Res<Time>,
 ) {
@@ -64,12 +76,12 @@ pub fn queue_resolve(
         }
 
         let queue_boost = if player.has_boost(Boost::SpawnTime) {
-            1.2
+            Boost::SpawnTime.value()
         } else {
             1.0
         };
 
-        let mut spawns: Vec<(usize, UnitName)> = Vec::with_capacity(2);
+        let mut spawns: Vec<(usize, UnitName, f32)> = Vec::with_capacity(2);
 
         let max_slots = if player.has_boost(Boost::DoubleQueue) {
             2
@@ -77,35 +89,59 @@ pub fn queue_resolve(
             1
         };
 
+        // Finished units wait in the queue until there's room in the population
+        let mut current = population(unit_q.iter(), player.color);
+
         for i in 0..max_slots {
             if let Some(queue) = player.queue.get_mut(i) {
                 queue.timer.tick(scale_duration(time.delta(), settings.speed * queue_boost));
 
-                if queue.timer.just_finished() {
-                    spawns.push((i, queue.unit));
+                if queue.timer.is_finished()
+                    && current + queue.unit.weight() <= balance().population
+                {
+                    current += queue.unit.weight();
+                    spawns.push((i, queue.unit, queue.cost));
                 }
             } else if player.is_human() {
                 if !player.can_queue(player.queue_default) {
                     player.queue_default = UnitName::default();
                 }
-                queue_unit_msg.write(QueueUnitMsg::new(player.id, player.queue_default));
+
+                // Queue directly, since only the requests of the player are recorded as inputs
+                // In economy games, the queue waits until the player can afford the unit
+                let unit = player.queue_default;
+                player.enqueue(unit, settings.economy);
             } else {
                 // Spawn units randomly with inverse probability to their spawning time
-                let units: Vec<UnitName> =
-                    UnitName::iter().filter(|u| player.can_queue(*u)).collect();
+                let units: Vec<UnitName> = match &player.pool.units {
+                    Some(units) => units.clone(),
+                    None => UnitName::iter().filter(|u| player.can_queue(*u)).collect(),
+                };
+
+                let units: Vec<UnitName> = units
+                    .into_iter()
+                    .filter(|u| !settings.economy || u.cost() <= player.gold())
+                    .collect();
+                if units.is_empty() {
+                    continue;
+                }
+
                 let weights: Vec<f64> =
                     units.iter().map(|u| 1.0 / u.spawn_duration() as f64).collect();
 
                 let dist = WeightedIndex::new(&weights).unwrap();
-                let unit = units[dist.sample(&mut rng())];
+                let unit = units[dist.sample(&mut *rng)];
 
-                queue_unit_msg.write(QueueUnitMsg::new(player.id, unit));
+                player.enqueue(unit, settings.economy);
             }
         }
 
-        for (i, unit) in spawns.iter().rev() {
+        for (i, unit, cost) in spawns.iter().rev() {
             if host.is_some() {
-                spawn_unit_msg.write(SpawnUnitMsg::new(player.color, *unit));
+                spawn_unit_msg.write(SpawnUnitMsg {
+                    cost: *cost,
+                    ..SpawnUnitMsg::new(player.color, *unit)
+                });
             } else {
                 #[cfg(not(target_arch = "wasm32"))]
                 client_send_msg.write(ClientSendMsg::new(ClientMessage::SpawnUnit(*unit)));
@@ -116,3 +152,15 @@ pub fn queue_resolve(
         }
     }
 }
+
+/// Give every player their income in economy games
+pub fn update_gold(mut players: ResMut<Players>, settings: Res<Settings>, time: Res<Time>) {
+    if !settings.economy {
+        return;
+    }
+
+    let income = balance().economy.income * time.delta_secs() * settings.speed;
+    for player in players.iter_mut() {
+        player.gold_earned += income;
+    }
+}
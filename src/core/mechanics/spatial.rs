@@ -0,0 +1,177 @@
+use crate::core::settings::PlayerColor;
+use crate::core::units::buildings::Building;
+use crate::core::units::units::Unit;
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+#[derive(Clone, Copy, Debug)]
+pub enum SpatialKind {
+    Unit(Unit),
+    Building(Building),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct SpatialEntry {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub kind: SpatialKind,
+}
+
+impl SpatialEntry {
+    pub fn color(&self) -> PlayerColor {
+        match &self.kind {
+            SpatialKind::Unit(u) => u.color,
+            SpatialKind::Building(b) => b.color,
+        }
+    }
+
+    pub fn unit(&self) -> Option<&Unit> {
+        match &self.kind {
+            SpatialKind::Unit(u) => Some(u),
+            _ => None,
+        }
+    }
+
+    pub fn building(&self) -> Option<&Building> {
+        match &self.kind {
+            SpatialKind::Building(b) => Some(b),
+            _ => None,
+        }
+    }
+
+    pub fn distance(&self, position: Vec3) -> f32 {
+        self.position.truncate().distance(position.truncate())
+    }
+}
+
+/// Spatial index over the units and buildings in the game
+///
+/// The grid is updated every frame with the entities that moved or changed,
+/// and is shared by every system that needs to find entities close to a position.
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<SpatialEntry>>,
+    entities: HashMap<Entity, IVec2>,
+}
+
+impl SpatialGrid {
+    /// Size of the (square) cells in world units
+    pub const CELL_SIZE: f32 = 64.;
+
+    fn cell(position: Vec2) -> IVec2 {
+        (position / Self::CELL_SIZE).floor().as_ivec2()
+    }
+
+    pub fn insert(&mut self, entry: SpatialEntry) {
+        self.remove(entry.entity);
+
+        let cell = Self::cell(entry.position.truncate());
+        self.cells.entry(cell).or_default().push(entry);
+        self.entities.insert(entry.entity, cell);
+    }
+
+    pub fn remove(&mut self, entity: Entity) {
+        if let Some(cell) = self.entities.remove(&entity) {
+            if let Some(entries) = self.cells.get_mut(&cell) {
+                entries.retain(|e| e.entity != entity);
+                if entries.is_empty() {
+                    self.cells.remove(&cell);
+                }
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.entities.clear();
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &SpatialEntry> {
+        self.cells.values().flatten()
+    }
+
+    /// Entries at a distance <= `radius` from `position`
+    pub fn within(&self, position: Vec3, radius: f32) -> impl Iterator<Item = &SpatialEntry> {
+        let min = Self::cell(position.truncate() - radius);
+        let max = Self::cell(position.truncate() + radius);
+
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |e| e.distance(position) <= radius)
+    }
+
+    /// Units at a distance <= `radius` from `position`
+    pub fn units_within(
+        &self,
+        position: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (&SpatialEntry, &Unit)> {
+        self.within(position, radius).filter_map(|e| e.unit().map(|u| (e, u)))
+    }
+
+    /// Buildings at a distance <= `radius` from `position`
+    pub fn buildings_within(
+        &self,
+        position: Vec3,
+        radius: f32,
+    ) -> impl Iterator<Item = (&SpatialEntry, &Building)> {
+        self.within(position, radius).filter_map(|e| e.building().map(|b| (e, b)))
+    }
+
+    /// Closest entry of another color than `color` within `radius`
+    pub fn nearest_enemy(
+        &self,
+        position: Vec3,
+        color: PlayerColor,
+        radius: f32,
+    ) -> Option<&SpatialEntry> {
+        self.within(position, radius)
+            .filter(|e| e.color() != color)
+            .min_by(|a, b| a.distance(position).total_cmp(&b.distance(position)))
+    }
+
+    /// Entries that satisfy `filter`, sorted by their distance to `position`
+    pub fn closest(
+        &self,
+        position: Vec3,
+        filter: impl Fn(&SpatialEntry) -> bool,
+    ) -> Vec<&SpatialEntry> {
+        let mut entries: Vec<_> = self.iter().filter(|e| filter(e)).collect();
+        entries.sort_by(|a, b| a.distance(position).total_cmp(&b.distance(position)));
+        entries
+    }
+}
+
+pub fn update_spatial_grid(
+    unit_q: Query<(Entity, &Transform, &Unit), Or<(Changed<Transform>, Changed<Unit>)>>,
+    building_q: Query<(Entity, &Transform, &Building), Or<(Changed<Transform>, Changed<Building>)>>,
+    mut removed_units: RemovedComponents<Unit>,
+    mut removed_buildings: RemovedComponents<Building>,
+    mut grid: ResMut<SpatialGrid>,
+) {
+    for entity in removed_units.read().chain(removed_buildings.read()) {
+        grid.remove(entity);
+    }
+
+    for (entity, t, unit) in &unit_q {
+        grid.insert(SpatialEntry {
+            entity,
+            position: t.translation,
+            kind: SpatialKind::Unit(*unit),
+        });
+    }
+
+    for (entity, t, building) in &building_q {
+        grid.insert(SpatialEntry {
+            entity,
+            position: t.translation,
+            kind: SpatialKind::Building(*building),
+        });
+    }
+}
+
+pub fn clear_spatial_grid(mut grid: ResMut<SpatialGrid>) {
+    grid.clear();
+}
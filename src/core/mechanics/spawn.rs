@@ -1,24 +1,28 @@
 use crate::core::assets::WorldAssets;
+use crate::core::balance::balance;
 use crate::core::constants::*;
-use crate::core::map::map::Lane;
+use crate::core::map::map::{Lane, Map, Movement};
 use crate::core::map::systems::MapCmp;
 use crate::core::map::ui::systems::UnitInfoCmp;
 use crate::core::map::utils::SpriteFrameLens;
-use crate::core::mechanics::combat::{Arrow, Projectile};
+use crate::core::mechanics::combat::{Arrow, Damage, Projectile};
 use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::status::StatusEffects;
 #[cfg(not(target_arch = "wasm32"))]
 use crate::core::multiplayer::EntityMap;
 use crate::core::player::Players;
+use crate::core::rng::GameRng;
 use crate::core::settings::PlayerColor;
 use crate::core::states::GameState;
 use crate::core::units::buildings::{Building, BuildingName};
-use crate::core::units::units::{Action, Unit, UnitName};
+use crate::core::units::units::{population, Unit, UnitName, Veterancy};
 use crate::core::utils::cursor;
 use crate::utils::NameFromEnum;
 use bevy::color::palettes::css::{BLACK, LIME};
 use bevy::color::Color;
 use bevy::ecs::children;
 use bevy::math::Vec3;
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use bevy::window::SystemCursorIcon;
 use bevy_tweening::{RepeatCount, Tween, TweenAnim};
@@ -31,6 +35,10 @@ pub struct HealthWrapperCmp;
 #[derive(Component)]
 pub struct HealthCmp;
 
+/// Badge showing the veterancy rank of a unit
+#[derive(Component)]
+pub struct RankCmp;
+
 #[derive(Message)]
 pub struct SpawnBuildingMsg {
     pub color: PlayerColor,
@@ -49,6 +57,11 @@ pub struct SpawnUnitMsg {
     pub unit: UnitName,
     pub position: Option<Vec2>,
     pub on_building: Option<Entity>,
+    pub hero: bool,
+    /// Veterancy carried over from another unit (e.g. when cloning it)
+    pub veterancy: Veterancy,
+    /// Gold paid for the unit, refunded if it can't spawn
+    pub cost: f32,
     pub lane: Option<Lane>,
     pub dust_effect: bool,
     pub entity: Option<Entity>,
@@ -61,6 +74,9 @@ impl SpawnUnitMsg {
             unit,
             position: None,
             on_building: None,
+            hero: false,
+            veterancy: Veterancy::default(),
+            cost: 0.,
             lane: None,
             dust_effect: false,
             entity: None,
@@ -71,10 +87,14 @@ impl SpawnUnitMsg {
 #[derive(Message)]
 pub struct SpawnArrowMsg {
     pub color: PlayerColor,
+    pub unit: Option<UnitName>,
+    pub shooter: Option<Entity>,
     pub projectile: Projectile,
     pub damage: f32,
+    pub splash: Option<Damage>,
     pub start: Vec2,
     pub destination: Vec2,
+    pub missed: bool,
     pub entity: Option<Entity>,
 }
 
@@ -87,22 +107,10 @@ pub fn spawn_building_message(
     mut spawn_building_msg: MessageReader<SpawnBuildingMsg>,
     mut effct_msg: MessageWriter<EffectMsg>,
     mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
-    assets: Res<WorldAssets>,
 ) {
     for msg in spawn_building_msg.read() {
-        let size = msg.building.size();
-
         let id = commands
             .spawn((
-                Sprite {
-                    image: assets.image(format!(
-                        "{}-{}",
-                        msg.color.to_name(),
-                        msg.building.to_name()
-                    )),
-                    custom_size: Some(size),
-                    ..default()
-                },
                 Transform {
                     translation: msg.position.extend(BUILDINGS_Z),
                     scale: Vec3::splat(BUILDING_SCALE),
@@ -110,25 +118,6 @@ pub fn spawn_building_message(
                 },
                 Building::new(msg.building, msg.color, msg.is_base, msg.health),
                 MapCmp,
-                children![(
-                    Sprite {
-                        color: Color::from(BLACK),
-                        custom_size: Some(Vec2::new(0.5 * size.x, 15.)),
-                        ..default()
-                    },
-                    Transform::from_xyz(0., size.y * 0.5, EFFECT_Z - 0.2),
-                    Visibility::Hidden,
-                    HealthWrapperCmp,
-                    children![(
-                        Sprite {
-                            color: Color::from(LIME),
-                            custom_size: Some(Vec2::new(0.49 * size.x, 13.)),
-                            ..default()
-                        },
-                        Transform::from_xyz(0., 0., EFFECT_Z - 0.1),
-                        HealthCmp,
-                    )],
-                )],
             ))
             .id();
 
@@ -143,6 +132,9 @@ pub fn spawn_building_message(
                     unit: UnitName::Archer,
                     position: Some(msg.position + pos),
                     on_building: Some(id),
+                    hero: false,
+                    veterancy: Veterancy::default(),
+                    cost: 0.,
                     lane: None,
                     dust_effect: false,
                     entity: None,
@@ -157,70 +149,174 @@ pub fn spawn_building_message(
     }
 }
 
+pub fn add_building_sprite(
+    mut commands: Commands,
+    building_q: Query<(Entity, &Building), Added<Building>>,
+    assets: Res<WorldAssets>,
+) {
+    for (building_e, building) in &building_q {
+        let size = building.name.size();
+
+        commands.entity(building_e).insert((
+            Sprite {
+                image: assets.image(format!(
+                    "{}-{}",
+                    building.color.to_name(),
+                    building.name.to_name()
+                )),
+                custom_size: Some(size),
+                ..default()
+            },
+            children![(
+                Sprite {
+                    color: Color::from(BLACK),
+                    custom_size: Some(Vec2::new(0.5 * size.x, 15.)),
+                    ..default()
+                },
+                Transform::from_xyz(0., size.y * 0.5, EFFECT_Z - 0.2),
+                Visibility::Hidden,
+                HealthWrapperCmp,
+                children![(
+                    Sprite {
+                        color: Color::from(LIME),
+                        custom_size: Some(Vec2::new(0.49 * size.x, 13.)),
+                        ..default()
+                    },
+                    Transform::from_xyz(0., 0., EFFECT_Z - 0.1),
+                    HealthCmp,
+                )],
+            )],
+        ));
+    }
+}
+
 pub fn spawn_unit_message(
     mut commands: Commands,
     building_q: Query<(&Transform, &Building)>,
-    players: Res<Players>,
+    unit_q: Query<&Unit>,
+    mut players: ResMut<Players>,
+    map: Res<Map>,
+    mut rng: ResMut<GameRng>,
     #[cfg(not(target_arch = "wasm32"))] mut entity_map: ResMut<EntityMap>,
     mut effect_msg: MessageWriter<EffectMsg>,
     mut spawn_unit_msg: MessageReader<SpawnUnitMsg>,
-    assets: Res<WorldAssets>,
 ) {
+    // Population of every player, including the units spawned during this step
+    let mut populations = HashMap::new();
+
     for msg in spawn_unit_msg.read() {
-        let unit = msg.unit;
-        let action = Action::default();
+        // Skip units over the population cap, except those mirrored from the host,
+        // spawned on buildings and heroes
+        if msg.entity.is_none() && msg.on_building.is_none() && !msg.hero {
+            let current = populations
+                .entry(msg.color)
+                .or_insert_with(|| population(unit_q.iter(), msg.color));
 
-        let atlas = assets.atlas(format!(
-            "{}-{}-{}",
-            msg.color.to_name(),
-            unit.to_name(),
-            action.to_name()
-        ));
+            if *current + msg.unit.weight() > balance().population {
+                // Refund through the earned gold, since that's what the host syncs
+                players.get_by_color_mut(msg.color).gold_earned += msg.cost;
+                continue;
+            }
+
+            *current += msg.unit.weight();
+        }
 
         // Determine the spawning translation
-        // If not provided, use the default position at the door of the base
+        // If not provided, use the default position at the door of the base,
+        // or the start of the sea lane for naval units
+        let base = building_q.iter().find(|(_, b)| b.color == msg.color && b.is_base);
         let translation = if let Some(pos) = msg.position {
             Some(pos.extend(UNITS_Z))
         } else {
-            building_q
-                .iter()
-                .find(|(_, b)| b.color == msg.color && b.is_base)
-                .map(|(t, _)| Vec3::new(t.translation.x, t.translation.y - 70., UNITS_Z))
+            base.map(|(t, _)| Vec3::new(t.translation.x, t.translation.y - 70., UNITS_Z))
         };
 
-        if let Some(translation) = translation {
+        if let Some(mut translation) = translation {
+            let player = players.get_by_color(msg.color);
+            let mut unit = Unit::new(msg.unit, player, msg.lane, msg.on_building, &map, &mut rng);
+            unit.hero = msg.hero;
+            unit.veterancy = msg.veterancy;
+            unit.health = unit.max_health(player);
+
+            if msg.position.is_none() && map.movement(unit.name.movement()) == Movement::Water {
+                let start = map.lane(&unit.lane, player.side, Movement::Water)[0];
+                translation = map.tile_to_world(start).extend(UNITS_Z);
+            }
+
+            translation.z = unit.name.z();
+
             let id = commands
                 .spawn((
-                    Sprite {
-                        image: atlas.image,
-                        texture_atlas: Some(atlas.atlas),
-                        custom_size: Some(Vec2::splat(unit.size())),
-                        flip_x: players.me.color != msg.color,
-                        ..default()
-                    },
                     Transform {
                         translation,
-                        scale: Vec3::splat(UNIT_SCALE),
+                        scale: Vec3::splat(if unit.hero {
+                            HERO_SCALE
+                        } else {
+                            UNIT_SCALE
+                        }),
                         ..default()
                     },
-                    TweenAnim::new(
-                        Tween::new(
-                            EaseFunction::Linear,
-                            Duration::from_millis(FRAME_RATE * unit.frames(action) as u64),
-                            SpriteFrameLens(atlas.last_index),
-                        )
-                        .with_repeat_count(RepeatCount::Infinite),
-                    ),
-                    Pickable::default(),
-                    Unit::new(unit, players.get_by_color(msg.color), msg.lane, msg.on_building),
+                    unit,
+                    StatusEffects::default(),
                     MapCmp,
-                    children![(
+                ))
+                .id();
+
+            if msg.dust_effect {
+                effect_msg.write(EffectMsg::dust(id));
+            }
+
+            #[cfg(not(target_arch = "wasm32"))]
+            if let Some(entity) = msg.entity {
+                entity_map.insert(entity, id);
+            }
+        }
+    }
+}
+
+pub fn add_unit_sprite(
+    mut commands: Commands,
+    unit_q: Query<(Entity, &Unit), Added<Unit>>,
+    players: Res<Players>,
+    assets: Res<WorldAssets>,
+) {
+    for (unit_e, unit) in &unit_q {
+        let (name, action) = (unit.name, unit.action);
+
+        let atlas = assets.atlas(format!(
+            "{}-{}-{}",
+            unit.color.to_name(),
+            name.to_name(),
+            action.to_name()
+        ));
+
+        commands
+            .entity(unit_e)
+            .insert((
+                Sprite {
+                    image: atlas.image,
+                    texture_atlas: Some(atlas.atlas),
+                    custom_size: Some(Vec2::splat(name.size())),
+                    flip_x: players.me.color != unit.color,
+                    ..default()
+                },
+                TweenAnim::new(
+                    Tween::new(
+                        EaseFunction::Linear,
+                        Duration::from_millis(FRAME_RATE * name.frames(action) as u64),
+                        SpriteFrameLens(atlas.last_index),
+                    )
+                    .with_repeat_count(RepeatCount::Infinite),
+                ),
+                Pickable::default(),
+                children![
+                    (
                         Sprite {
                             color: Color::from(BLACK),
                             custom_size: Some(4. + HEALTH_SIZE),
                             ..default()
                         },
-                        Transform::from_xyz(0., unit.world_size() * 0.7, 0.1),
+                        Transform::from_xyz(0., name.world_size() * 0.7, 0.1),
                         Visibility::Hidden,
                         HealthWrapperCmp,
                         children![(
@@ -232,41 +328,45 @@ pub fn spawn_unit_message(
                             Transform::from_xyz(0., 0., 0.2),
                             HealthCmp,
                         )],
-                    )],
-                ))
-                .observe(cursor::<Over>(SystemCursorIcon::Pointer))
-                .observe(cursor::<Out>(SystemCursorIcon::Default))
-                .observe(
-                    move |_: On<Pointer<Click>>,
-                          mut info_q: Query<(&mut Visibility, &UnitInfoCmp)>,
-                          game_state: Res<State<GameState>>,
-                          mut next_game_state: ResMut<NextState<GameState>>| {
-                        if matches!(
-                            game_state.get(),
-                            GameState::Playing | GameState::Paused | GameState::UnitInfo
-                        ) {
-                            for (mut v, i) in info_q.iter_mut() {
-                                *v = if **i == unit {
-                                    Visibility::Inherited
-                                } else {
-                                    Visibility::Hidden
-                                }
+                    ),
+                    (
+                        Sprite {
+                            image: assets.image("sword"),
+                            custom_size: Some(RANK_SIZE),
+                            ..default()
+                        },
+                        Transform::from_xyz(
+                            0.,
+                            name.world_size() * 0.7 + HEALTH_SIZE.y + RANK_SIZE.y * 0.5,
+                            0.1
+                        ),
+                        Visibility::Hidden,
+                        RankCmp,
+                    )
+                ],
+            ))
+            .observe(cursor::<Over>(SystemCursorIcon::Pointer))
+            .observe(cursor::<Out>(SystemCursorIcon::Default))
+            .observe(
+                move |_: On<Pointer<Click>>,
+                      mut info_q: Query<(&mut Visibility, &UnitInfoCmp)>,
+                      game_state: Res<State<GameState>>,
+                      mut next_game_state: ResMut<NextState<GameState>>| {
+                    if matches!(
+                        game_state.get(),
+                        GameState::Playing | GameState::Paused | GameState::UnitInfo
+                    ) {
+                        for (mut v, i) in info_q.iter_mut() {
+                            *v = if **i == name {
+                                Visibility::Inherited
+                            } else {
+                                Visibility::Hidden
                             }
-                            next_game_state.set(GameState::UnitInfo);
                         }
-                    },
-                )
-                .id();
-
-            if msg.dust_effect {
-                effect_msg.write(EffectMsg::dust(id));
-            }
-
-            #[cfg(not(target_arch = "wasm32"))]
-            if let Some(entity) = msg.entity {
-                entity_map.insert(entity, id);
-            }
-        }
+                        next_game_state.set(GameState::UnitInfo);
+                    }
+                },
+            );
     }
 }
 
@@ -274,48 +374,32 @@ pub fn spawn_arrow_message(
     mut commands: Commands,
     #[cfg(not(target_arch = "wasm32"))] mut entity_map: ResMut<EntityMap>,
     mut spawn_arrow_msg: MessageReader<SpawnArrowMsg>,
-    assets: Res<WorldAssets>,
 ) {
     for msg in spawn_arrow_msg.read() {
-        let sprite = if msg.projectile.animation() {
-            let atlas = assets.atlas(msg.projectile.to_lowername());
-            Sprite {
-                image: atlas.image,
-                texture_atlas: Some(atlas.atlas),
-                ..default()
-            }
-        } else {
-            Sprite {
-                image: assets.image(msg.projectile.to_lowername()),
-                ..default()
-            }
-        };
-
         let id = commands
             .spawn((
-                sprite,
                 Transform {
                     translation: msg.start.extend(ARROW_Z),
                     rotation: Quat::from_rotation_z(FRAC_PI_4 + msg.projectile.angle()),
                     scale: Vec3::splat(UNIT_SCALE),
                 },
-                Arrow::new(msg.color, msg.projectile, msg.damage, msg.start, msg.destination),
+                Arrow {
+                    shooter: msg.shooter,
+                    splash: msg.splash,
+                    missed: msg.missed,
+                    ..Arrow::new(
+                        msg.color,
+                        msg.unit,
+                        msg.projectile,
+                        msg.damage,
+                        msg.start,
+                        msg.destination,
+                    )
+                },
                 MapCmp,
             ))
             .id();
 
-        if msg.projectile.animation() {
-            let atlas = assets.atlas(msg.projectile.to_lowername());
-            commands.entity(id).insert(TweenAnim::new(
-                Tween::new(
-                    EaseFunction::Linear,
-                    Duration::from_millis(FRAME_RATE * (atlas.last_index + 1) as u64),
-                    SpriteFrameLens(atlas.last_index),
-                )
-                .with_repeat_count(RepeatCount::Infinite),
-            ));
-        }
-
         #[cfg(not(target_arch = "wasm32"))]
         if let Some(entity) = msg.entity {
             entity_map.insert(entity, id);
@@ -323,6 +407,38 @@ pub fn spawn_arrow_message(
     }
 }
 
+pub fn add_arrow_sprite(
+    mut commands: Commands,
+    arrow_q: Query<(Entity, &Arrow), Added<Arrow>>,
+    assets: Res<WorldAssets>,
+) {
+    for (arrow_e, arrow) in &arrow_q {
+        if arrow.projectile.animation() {
+            let atlas = assets.atlas(arrow.projectile.to_lowername());
+            commands.entity(arrow_e).insert((
+                Sprite {
+                    image: atlas.image,
+                    texture_atlas: Some(atlas.atlas),
+                    ..default()
+                },
+                TweenAnim::new(
+                    Tween::new(
+                        EaseFunction::Linear,
+                        Duration::from_millis(FRAME_RATE * (atlas.last_index + 1) as u64),
+                        SpriteFrameLens(atlas.last_index),
+                    )
+                    .with_repeat_count(RepeatCount::Infinite),
+                ),
+            ));
+        } else {
+            commands.entity(arrow_e).insert(Sprite {
+                image: assets.image(arrow.projectile.to_lowername()),
+                ..default()
+            });
+        }
+    }
+}
+
 pub fn despawn_message(
     mut commands: Commands,
     unit_q: Query<(Entity, &Unit)>,
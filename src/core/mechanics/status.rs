@@ -0,0 +1,250 @@
+use crate::core::balance::balance;
+use crate::core::boosts::Boost;
+use crate::core::mechanics::combat::ApplyDamageMsg;
+use crate::core::player::{Player, Players, Strategy};
+use crate::core::settings::Settings;
+use crate::core::units::units::{Action, Unit, UnitName};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Status {
+    /// Multiplies the movement speed by the value
+    Slow,
+    /// The unit can't move nor attack
+    Stun,
+    /// Deals the value as damage every second
+    Burn,
+    /// Deals the value as damage every second, for every stack
+    Poison,
+    /// Absorbs damage until the value is depleted
+    Shield,
+    /// Multiplies the movement and attack speed by the value
+    Haste,
+    /// Reduces the armor by the value
+    ArmorShred,
+}
+
+impl Status {
+    pub fn is_damage_over_time(&self) -> bool {
+        matches!(self, Status::Burn | Status::Poison)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct StatusEffect {
+    pub status: Status,
+    pub value: f32,
+    pub stacks: u32,
+    /// Unit that applied the status
+    pub source: Option<UnitName>,
+    /// Seconds until the status wears off
+    pub remaining: f32,
+    /// Seconds since the last damage tick
+    pub elapsed: f32,
+}
+
+impl StatusEffect {
+    pub const TICK_SECS: f32 = 1.;
+
+    pub fn new(status: Status, value: f32, duration: f32) -> Self {
+        Self {
+            status,
+            value,
+            stacks: 1,
+            source: None,
+            remaining: duration,
+            elapsed: 0.,
+        }
+    }
+
+    pub fn with_source(mut self, source: UnitName) -> Self {
+        self.source = Some(source);
+        self
+    }
+}
+
+/// Temporary modifiers on a unit
+#[derive(Component, Clone, Debug, Default, Deref, Serialize, Deserialize)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn get(&self, status: Status) -> Option<&StatusEffect> {
+        self.iter().find(|e| e.status == status)
+    }
+
+    pub fn has(&self, status: Status) -> bool {
+        self.get(status).is_some()
+    }
+
+    /// Value of a multiplying status, or 1 when not active
+    fn multiplier(&self, status: Status) -> f32 {
+        self.get(status).map_or(1., |e| e.value)
+    }
+
+    /// Add a status, replacing the value and refreshing the duration if it's already active
+    pub fn add(&mut self, effect: StatusEffect) {
+        if let Some(current) = self.0.iter_mut().find(|e| e.status == effect.status) {
+            current.value = effect.value;
+            current.source = effect.source;
+            current.remaining = current.remaining.max(effect.remaining);
+        } else {
+            self.0.push(effect);
+        }
+    }
+
+    /// Add a stack of poison from `source`, replacing the poison of other units
+    pub fn add_poison(&mut self, source: UnitName) {
+        let Some(definition) = source.poison() else {
+            return;
+        };
+
+        match self.0.iter_mut().find(|e| e.status == Status::Poison) {
+            Some(poison) if poison.source == Some(source) => {
+                poison.stacks = (poison.stacks + 1).min(definition.max_stacks);
+                poison.remaining = definition.duration;
+            },
+            _ => {
+                self.remove(|s| s == Status::Poison);
+                self.0.push(
+                    StatusEffect::new(Status::Poison, definition.damage, definition.duration)
+                        .with_source(source),
+                );
+            },
+        }
+    }
+
+    pub fn remove(&mut self, f: impl Fn(Status) -> bool) {
+        self.0.retain(|e| !f(e.status));
+    }
+
+    /// Absorb damage with the shield, returning the damage that passes through
+    pub fn absorb(&mut self, damage: f32) -> f32 {
+        let Some(shield) = self.0.iter_mut().find(|e| e.status == Status::Shield) else {
+            return damage;
+        };
+
+        let absorbed = shield.value.min(damage);
+        shield.value -= absorbed;
+        if shield.value <= 0. {
+            self.remove(|s| s == Status::Shield);
+        }
+
+        damage - absorbed
+    }
+}
+
+/// Stats of a unit after applying the strategy and boosts of its player and its statuses
+///
+/// This is the only place where the base stats of a unit are modified.
+pub struct Stats {
+    pub armor: f32,
+    pub magic_resist: f32,
+    pub speed: f32,
+    /// Multiplier of the speed of attack (and heal) cycles
+    pub attack_speed: f32,
+}
+
+impl Stats {
+    pub fn new(unit: &Unit, statuses: &StatusEffects, player: &Player) -> Self {
+        let strategies = balance().strategies.clone();
+
+        let mut armor = unit.name.armor();
+        let mut magic_resist = unit.name.magic_resist();
+
+        if unit.action == Action::Guard {
+            armor *= strategies.guard_defense;
+            magic_resist *= strategies.guard_defense;
+        }
+
+        // Units in berserk mode lose defense, except those on buildings
+        if player.strategy == Strategy::Berserk && unit.on_building.is_none() {
+            armor *= strategies.berserk_defense;
+            magic_resist *= strategies.berserk_defense;
+        }
+
+        if let Some(shred) = statuses.get(Status::ArmorShred) {
+            armor = (armor - shred.value).max(0.);
+        }
+
+        let mut speed = unit.name.speed()
+            * statuses.multiplier(Status::Slow)
+            * statuses.multiplier(Status::Haste);
+
+        if player.has_boost(Boost::Run) {
+            speed *= Boost::Run.value();
+        }
+
+        if player.strategy == Strategy::March {
+            speed *= strategies.march_speed;
+        }
+
+        // Units in berserk mode attack faster
+        let mut attack_speed = statuses.multiplier(Status::Haste);
+        if player.strategy == Strategy::Berserk && matches!(unit.action, Action::Attack(_)) {
+            attack_speed *= strategies.berserk_attack_speed;
+        }
+
+        if statuses.has(Status::Stun) {
+            speed = 0.;
+            attack_speed = 0.;
+        }
+
+        Self {
+            armor,
+            magic_resist,
+            speed,
+            attack_speed,
+        }
+    }
+}
+
+pub fn update_status_effects(
+    mut unit_q: Query<(Entity, &Unit, &mut StatusEffects)>,
+    settings: Res<Settings>,
+    players: Res<Players>,
+    time: Res<Time>,
+    mut apply_damage_msg: MessageWriter<ApplyDamageMsg>,
+) {
+    let delta = time.delta_secs() * settings.speed;
+
+    for (unit_e, unit, mut statuses) in &mut unit_q {
+        // Frozen units keep attacking, but can't move while the boost is active,
+        // including those spawned after the activation
+        let player = players.get_by_color(unit.color);
+        let enemy = players.get_by_side(player.side.opposite());
+        if let Some(frozen) = enemy.boosts.iter().find(|b| b.name == Boost::Frozen && b.active) {
+            statuses.add(StatusEffect::new(Status::Slow, 0., frozen.timer.remaining_secs()));
+        }
+
+        if statuses.is_empty() {
+            continue;
+        }
+
+        // Damage over time ignores armor and magic resist, but is reduced by ArmorGain
+        let reduction = if player.has_boost(Boost::ArmorGain) {
+            Boost::ArmorGain.value()
+        } else {
+            1.
+        };
+
+        for effect in statuses.0.iter_mut() {
+            effect.remaining -= delta;
+
+            if effect.status.is_damage_over_time() {
+                effect.elapsed += delta;
+                while effect.elapsed >= StatusEffect::TICK_SECS {
+                    effect.elapsed -= StatusEffect::TICK_SECS;
+                    apply_damage_msg.write(ApplyDamageMsg::new(
+                        unit_e,
+                        effect.value * effect.stacks as f32 * reduction,
+                        effect.source,
+                        None,
+                    ));
+                }
+            }
+        }
+
+        statuses.0.retain(|e| e.remaining > 0.);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::core::audio::PlayAudioMsg;
+use crate::core::player::Players;
+use crate::core::replay::{PlayerInput, Replay};
+use crate::core::rng::GameRng;
+use crate::core::settings::Settings;
+use crate::core::simulation::Step;
+use crate::core::units::units::UnitName;
+use crate::core::utils::ClientId;
+use bevy::prelude::*;
+use rand::prelude::IndexedRandom;
+use strum::IntoEnumIterator;
+
+#[derive(Message)]
+pub struct UnlockUnitMsg {
+    pub id: ClientId,
+    pub unit: UnitName,
+}
+
+impl UnlockUnitMsg {
+    pub fn new(id: ClientId, unit: UnitName) -> Self {
+        Self {
+            id,
+            unit,
+        }
+    }
+}
+
+pub fn unlock_message(
+    mut unlock_unit_msg: MessageReader<UnlockUnitMsg>,
+    mut play_audio_msg: MessageWriter<PlayAudioMsg>,
+    mut players: ResMut<Players>,
+    mut replay: Option<ResMut<Replay>>,
+    settings: Res<Settings>,
+    step: Res<Step>,
+) {
+    for msg in unlock_unit_msg.read() {
+        if let Some(replay) = &mut replay {
+            replay.record(*step, PlayerInput::UnlockUnit(msg.id, msg.unit));
+        }
+
+        let player = players.get_by_id_mut(msg.id);
+
+        // Reject units whose requirements aren't met yet
+        if !player.unlock(msg.unit, settings.economy) && player.is_human() {
+            play_audio_msg.write(PlayAudioMsg::new("error"));
+        }
+    }
+}
+
+/// Advance the match time and let the AI unlock units as soon as they're available
+pub fn update_tech(
+    mut players: ResMut<Players>,
+    mut rng: ResMut<GameRng>,
+    settings: Res<Settings>,
+    time: Res<Time>,
+) {
+    for player in players.iter_mut() {
+        player.elapsed += time.delta_secs() * settings.speed;
+
+        // AI players with a fixed pool of units don't need the tech tree
+        if !player.is_human() && player.pool.units.is_none() {
+            let units: Vec<UnitName> =
+                UnitName::iter().filter(|u| player.can_unlock(*u, settings.economy)).collect();
+
+            if let Some(unit) = units.choose(&mut *rng) {
+                player.unlock(*unit, settings.economy);
+            }
+        }
+    }
+}
@@ -11,6 +11,7 @@ use bevy::window::SystemCursorIcon;
 use {
     crate::core::network::{new_renet_client, new_renet_server, Ip},
     crate::core::persistence::{LoadGameMsg, SaveGameMsg},
+    crate::core::replay::LoadReplayMsg,
 };
 
 #[derive(Component)]
@@ -21,6 +22,8 @@ pub enum MenuBtn {
     Singleplayer,
     #[cfg(not(target_arch = "wasm32"))]
     Multiplayer,
+    #[cfg(not(target_arch = "wasm32"))]
+    Replays,
     NewGame,
     #[cfg(not(target_arch = "wasm32"))]
     LoadGame,
@@ -45,6 +48,9 @@ pub struct LobbyTextCmp;
 #[derive(Component)]
 pub struct IpTextCmp;
 
+#[derive(Component)]
+pub struct SeedTextCmp;
+
 pub fn on_click_menu_button(
     event: On<Pointer<Click>>,
     mut commands: Commands,
@@ -53,6 +59,7 @@ pub fn on_click_menu_button(
     mut start_new_game_msg: MessageWriter<StartNewGameMsg>,
     #[cfg(not(target_arch = "wasm32"))] mut load_game_msg: MessageWriter<LoadGameMsg>,
     #[cfg(not(target_arch = "wasm32"))] mut save_game_msg: MessageWriter<SaveGameMsg>,
+    #[cfg(not(target_arch = "wasm32"))] mut load_replay_msg: MessageWriter<LoadReplayMsg>,
     app_state: Res<State<AppState>>,
     game_state: Res<State<GameState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
@@ -72,6 +79,10 @@ pub fn on_click_menu_button(
         MenuBtn::Multiplayer => {
             next_app_state.set(AppState::MultiPlayerMenu);
         },
+        #[cfg(not(target_arch = "wasm32"))]
+        MenuBtn::Replays => {
+            load_replay_msg.write(LoadReplayMsg);
+        },
         MenuBtn::NewGame => {
             start_new_game_msg.write(StartNewGameMsg);
         },
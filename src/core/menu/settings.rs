@@ -3,8 +3,10 @@ use std::fmt::Debug;
 use crate::core::assets::WorldAssets;
 use crate::core::audio::ChangeAudioMsg;
 use crate::core::constants::*;
+use crate::core::map::map::MapName;
 use crate::core::menu::utils::add_text;
 use crate::core::settings::{AudioState, PlayerColor, Settings};
+use crate::core::units::heroes::HeroName;
 use crate::core::utils::cursor;
 use crate::utils::NameFromEnum;
 use bevy::prelude::*;
@@ -17,6 +19,14 @@ pub enum SettingsBtn {
     Purple,
     Red,
     Yellow,
+    Paladin,
+    Ranger,
+    Oracle,
+    Island,
+    Duel,
+    Crossroads,
+    Classic,
+    Economy,
     Mute,
     Sound,
     Music,
@@ -31,6 +41,14 @@ fn match_setting(button: &SettingsBtn, settings: &Settings) -> bool {
         SettingsBtn::Purple => settings.color == PlayerColor::Purple,
         SettingsBtn::Red => settings.color == PlayerColor::Red,
         SettingsBtn::Yellow => settings.color == PlayerColor::Yellow,
+        SettingsBtn::Paladin => settings.hero == HeroName::Paladin,
+        SettingsBtn::Ranger => settings.hero == HeroName::Ranger,
+        SettingsBtn::Oracle => settings.hero == HeroName::Oracle,
+        SettingsBtn::Island => settings.map == MapName::Island,
+        SettingsBtn::Duel => settings.map == MapName::Duel,
+        SettingsBtn::Crossroads => settings.map == MapName::Crossroads,
+        SettingsBtn::Classic => !settings.economy,
+        SettingsBtn::Economy => settings.economy,
         SettingsBtn::Mute => settings.audio == AudioState::Mute,
         SettingsBtn::Sound => settings.audio == AudioState::Sound,
         SettingsBtn::Music => settings.audio == AudioState::Music,
@@ -64,6 +82,14 @@ pub fn on_click_label_button(
         SettingsBtn::Purple => settings.color = PlayerColor::Purple,
         SettingsBtn::Red => settings.color = PlayerColor::Red,
         SettingsBtn::Yellow => settings.color = PlayerColor::Yellow,
+        SettingsBtn::Paladin => settings.hero = HeroName::Paladin,
+        SettingsBtn::Ranger => settings.hero = HeroName::Ranger,
+        SettingsBtn::Oracle => settings.hero = HeroName::Oracle,
+        SettingsBtn::Island => settings.map = MapName::Island,
+        SettingsBtn::Duel => settings.map = MapName::Duel,
+        SettingsBtn::Crossroads => settings.map = MapName::Crossroads,
+        SettingsBtn::Classic => settings.economy = false,
+        SettingsBtn::Economy => settings.economy = true,
         SettingsBtn::Mute => {
             settings.audio = AudioState::Mute;
             change_audio_msg.write(ChangeAudioMsg(Some(AudioState::Mute)));
@@ -88,6 +114,41 @@ pub fn on_click_label_button(
     }
 }
 
+/// Spawn the labels to select the map and mode of a new game
+pub fn spawn_new_game_labels(
+    parent: &mut ChildSpawnerCommands,
+    settings: &Settings,
+    assets: &WorldAssets,
+    window: &Window,
+) {
+    parent
+        .spawn(Node {
+            width: percent(40.),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        })
+        .with_children(|parent| {
+            spawn_label(
+                parent,
+                "Map",
+                vec![SettingsBtn::Island, SettingsBtn::Duel, SettingsBtn::Crossroads],
+                settings,
+                assets,
+                window,
+            );
+            spawn_label(
+                parent,
+                "Mode",
+                vec![SettingsBtn::Classic, SettingsBtn::Economy],
+                settings,
+                assets,
+                window,
+            );
+        });
+}
+
 pub fn spawn_label(
     parent: &mut ChildSpawnerCommands,
     title: &str,
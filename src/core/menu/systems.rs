@@ -12,17 +12,22 @@ use {
 use crate::core::assets::WorldAssets;
 use crate::core::boosts::AfterBoostCount;
 use crate::core::constants::*;
-use crate::core::map::map::Map;
+use crate::core::map::map::{Map, Maps};
 use crate::core::mechanics::spawn::SpawnBuildingMsg;
 use crate::core::menu::buttons::*;
-use crate::core::menu::settings::{spawn_label, SettingsBtn};
+use crate::core::menu::settings::{spawn_label, spawn_new_game_labels, SettingsBtn};
 use crate::core::menu::utils::{add_root_node, add_text};
 #[cfg(not(target_arch = "wasm32"))]
 use crate::core::multiplayer::EntityMap;
 use crate::core::player::{Player, Players, Side};
+use crate::core::replay::Replay;
+use crate::core::rng::GameRng;
 use crate::core::settings::{GameMode, PlayerColor, Settings};
 use crate::core::states::{AppState, GameState};
 use crate::core::units::buildings::BuildingName;
+use crate::core::units::heroes::HeroName;
+use rand::prelude::IndexedRandom;
+use strum::IntoEnumIterator;
 
 #[derive(Resource)]
 pub struct Host;
@@ -61,11 +66,14 @@ pub fn setup_menu(
                         spawn_menu_button(parent, MenuBtn::Singleplayer, &assets, &window);
                         #[cfg(not(target_arch = "wasm32"))]
                         spawn_menu_button(parent, MenuBtn::Multiplayer, &assets, &window);
+                        #[cfg(not(target_arch = "wasm32"))]
+                        spawn_menu_button(parent, MenuBtn::Replays, &assets, &window);
                         spawn_menu_button(parent, MenuBtn::Settings, &assets, &window);
                         #[cfg(not(target_arch = "wasm32"))]
                         spawn_menu_button(parent, MenuBtn::Quit, &assets, &window);
                     }
                     AppState::SinglePlayerMenu => {
+                        spawn_new_game_labels(parent, &settings, &assets, &window);
                         spawn_menu_button(parent, MenuBtn::NewGame, &assets, &window);
                         #[cfg(not(target_arch = "wasm32"))]
                         spawn_menu_button(parent, MenuBtn::LoadGame, &assets, &window);
@@ -108,6 +116,7 @@ pub fn setup_menu(
                             ));
 
                             if n_players > 1 {
+                                spawn_new_game_labels(parent, &settings, &assets, &window);
                                 spawn_menu_button(parent, MenuBtn::NewGame, &assets, &window);
                                 spawn_menu_button(parent, MenuBtn::LoadGame, &assets, &window);
                             }
@@ -151,6 +160,18 @@ pub fn setup_menu(
                                     &assets,
                                     &window,
                                 );
+                                spawn_label(
+                                    parent,
+                                    "Hero",
+                                    vec![
+                                        SettingsBtn::Paladin,
+                                        SettingsBtn::Ranger,
+                                        SettingsBtn::Oracle,
+                                    ],
+                                    &settings,
+                                    &assets,
+                                    &window,
+                                );
                                 spawn_label(
                                     parent,
                                     "Audio",
@@ -174,6 +195,16 @@ pub fn setup_menu(
                                     &assets,
                                     &window,
                                 );
+                                parent.spawn((
+                                    add_text(
+                                        seed_text(settings.seed),
+                                        "bold",
+                                        BUTTON_TEXT_SIZE,
+                                        &assets,
+                                        &window,
+                                    ),
+                                    SeedTextCmp,
+                                ));
                             });
 
                         spawn_menu_button(parent, MenuBtn::Back, &assets, &window);
@@ -266,13 +297,68 @@ pub fn update_ip(
     }
 }
 
+fn seed_text(seed: Option<u64>) -> String {
+    match seed {
+        Some(seed) => format!("Seed: {seed}"),
+        None => "Seed: random".to_string(),
+    }
+}
+
+pub fn update_seed(
+    mut text_q: Query<&mut Text, With<SeedTextCmp>>,
+    mut settings: ResMut<Settings>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+) {
+    for key in keyboard.get_just_released() {
+        let digit = match key {
+            KeyCode::Digit0 => 0,
+            KeyCode::Digit1 => 1,
+            KeyCode::Digit2 => 2,
+            KeyCode::Digit3 => 3,
+            KeyCode::Digit4 => 4,
+            KeyCode::Digit5 => 5,
+            KeyCode::Digit6 => 6,
+            KeyCode::Digit7 => 7,
+            KeyCode::Digit8 => 8,
+            KeyCode::Digit9 => 9,
+            KeyCode::Backspace => {
+                // Remove the last digit, going back to a random seed when empty
+                settings.seed = settings.seed.filter(|s| *s >= 10).map(|s| s / 10);
+                continue;
+            },
+            KeyCode::Delete => {
+                settings.seed = None;
+                continue;
+            },
+            _ => continue,
+        };
+
+        let seed = settings.seed.unwrap_or(0);
+        if let Some(seed) = seed.checked_mul(10).and_then(|s| s.checked_add(digit)) {
+            settings.seed = Some(seed);
+        }
+    }
+
+    if let Ok(mut text) = text_q.single_mut() {
+        text.0 = seed_text(settings.seed);
+    }
+}
+
 pub fn setup_game_menu(
     mut commands: Commands,
     host: Option<Res<Host>>,
+    rng: Res<GameRng>,
     assets: Res<WorldAssets>,
     window: Single<&Window>,
 ) {
     commands.spawn((add_root_node(true), MenuCmp)).with_children(|parent| {
+        parent.spawn(add_text(
+            format!("Seed: {}", rng.seed),
+            "bold",
+            SUBTITLE_TEXT_SIZE,
+            &assets,
+            &window,
+        ));
         spawn_menu_button(parent, MenuBtn::Continue, &assets, &window);
         #[cfg(not(target_arch = "wasm32"))]
         {
@@ -350,6 +436,26 @@ pub fn exit_multiplayer_lobby(
     }
 }
 
+/// Spawn the bases of the players, in the order of the map's starting positions
+pub fn spawn_bases(
+    colors: [PlayerColor; 2],
+    map: &Map,
+    spawn_building_msg: &mut MessageWriter<SpawnBuildingMsg>,
+) {
+    for (color, position) in colors.into_iter().zip(map.starting_positions()) {
+        spawn_building_msg.write(SpawnBuildingMsg {
+            color,
+            building: BuildingName::default(),
+            position,
+            is_base: true,
+            health: BuildingName::default().health(),
+            dust_effect: false,
+            with_units: true,
+            entity: None,
+        });
+    }
+}
+
 pub fn start_new_game_message(
     mut commands: Commands,
     mut start_new_game_msg: MessageReader<StartNewGameMsg>,
@@ -357,17 +463,25 @@ pub fn start_new_game_message(
     mut settings: ResMut<Settings>,
     #[cfg(not(target_arch = "wasm32"))] mut server_send_msg: MessageWriter<ServerSendMsg>,
     mut spawn_building_msg: MessageWriter<SpawnBuildingMsg>,
+    maps: Res<Maps>,
     app_state: Res<State<AppState>>,
     mut next_app_state: ResMut<NextState<AppState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
     if !start_new_game_msg.is_empty() {
+        let rng = GameRng::new(settings.seed.unwrap_or_else(rand::random));
+
         let (game_mode, enemy_id, enemy_color) = if *app_state.get() == AppState::SinglePlayerMenu {
             let enemy_color = match settings.color {
                 PlayerColor::Red => PlayerColor::Blue,
                 _ => PlayerColor::Red,
             };
 
+            // Drawn from a copy of the seeded rng, so the same seed picks the same hero
+            // without advancing the match's stream (replays read the pick from the settings)
+            settings.enemy_hero =
+                *HeroName::iter().collect::<Vec<_>>().choose(&mut rng.clone()).unwrap();
+
             (GameMode::SinglePlayer, 1, enemy_color)
         } else {
             #[cfg(not(target_arch = "wasm32"))]
@@ -384,8 +498,12 @@ pub fn start_new_game_message(
 
                 server_send_msg.write(ServerSendMsg::new(
                     ServerMessage::StartGame {
-                        player: Player::new(enemy_id, enemy_color, Side::Right),
+                        player: Player::new(enemy_id, enemy_color, Side::Right)
+                            .with_hero(settings.enemy_hero),
                         enemy_color: settings.color,
+                        map: settings.map,
+                        economy: settings.economy,
+                        seed: rng.seed,
                     },
                     Some(enemy_id),
                 ));
@@ -400,32 +518,24 @@ pub fn start_new_game_message(
         settings.game_mode = game_mode;
         settings.enemy_color = enemy_color;
 
-        // Spawn starting buildings
-        for (color, position) in
-            [settings.color, enemy_color].into_iter().zip(Map::starting_positions())
-        {
-            spawn_building_msg.write(SpawnBuildingMsg {
-                color,
-                building: BuildingName::default(),
-                position,
-                is_base: true,
-                health: BuildingName::default().health(),
-                dust_effect: false,
-                with_units: true,
-                entity: None,
-            });
-        }
+        let map = maps[&settings.map].clone();
+        spawn_bases([settings.color, enemy_color], &map, &mut spawn_building_msg);
 
         settings.reset();
 
+        let players = Players {
+            me: Player::new(0, settings.color, Side::Left).with_hero(settings.hero),
+            enemy: Player::new(enemy_id, enemy_color, Side::Right).with_hero(settings.enemy_hero),
+        };
+
+        commands.insert_resource(Replay::new(rng.seed, &settings, &players));
         commands.insert_resource(Host);
+        commands.insert_resource(map);
+        commands.insert_resource(rng);
         commands.insert_resource(AfterBoostCount::default());
         #[cfg(not(target_arch = "wasm32"))]
         commands.insert_resource(EntityMap::default());
-        commands.insert_resource(Players {
-            me: Player::new(0, settings.color, Side::Left),
-            enemy: Player::new(enemy_id, enemy_color, Side::Right),
-        });
+        commands.insert_resource(players);
         next_game_state.set(GameState::default());
         next_app_state.set(AppState::Game);
 
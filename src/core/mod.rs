@@ -1,5 +1,8 @@
 mod assets;
 mod audio;
+mod balance;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
 mod boosts;
 mod camera;
 mod constants;
@@ -13,33 +16,39 @@ mod network;
 #[cfg(not(target_arch = "wasm32"))]
 mod persistence;
 mod player;
+mod replay;
+mod rng;
 mod settings;
+mod simulation;
 mod states;
 mod systems;
 mod units;
 mod utils;
 
-use crate::core::assets::WorldAssets;
+use crate::core::assets::{DataLoader, WorldAssets};
 use crate::core::audio::*;
+use crate::core::balance::{load_balance, update_balance, Balance};
 use crate::core::boosts::*;
 use crate::core::camera::*;
 use crate::core::constants::{UPDATE_TIMER, WATER_COLOR};
-use crate::core::map::map::Map;
-use crate::core::map::systems::{draw_map, setup_end_game, MapCmp};
+use crate::core::map::map::Maps;
+use crate::core::map::systems::{draw_map, load_maps, setup_end_game, update_maps, MapCmp};
 use crate::core::map::ui::boosts::{setup_after_boost, setup_boost_selection};
 use crate::core::map::ui::scrolling::{on_scroll_handler, send_scroll_events};
 use crate::core::map::ui::systems::*;
-use crate::core::mechanics::combat::{apply_damage_message, resolve_attack, ApplyDamageMsg};
 use crate::core::mechanics::effects::*;
-use crate::core::mechanics::movement::apply_movement;
-use crate::core::mechanics::queue::*;
+use crate::core::mechanics::queue::queue_message;
 use crate::core::mechanics::spawn::*;
 use crate::core::menu::buttons::MenuCmp;
 use crate::core::menu::systems::*;
-use crate::core::settings::Settings;
+use crate::core::replay::*;
+use crate::core::simulation::*;
 use crate::core::states::{AppState, GameState};
 use crate::core::systems::*;
-use crate::core::units::systems::{update_buildings, update_units};
+use crate::core::units::definitions::{
+    load_unit_definitions, update_unit_definitions, UnitDefinitions,
+};
+use crate::core::units::systems::{update_buildings, update_guard_animation, update_units};
 use crate::core::utils::despawn;
 use bevy::prelude::*;
 use bevy::time::common_conditions::on_timer;
@@ -55,86 +64,37 @@ use {
 
 pub struct GamePlugin;
 
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct InGameSet;
-
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct InPlayingSet;
-
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct InPlayingOrPausedSet;
-
-#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
-struct InPlayingOrPausedOrEndSet;
-
-macro_rules! configure_stages {
-    ($app:expr, $set:ident, $run_if:expr) => {
-        $app.configure_sets(First, $set.run_if($run_if))
-            .configure_sets(PreUpdate, $set.run_if($run_if))
-            .configure_sets(Update, $set.run_if($run_if))
-            .configure_sets(PostUpdate, $set.run_if($run_if))
-            .configure_sets(Last, $set.run_if($run_if));
-    };
-}
-
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        app.add_plugins(SimulationPlugin);
+
         app
-            // States
-            .init_state::<AppState>()
-            .init_state::<GameState>()
             // Messages
-            .add_message::<PlayAudioMsg>()
             .add_message::<PauseAudioMsg>()
             .add_message::<StopAudioMsg>()
             .add_message::<MuteAudioMsg>()
             .add_message::<ChangeAudioMsg>()
             .add_message::<StartNewGameMsg>()
-            .add_message::<QueueUnitMsg>()
-            .add_message::<SpawnBuildingMsg>()
-            .add_message::<SpawnUnitMsg>()
-            .add_message::<SpawnArrowMsg>()
-            .add_message::<DespawnMsg>()
-            .add_message::<ActivateBoostMsg>()
-            .add_message::<ApplyDamageMsg>()
-            .add_message::<EffectMsg>()
             .add_message::<DeferredEffectMsg>()
+            .add_message::<NoticeMsg>()
+            .add_message::<StartReplayMsg>()
             // Resources
             .insert_resource(ClearColor(WATER_COLOR))
             .init_resource::<WorldAssets>()
             .init_resource::<PlayingAudio>()
-            .init_resource::<Settings>()
-            .init_resource::<Map>()
+            .init_resource::<Maps>()
+            // Assets
+            .init_asset::<UnitDefinitions>()
+            .init_asset::<Balance>()
+            .init_asset_loader::<DataLoader<UnitDefinitions>>()
+            .init_asset_loader::<DataLoader<Balance>>()
             // Observers
             .add_observer(on_scroll_handler);
 
-        // Sets
-        configure_stages!(app, InGameSet, in_state(AppState::Game));
-        configure_stages!(
-            app,
-            InPlayingSet,
-            in_state(GameState::Playing).and(in_state(AppState::Game))
-        );
-        configure_stages!(
-            app,
-            InPlayingOrPausedSet,
-            in_state(GameState::Playing)
-                .or(in_state(GameState::Paused))
-                .or(in_state(GameState::BoostSelection))
-                .or(in_state(GameState::AfterBoostSelection))
-                .or(in_state(GameState::UnitInfo))
-                .and(in_state(AppState::Game))
-        );
-        configure_stages!(
-            app,
-            InPlayingOrPausedOrEndSet,
-            in_state(GameState::Playing)
-                .or(in_state(GameState::Paused))
-                .or(in_state(GameState::EndGame))
-                .and(in_state(AppState::Game))
-        );
-
         app
+            // Definitions
+            .add_systems(Startup, (load_unit_definitions, load_balance, load_maps))
+            .add_systems(Update, (update_unit_definitions, update_balance, update_maps))
             // Camera
             .add_systems(Startup, setup_camera)
             .add_systems(Update, (move_camera, move_camera_keys).in_set(InPlayingOrPausedOrEndSet))
@@ -152,6 +112,7 @@ impl Plugin for GamePlugin {
                 .add_systems(OnExit(state), despawn::<MenuCmp>);
         }
         app.add_systems(Update, start_new_game_message.run_if(not(in_state(AppState::Game))));
+        app.add_systems(Update, update_seed.run_if(in_state(AppState::Settings)));
 
         app
             // Utilities
@@ -159,37 +120,71 @@ impl Plugin for GamePlugin {
                 Update,
                 (
                     check_keys_menu,
-                    (check_keys_game, check_keys_playing_game).in_set(InPlayingOrPausedSet),
+                    (
+                        check_keys_game,
+                        check_keys_playing_game.run_if(not(resource_exists::<ReplayViewer>)),
+                    )
+                        .in_set(InPlayingOrPausedSet),
                 ),
             )
+            // Replays
+            .add_systems(
+                FixedUpdate,
+                (
+                    play_replay.run_if(resource_exists::<ReplayViewer>),
+                    record_inputs.run_if(resource_exists::<Replay>),
+                )
+                    .chain()
+                    .before(queue_message)
+                    .in_set(InPlayingOrPausedSet),
+            )
+            .add_systems(Update, start_replay_message)
+            .add_systems(
+                OnEnter(AppState::Game),
+                setup_replay_ui.run_if(resource_exists::<ReplayViewer>),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_replay_ui.in_set(InGameSet),
+                    seek_replay.in_set(InPlayingSet),
+                    check_keys_replay.in_set(InPlayingOrPausedSet),
+                )
+                    .run_if(resource_exists::<ReplayViewer>),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), exit_replay)
             .add_systems(PostUpdate, on_resize_message)
             // In-game states
             .add_systems(OnEnter(AppState::Game), (draw_map, draw_ui))
             .add_systems(
                 Update,
-                (update_ui, update_ui2, update_animations, update_buildings, send_scroll_events)
+                (
+                    add_building_sprite,
+                    add_unit_sprite,
+                    add_arrow_sprite,
+                    update_ui,
+                    update_ui2,
+                    update_population_ui,
+                    update_hero_ui,
+                    update_gold_ui,
+                    update_animations,
+                    update_buildings,
+                    send_scroll_events,
+                )
                     .in_set(InGameSet),
             )
             .add_systems(
                 Update,
-                (
-                    activate_boost_message,
-                    update_boosts,
-                    queue_resolve,
-                    spawn_unit_message,
-                    spawn_building_message,
-                    spawn_arrow_message,
-                    update_units,
-                    update_strategy_timer,
-                    (check_boost_timer, apply_movement, resolve_attack, apply_damage_message)
-                        .chain()
-                        .run_if(resource_exists::<Host>),
-                )
-                    .in_set(InPlayingSet),
+                (update_units, update_guard_animation).after(add_unit_sprite).in_set(InPlayingSet),
+            )
+            .add_systems(
+                Update,
+                target_boost.run_if(resource_exists::<BoostTargeting>).in_set(InPlayingSet),
             )
             .add_systems(PostUpdate, (effect_message, deferred_message).chain().in_set(InGameSet))
-            .add_systems(PostUpdate, queue_message.in_set(InPlayingOrPausedSet))
-            .add_systems(Last, (despawn_effects, despawn_message).in_set(InGameSet))
+            // Notices are also shown in the menus, e.g. when a replay fails to load
+            .add_systems(PostUpdate, (notice_message, update_notices))
+            .add_systems(Last, despawn_effects.in_set(InGameSet))
             .add_systems(OnExit(AppState::Game), (despawn::<MapCmp>, reset_camera))
             .add_systems(OnEnter(GameState::BoostSelection), setup_boost_selection)
             .add_systems(OnExit(GameState::BoostSelection), despawn::<CardCmp>)
@@ -198,6 +193,12 @@ impl Plugin for GamePlugin {
             .add_systems(OnEnter(GameState::UnitInfo), setup_unit_info)
             .add_systems(Update, click_on_map.run_if(in_state(GameState::UnitInfo)))
             .add_systems(OnExit(GameState::UnitInfo), hide_unit_info)
+            .add_systems(OnEnter(GameState::TechTree), setup_tech_tree)
+            .add_systems(
+                Update,
+                (update_tech_ui, click_on_map).run_if(in_state(GameState::TechTree)),
+            )
+            .add_systems(OnExit(GameState::TechTree), hide_tech_tree)
             .add_systems(OnEnter(GameState::GameMenu), setup_game_menu)
             .add_systems(OnExit(GameState::GameMenu), despawn::<MenuCmp>)
             .add_systems(OnEnter(GameState::EndGame), (despawn::<UiCmp>, setup_end_game))
@@ -207,11 +208,8 @@ impl Plugin for GamePlugin {
         #[cfg(not(target_arch = "wasm32"))]
         app
             // Networking && multiplayer
-            .add_message::<ServerSendMsg>()
-            .add_message::<ClientSendMsg>()
             .add_message::<UpdatePopulationMsg>()
             .init_resource::<Ip>()
-            .init_resource::<EntityMap>()
             .init_resource::<AfterBoostCount>()
             .add_observer(server_update)
             .add_systems(
@@ -253,6 +251,13 @@ impl Plugin for GamePlugin {
             // Persistence
             .add_message::<SaveGameMsg>()
             .add_message::<LoadGameMsg>()
+            .add_message::<LoadReplayMsg>()
+            .add_systems(Update, load_replay)
+            .add_systems(OnExit(AppState::Game), save_replay)
+            .add_systems(
+                OnEnter(GameState::EndGame),
+                share_replay.run_if(resource_exists::<Replay>.and(resource_exists::<RenetServer>)),
+            )
             .add_systems(
                 Update,
                 (
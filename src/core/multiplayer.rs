@@ -1,6 +1,7 @@
-use crate::core::constants::{BUILDINGS_Z, UNITS_Z};
+use crate::core::constants::BUILDINGS_Z;
 use crate::core::mechanics::combat::Arrow;
 use crate::core::mechanics::spawn::{DespawnMsg, SpawnArrowMsg, SpawnBuildingMsg, SpawnUnitMsg};
+use crate::core::mechanics::status::StatusEffects;
 use crate::core::network::{ClientMessage, ClientSendMsg, ServerMessage, ServerSendMsg};
 use crate::core::player::Players;
 use crate::core::settings::Settings;
@@ -15,9 +16,9 @@ use std::collections::HashMap;
 #[derive(Resource, Default, Deref, DerefMut)]
 pub struct EntityMap(pub BiMap<Entity, Entity>);
 
-#[derive(Clone, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct Population {
-    pub units: HashMap<Entity, (Vec2, bool, Unit)>,
+    pub units: HashMap<Entity, (Vec2, bool, Unit, StatusEffects)>,
     pub buildings: HashMap<Entity, (Vec2, Building)>,
     pub arrows: HashMap<Entity, (Vec3, Quat, Option<Rect>, Arrow)>,
 }
@@ -44,11 +45,12 @@ pub fn client_send_status(
     players: Res<Players>,
     mut client_send_message: MessageWriter<ClientSendMsg>,
 ) {
-    client_send_message.write(ClientSendMsg::new(ClientMessage::Status(players.me.clone())));
+    client_send_message
+        .write(ClientSendMsg::new(ClientMessage::Status(Box::new(players.me.clone()))));
 }
 
 pub fn server_send_status(
-    unit_q: Query<(Entity, &Transform, &Sprite, &Unit)>,
+    unit_q: Query<(Entity, &Transform, &Sprite, &Unit, &StatusEffects)>,
     building_q: Query<(Entity, &Transform, &Building)>,
     arrow_q: Query<(Entity, &Transform, &Sprite, &Arrow)>,
     settings: Res<Settings>,
@@ -60,10 +62,16 @@ pub fn server_send_status(
             speed: settings.speed,
             strategy: players.me.strategy,
             boosts: players.me.boosts.clone(),
+            gold_earned: players.enemy.gold_earned,
+            kills: players.enemy.kills,
+            hero: players.enemy.hero.clone(),
+            enemy_hero: players.me.hero.clone(),
             population: Population {
                 units: unit_q
                     .iter()
-                    .map(|(e, t, s, u)| (e, (t.translation.truncate(), s.flip_x, *u)))
+                    .map(|(e, t, s, u, se)| {
+                        (e, (t.translation.truncate(), s.flip_x, *u, se.clone()))
+                    })
                     .collect(),
                 buildings: building_q
                     .iter()
@@ -81,7 +89,7 @@ pub fn server_send_status(
 
 pub fn update_population_message(
     mut update_population_ev: MessageReader<UpdatePopulationMsg>,
-    mut unit_q: Query<(Entity, &mut Transform, &mut Sprite, &mut Unit)>,
+    mut unit_q: Query<(Entity, &mut Transform, &mut Sprite, &mut Unit, &mut StatusEffects)>,
     mut building_q: Query<(Entity, &mut Transform, &mut Building), (Without<Unit>, Without<Arrow>)>,
     mut arrow_q: Query<
         (Entity, &mut Transform, &mut Sprite, &mut Arrow),
@@ -108,12 +116,14 @@ pub fn update_population_message(
         check(arrow_q.iter().map(|(e, ..)| e).collect(), msg.arrows.keys().collect());
 
         // Update the current population
-        for (unit_e, (t, s, u)) in &msg.units {
+        for (unit_e, (t, s, u, se)) in &msg.units {
             if let Some(e) = entity_map.get_by_left(unit_e) {
-                if let Ok((_, mut unit_t, mut unit_s, mut unit)) = unit_q.get_mut(*e) {
-                    unit_t.translation = t.extend(UNITS_Z);
+                if let Ok((_, mut unit_t, mut unit_s, mut unit, mut statuses)) = unit_q.get_mut(*e)
+                {
+                    unit_t.translation = t.extend(u.name.z());
                     unit_s.flip_x = *s;
                     *unit = *u;
+                    *statuses = se.clone();
 
                     if let Action::Attack(e) | Action::Heal(e) = &mut unit.action {
                         *e = *entity_map.get_by_left(e).unwrap_or(e);
@@ -125,6 +135,9 @@ pub fn update_population_message(
                     unit: u.name,
                     position: Some(*t),
                     on_building: u.on_building,
+                    hero: u.hero,
+                    veterancy: u.veterancy,
+                    cost: 0.,
                     lane: Some(u.lane),
                     dust_effect: false,
                     entity: Some(*unit_e),
@@ -159,14 +172,23 @@ pub fn update_population_message(
                     arrow_t.rotation = *r;
                     arrow_s.rect = *s;
                     *arrow = a.clone();
+
+                    // Entities of the shooter and its victims in this world
+                    arrow.shooter = a.shooter.and_then(|e| entity_map.get_by_left(&e).copied());
+                    arrow.hits =
+                        a.hits.iter().filter_map(|e| entity_map.get_by_left(e).copied()).collect();
                 }
             } else {
                 spawn_arrow_msg.write(SpawnArrowMsg {
                     color: a.color,
+                    unit: a.unit,
+                    shooter: a.shooter.and_then(|e| entity_map.get_by_left(&e).copied()),
                     projectile: a.projectile,
                     damage: a.damage,
+                    splash: a.splash,
                     start: a.start,
                     destination: a.destination,
+                    missed: a.missed,
                     entity: Some(*arrow_e),
                 });
             }
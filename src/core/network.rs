@@ -2,16 +2,22 @@ use std::net::{IpAddr, UdpSocket};
 use std::time::SystemTime;
 
 use crate::core::audio::PlayAudioMsg;
+use crate::core::balance::balance;
 use crate::core::boosts::{ActivateBoostMsg, AfterBoostCount, Boost};
-use crate::core::constants::MAX_BOOSTS;
+use crate::core::map::map::{MapName, Maps};
 use crate::core::mechanics::effects::{Effect, EffectMsg};
+use crate::core::mechanics::heroes::UseAbilityMsg;
 use crate::core::mechanics::spawn::SpawnUnitMsg;
 use crate::core::menu::buttons::LobbyTextCmp;
 use crate::core::menu::systems::Host;
 use crate::core::multiplayer::{EntityMap, Population, UpdatePopulationMsg};
 use crate::core::player::{Player, Players, SelectedBoost, Side, Strategy};
+use crate::core::replay::{PlayerInput, Replay};
+use crate::core::rng::GameRng;
 use crate::core::settings::{GameMode, PlayerColor, Settings};
+use crate::core::simulation::Step;
 use crate::core::states::{AppState, GameState};
+use crate::core::units::heroes::{Hero, HeroName};
 use crate::core::units::units::UnitName;
 use crate::core::utils::ClientId;
 use bevy::prelude::*;
@@ -67,12 +73,23 @@ pub enum ServerMessage {
     StartGame {
         player: Player,
         enemy_color: PlayerColor,
+        map: MapName,
+        economy: bool,
+        seed: u64,
     },
     State(GameState),
     Status {
         speed: f32,
         boosts: Vec<SelectedBoost>,
         strategy: Strategy,
+        /// Gold earned by the client
+        gold_earned: f32,
+        /// Units killed by the client
+        kills: u32,
+        /// Hero of the client
+        hero: Option<Hero>,
+        /// Hero of the host
+        enemy_hero: Option<Hero>,
         population: Population,
     },
     Effect {
@@ -80,6 +97,8 @@ pub enum ServerMessage {
         entity: Entity,
     },
     PlayWarning,
+    /// Recording of the match, shared by the host when the match ends
+    Replay(Box<Replay>),
 }
 
 impl ServerMessage {
@@ -96,10 +115,13 @@ impl ServerMessage {
 #[derive(Serialize, Deserialize)]
 pub enum ClientMessage {
     ShareColor(PlayerColor),
+    ShareHero(HeroName),
     State(GameState),
-    Status(Player),
+    Status(Box<Player>),
     SpawnUnit(UnitName),
-    ActivateBoost(Boost),
+    /// Activated boost, with the location of the map it targets
+    ActivateBoost(Boost, Option<Vec2>),
+    UseAbility(usize),
 }
 
 impl ClientMessage {
@@ -226,8 +248,11 @@ pub fn server_receive_message(
     mut settings: ResMut<Settings>,
     mut players: Option<ResMut<Players>>,
     mut boost_count: ResMut<AfterBoostCount>,
+    mut replay: Option<ResMut<Replay>>,
+    step: Res<Step>,
     mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
     mut activate_boost_msg: MessageWriter<ActivateBoostMsg>,
+    mut use_ability_msg: MessageWriter<UseAbilityMsg>,
     game_state: Res<State<GameState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
 ) {
@@ -236,8 +261,12 @@ pub fn server_receive_message(
             let (d, _) = decode_from_slice(&message, standard()).unwrap();
             match d {
                 ClientMessage::ShareColor(enemy_color) => settings.enemy_color = enemy_color,
+                ClientMessage::ShareHero(enemy_hero) => settings.enemy_hero = enemy_hero,
                 ClientMessage::State(state) => match state {
-                    GameState::GameMenu | GameState::Paused | GameState::UnitInfo
+                    GameState::GameMenu
+                    | GameState::Paused
+                    | GameState::UnitInfo
+                    | GameState::TechTree
                         if *game_state.get() == GameState::Playing =>
                     {
                         next_game_state.set(GameState::Paused);
@@ -248,14 +277,59 @@ pub fn server_receive_message(
                 },
                 ClientMessage::Status(player) => {
                     if let Some(players) = &mut players {
-                        players.enemy = player;
+                        // Only record the parts of the status that the host simulates with
+                        let boosts = |p: &Player| {
+                            p.boosts.iter().map(|b| (b.name, b.active)).collect::<Vec<_>>()
+                        };
+                        if player.strategy != players.enemy.strategy
+                            || player.direction != players.enemy.direction
+                            || boosts(&player) != boosts(&players.enemy)
+                            || player.tech.unlocked != players.enemy.tech.unlocked
+                        {
+                            if let Some(replay) = &mut replay {
+                                replay.record(*step, PlayerInput::Status(player.clone()));
+                            }
+                        }
+
+                        // The host simulates the income, bounties, kills and hero of every player
+                        let hero = players.enemy.hero.take();
+                        let (gold_earned, kills) = (players.enemy.gold_earned, players.enemy.kills);
+                        players.enemy = *player;
+                        players.enemy.gold_earned = gold_earned;
+                        players.enemy.kills = kills;
+                        players.enemy.hero = hero;
                     }
                 },
                 ClientMessage::SpawnUnit(unit) => {
-                    spawn_unit_msg.write(SpawnUnitMsg::new(settings.enemy_color, unit));
+                    if let Some(replay) = &mut replay {
+                        replay.record(*step, PlayerInput::SpawnUnit(settings.enemy_color, unit));
+                    }
+
+                    // The client paid for the unit when queuing it
+                    spawn_unit_msg.write(SpawnUnitMsg {
+                        cost: if settings.economy {
+                            unit.cost()
+                        } else {
+                            0.
+                        },
+                        ..SpawnUnitMsg::new(settings.enemy_color, unit)
+                    });
+                },
+                ClientMessage::ActivateBoost(boost, target) => {
+                    if let Some(replay) = &mut replay {
+                        replay.record(
+                            *step,
+                            PlayerInput::ActivateBoost(settings.enemy_color, boost, target),
+                        );
+                    }
+
+                    activate_boost_msg.write(ActivateBoostMsg {
+                        target,
+                        ..ActivateBoostMsg::new(boost, settings.enemy_color)
+                    });
                 },
-                ClientMessage::ActivateBoost(boost) => {
-                    activate_boost_msg.write(ActivateBoostMsg::new(boost, settings.enemy_color));
+                ClientMessage::UseAbility(ability) => {
+                    use_ability_msg.write(UseAbilityMsg::new(settings.enemy_color, ability));
                 },
             }
         }
@@ -277,6 +351,7 @@ pub fn client_receive_message(
     mut n_players_q: Query<&mut Text, With<LobbyTextCmp>>,
     mut client: ResMut<RenetClient>,
     mut settings: ResMut<Settings>,
+    maps: Res<Maps>,
     mut players: Option<ResMut<Players>>,
     mut boost_count: ResMut<AfterBoostCount>,
     mut next_app_state: ResMut<NextState<AppState>>,
@@ -297,17 +372,25 @@ pub fn client_receive_message(
 
                 client_send_msg
                     .write(ClientSendMsg::new(ClientMessage::ShareColor(settings.color)));
+                client_send_msg.write(ClientSendMsg::new(ClientMessage::ShareHero(settings.hero)));
             },
             ServerMessage::StartGame {
                 player,
                 enemy_color,
+                map,
+                economy,
+                seed,
             } => {
                 settings.reset();
                 settings.game_mode = GameMode::Multiplayer;
                 settings.color = player.color;
                 settings.enemy_color = enemy_color;
+                settings.map = map;
+                settings.economy = economy;
 
                 commands.remove_resource::<Host>();
+                commands.insert_resource(maps[&map].clone());
+                commands.insert_resource(GameRng::new(seed));
                 commands.insert_resource(EntityMap::default());
                 commands.insert_resource(AfterBoostCount::default());
                 commands.insert_resource(Players {
@@ -318,7 +401,10 @@ pub fn client_receive_message(
                 next_app_state.set(AppState::Game);
             },
             ServerMessage::State(state) => match state {
-                GameState::GameMenu | GameState::Paused | GameState::UnitInfo
+                GameState::GameMenu
+                | GameState::Paused
+                | GameState::UnitInfo
+                | GameState::TechTree
                     if *game_state.get() == GameState::Playing =>
                 {
                     next_game_state.set(GameState::Paused)
@@ -329,7 +415,7 @@ pub fn client_receive_message(
                 },
                 GameState::BoostSelection => {
                     if let Some(players) = &players {
-                        if players.me.boosts.len() != MAX_BOOSTS {
+                        if players.me.boosts.len() != balance().max_boosts {
                             next_game_state.set(state)
                         } else {
                             next_game_state.set(GameState::AfterBoostSelection)
@@ -359,6 +445,7 @@ pub fn client_receive_message(
             ServerMessage::PlayWarning => {
                 play_audio_msg.write(PlayAudioMsg::new("warning"));
             },
+            ServerMessage::Replay(replay) => commands.insert_resource(*replay),
             _ => unreachable!(),
         }
     }
@@ -370,6 +457,10 @@ pub fn client_receive_message(
                 speed,
                 strategy,
                 boosts,
+                gold_earned,
+                kills,
+                hero,
+                enemy_hero,
                 population,
             } => {
                 settings.speed = speed;
@@ -377,6 +468,10 @@ pub fn client_receive_message(
                 if let Some(players) = &mut players {
                     players.enemy.strategy = strategy;
                     players.enemy.boosts = boosts;
+                    players.me.gold_earned = gold_earned;
+                    players.me.kills = kills;
+                    players.me.hero = hero;
+                    players.enemy.hero = enemy_hero;
                 }
 
                 update_population_msg.write(UpdatePopulationMsg(population));
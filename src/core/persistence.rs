@@ -4,11 +4,15 @@ use std::io;
 use std::io::{Read, Write};
 
 use crate::core::audio::ChangeAudioMsg;
+use crate::core::map::map::Maps;
 use crate::core::mechanics::combat::Arrow;
+use crate::core::mechanics::status::StatusEffects;
 use crate::core::menu::systems::Host;
 use crate::core::multiplayer::{Population, UpdatePopulationMsg};
 use crate::core::network::{ServerMessage, ServerSendMsg};
 use crate::core::player::Players;
+use crate::core::replay::Replay;
+use crate::core::rng::GameRng;
 use crate::core::settings::Settings;
 use crate::core::states::{AppState, GameState};
 use crate::core::units::buildings::Building;
@@ -25,6 +29,7 @@ use serde::{Deserialize, Serialize};
 pub struct SaveAll {
     pub settings: Settings,
     pub players: Players,
+    pub rng: GameRng,
     pub population: Population,
 }
 
@@ -58,6 +63,7 @@ pub fn load_game(
     mut commands: Commands,
     mut load_game_msg: MessageReader<LoadGameMsg>,
     server: Option<Res<RenetServer>>,
+    maps: Res<Maps>,
     mut server_send_msg: MessageWriter<ServerSendMsg>,
     mut update_population_msg: MessageWriter<UpdatePopulationMsg>,
     mut change_audio_msg: MessageWriter<ChangeAudioMsg>,
@@ -92,6 +98,9 @@ pub fn load_game(
                                 ServerMessage::StartGame {
                                     player: player.clone(),
                                     enemy_color: data.settings.color,
+                                    map: data.settings.map,
+                                    economy: data.settings.economy,
+                                    seed: data.rng.seed,
                                 },
                                 Some(player.id),
                             ));
@@ -102,13 +111,20 @@ pub fn load_game(
                 }
             }
 
+            // The replay of a loaded game starts from the saved state
+            let mut replay = Replay::new(data.rng.seed, &data.settings, &data.players);
+            replay.start = Some((data.rng.clone(), data.population.clone()));
+            commands.insert_resource(replay);
+
             update_population_msg.write(UpdatePopulationMsg(data.population));
 
             change_audio_msg.write(ChangeAudioMsg(Some(data.settings.audio)));
 
             commands.insert_resource(Host);
+            commands.insert_resource(maps[&data.settings.map].clone());
             commands.insert_resource(data.settings);
             commands.insert_resource(data.players);
+            commands.insert_resource(data.rng);
 
             next_game_state.set(GameState::default());
             next_app_state.set(AppState::Game);
@@ -118,12 +134,13 @@ pub fn load_game(
 
 #[cfg(not(target_arch = "wasm32"))]
 pub fn save_game(
-    unit_q: Query<(Entity, &Transform, &Sprite, &Unit)>,
+    unit_q: Query<(Entity, &Transform, &Sprite, &Unit, &StatusEffects)>,
     building_q: Query<(Entity, &Transform, &Building)>,
     arrow_q: Query<(Entity, &Transform, &Sprite, &Arrow)>,
     mut save_game_msg: MessageReader<SaveGameMsg>,
     settings: Res<Settings>,
     players: Res<Players>,
+    rng: Res<GameRng>,
 ) {
     for msg in save_game_msg.read() {
         let file_path = if msg.0 {
@@ -143,10 +160,13 @@ pub fn save_game(
             let data = SaveAll {
                 settings: settings.clone(),
                 players: players.clone(),
+                rng: rng.clone(),
                 population: Population {
                     units: unit_q
                         .iter()
-                        .map(|(e, t, s, u)| (e, (t.translation.truncate(), s.flip_x, *u)))
+                        .map(|(e, t, s, u, se)| {
+                            (e, (t.translation.truncate(), s.flip_x, *u, se.clone()))
+                        })
                         .collect(),
                     buildings: building_q
                         .iter()
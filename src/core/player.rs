@@ -1,17 +1,17 @@
+use crate::core::balance::balance;
 use crate::core::boosts::Boost;
-use crate::core::constants::STRATEGY_TIMER;
 use crate::core::map::map::Lane;
 use crate::core::settings::PlayerColor;
+use crate::core::units::heroes::{Hero, HeroName};
 use crate::core::units::units::UnitName;
 use crate::core::utils::ClientId;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 use std::time::Duration;
-use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
-#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(EnumIter, Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum Side {
     Left,
     Right,
@@ -26,67 +26,86 @@ impl Side {
     }
 }
 
+/// Set of lanes that spawning units can take, stored as a bitmask
+///
+/// An empty set means that units can take any lane.
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
-pub enum PlayerDirection {
-    #[default]
-    Any,
-    Top,
-    TopMid,
-    Mid,
-    MidBot,
-    Bot,
-    TopBot,
-}
+pub struct LaneSelection(u8);
 
-impl PlayerDirection {
-    pub fn image(&self) -> &str {
-        match self {
-            PlayerDirection::Any => "any arrow",
-            PlayerDirection::Top | PlayerDirection::Bot => "top arrow",
-            PlayerDirection::TopMid | PlayerDirection::MidBot => "top-mid arrow",
-            PlayerDirection::Mid => "mid arrow",
-            PlayerDirection::TopBot => "top bot arrow",
-        }
+impl LaneSelection {
+    pub fn any() -> Self {
+        Self(0)
     }
 
-    pub fn flip_y(&self) -> bool {
-        matches!(self, PlayerDirection::Bot | PlayerDirection::MidBot)
+    pub fn only(lanes: &[Lane]) -> Self {
+        Self(lanes.iter().fold(0, |mask, lane| mask | (1 << lane.0)))
     }
 
-    pub fn next(&self) -> Self {
-        match self {
-            PlayerDirection::Any => PlayerDirection::Top,
-            PlayerDirection::Top => PlayerDirection::TopMid,
-            PlayerDirection::TopMid => PlayerDirection::Mid,
-            PlayerDirection::Mid => PlayerDirection::MidBot,
-            PlayerDirection::MidBot => PlayerDirection::Bot,
-            PlayerDirection::Bot => PlayerDirection::TopBot,
-            PlayerDirection::TopBot => PlayerDirection::Any,
-        }
+    pub fn is_any(&self) -> bool {
+        self.0 == 0
     }
 
-    pub fn previous(&self) -> Self {
-        match self {
-            PlayerDirection::Any => PlayerDirection::TopBot,
-            PlayerDirection::Top => PlayerDirection::Any,
-            PlayerDirection::TopMid => PlayerDirection::Top,
-            PlayerDirection::Mid => PlayerDirection::TopMid,
-            PlayerDirection::MidBot => PlayerDirection::Mid,
-            PlayerDirection::Bot => PlayerDirection::MidBot,
-            PlayerDirection::TopBot => PlayerDirection::Bot,
+    pub fn contains(&self, lane: Lane) -> bool {
+        self.is_any() || self.0 & (1 << lane.0) != 0
+    }
+
+    /// Enable or disable a lane, going back to any lane when none or all are enabled
+    pub fn toggle(&mut self, lane: Lane, n_lanes: usize) {
+        let all = (1 << n_lanes) - 1;
+        let current = if self.is_any() {
+            all
+        } else {
+            self.0
+        };
+
+        let mask = current ^ (1 << lane.0);
+        self.0 = if mask & all == all {
+            0
+        } else {
+            mask
+        };
+    }
+
+    /// Enabled lanes in a map with `n_lanes` lanes
+    pub fn lanes(&self, n_lanes: usize) -> Vec<Lane> {
+        let lanes: Vec<Lane> = (0..n_lanes).map(Lane).filter(|l| self.contains(*l)).collect();
+
+        if lanes.is_empty() {
+            (0..n_lanes).map(Lane).collect()
+        } else {
+            lanes
         }
     }
 
-    pub fn lanes(&self) -> Vec<Lane> {
-        match self {
-            Self::Any => Lane::iter().collect(),
-            Self::Top => vec![Lane::Top],
-            Self::TopMid => vec![Lane::Top, Lane::Mid],
-            Self::Mid => vec![Lane::Mid],
-            Self::MidBot => vec![Lane::Mid, Lane::Bot],
-            Self::Bot => vec![Lane::Bot],
-            Self::TopBot => vec![Lane::Top, Lane::Bot],
+    /// Selections to cycle through: any lane, every lane, every pair of
+    /// adjacent lanes and the two outer lanes
+    pub fn options(n_lanes: usize) -> Vec<Self> {
+        let mut options = vec![Self::any()];
+
+        for i in 0..n_lanes {
+            options.push(Self::only(&[Lane(i)]));
+            if i + 1 < n_lanes {
+                options.push(Self::only(&[Lane(i), Lane(i + 1)]));
+            }
         }
+
+        if n_lanes > 2 {
+            options.push(Self::only(&[Lane(0), Lane(n_lanes - 1)]));
+        }
+
+        options
+    }
+
+    pub fn next(&self, n_lanes: usize) -> Self {
+        let options = Self::options(n_lanes);
+        let i = options.iter().position(|o| o == self).unwrap_or(0);
+        options[(i + 1) % options.len()]
+    }
+
+    pub fn previous(&self, n_lanes: usize) -> Self {
+        let options = Self::options(n_lanes);
+        let i = options.iter().position(|o| o == self).unwrap_or(0);
+        options[(i + options.len() - 1) % options.len()]
     }
 }
 
@@ -135,6 +154,8 @@ impl Strategy {
 pub struct QueuedUnit {
     pub unit: UnitName,
     pub timer: Timer,
+    /// Gold paid for the unit, refunded when it's removed from the queue
+    pub cost: f32,
 }
 
 impl QueuedUnit {
@@ -142,6 +163,7 @@ impl QueuedUnit {
         Self {
             unit,
             timer: Timer::new(Duration::from_millis(millis), TimerMode::Once),
+            cost: 0.,
         }
     }
 }
@@ -168,38 +190,83 @@ impl SelectedBoost {
     }
 }
 
+/// Units and boosts an AI player picks from, any when not set
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Pool {
+    pub units: Option<Vec<UnitName>>,
+    pub boosts: Option<Vec<Boost>>,
+}
+
+impl Pool {
+    pub fn has_boost(&self, boost: Boost) -> bool {
+        self.boosts.as_ref().is_none_or(|b| b.contains(&boost))
+    }
+}
+
+/// Units unlocked by a player during the match
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct TechTree {
+    pub unlocked: Vec<UnitName>,
+    /// Unlocked unit shown in the shop
+    pub selected: Option<UnitName>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Player {
     pub id: ClientId,
     pub color: PlayerColor,
     pub side: Side,
-    pub direction: PlayerDirection,
+    pub direction: LaneSelection,
     pub strategy: Strategy,
     pub strategy_timer: Timer,
     pub queue: VecDeque<QueuedUnit>,
     pub queue_default: UnitName,
     pub boosts: Vec<SelectedBoost>,
+    pub pool: Pool,
+    /// Gold earned over time and from kills (simulated by the host)
+    pub gold_earned: f32,
+    /// Gold spent on queued units (tracked by the player resolving the queue)
+    pub gold_spent: f32,
+    pub tech: TechTree,
+    /// Enemy units killed (simulated by the host)
+    pub kills: u32,
+    /// Seconds played in the match
+    pub elapsed: f32,
+    /// Hero picked before the match (simulated by the host)
+    pub hero: Option<Hero>,
 }
 
 impl Player {
     pub fn new(id: ClientId, color: PlayerColor, side: Side) -> Self {
         // Start the game with the timer finished, so capable of changing strategy
-        let mut timer = Timer::new(Duration::from_secs(STRATEGY_TIMER), TimerMode::Once);
+        let mut timer = Timer::new(Duration::from_secs(balance().strategy_timer), TimerMode::Once);
         timer.finish();
 
         Self {
             id,
             color,
             side,
-            direction: PlayerDirection::default(),
+            direction: LaneSelection::default(),
             strategy: Strategy::default(),
             strategy_timer: timer,
             queue: VecDeque::new(),
             queue_default: UnitName::default(),
             boosts: vec![],
+            pool: Pool::default(),
+            gold_earned: balance().economy.start,
+            gold_spent: 0.,
+            tech: TechTree::default(),
+            kills: 0,
+            elapsed: 0.,
+            hero: None,
         }
     }
 
+    pub fn with_hero(mut self, hero: HeroName) -> Self {
+        self.hero = Some(Hero::new(hero));
+        self
+    }
+
     pub fn is_human(&self) -> bool {
         self.id == 0 || (self.id > 10 && self.id < ClientId::MAX)
     }
@@ -208,17 +275,73 @@ impl Player {
         self.boosts.iter().any(|b| b.name == boost && b.active)
     }
 
+    pub fn gold(&self) -> f32 {
+        self.gold_earned - self.gold_spent
+    }
+
+    /// Add a unit to the queue, paying its cost in economy games
+    ///
+    /// Returns false if the player can't afford the unit.
+    pub fn enqueue(&mut self, unit: UnitName, economy: bool) -> bool {
+        let cost = if economy {
+            unit.cost()
+        } else {
+            0.
+        };
+
+        if cost > self.gold() {
+            return false;
+        }
+
+        self.gold_spent += cost;
+        self.queue.push_back(QueuedUnit {
+            cost,
+            ..QueuedUnit::new(unit, unit.spawn_duration())
+        });
+
+        true
+    }
+
+    /// Remove a unit from the queue, refunding its cost
+    pub fn dequeue(&mut self, i: usize) {
+        if let Some(queued) = self.queue.remove(i) {
+            self.gold_spent -= queued.cost;
+        }
+    }
+
+    /// Whether the unit can be unlocked in the tech tree right now
+    pub fn can_unlock(&self, unit: UnitName, economy: bool) -> bool {
+        let Some(unlock) = unit.unlock() else {
+            return false;
+        };
+
+        !self.tech.unlocked.contains(&unit)
+            && unlock.requires.iter().all(|u| self.can_queue(*u))
+            && self.elapsed >= unlock.secs
+            && self.kills >= unlock.kills
+            && (!economy || self.gold() >= unlock.gold)
+    }
+
+    /// Unlock a unit in the tech tree, paying its gold in economy games
+    ///
+    /// Returns false if the requirements aren't met.
+    pub fn unlock(&mut self, unit: UnitName, economy: bool) -> bool {
+        if !self.can_unlock(unit, economy) {
+            return false;
+        }
+
+        if economy {
+            self.gold_spent += unit.unlock().map_or(0., |u| u.gold);
+        }
+
+        self.tech.unlocked.push(unit);
+        self.tech.selected = Some(unit);
+
+        true
+    }
+
     pub fn can_queue(&self, unit: UnitName) -> bool {
-        unit.is_basic_unit()
-            || (unit == UnitName::Bear && self.has_boost(Boost::QueueBears))
-            || (unit == UnitName::Gnoll && self.has_boost(Boost::QueueGnolls))
-            || (unit == UnitName::Goblin && self.has_boost(Boost::QueueGoblins))
-            || (unit == UnitName::Hammerhead && self.has_boost(Boost::QueueHammerheads))
-            || (unit == UnitName::Minotaur && self.has_boost(Boost::QueueMinotaurs))
-            || (unit == UnitName::Shaman && self.has_boost(Boost::QueueShamans))
-            || (unit == UnitName::Shark && self.has_boost(Boost::QueueSharks))
-            || (unit == UnitName::Skull && self.has_boost(Boost::QueueSkulls))
-            || (unit == UnitName::Turtle && self.has_boost(Boost::QueueTurtles))
+        unit.is_basic_unit() || self.tech.unlocked.contains(&unit)
     }
 }
 
@@ -251,6 +374,14 @@ impl Players {
         }
     }
 
+    pub fn get_by_color_mut(&mut self, color: PlayerColor) -> &mut Player {
+        if self.me.color == color {
+            &mut self.me
+        } else {
+            &mut self.enemy
+        }
+    }
+
     pub fn get_by_side(&self, side: Side) -> &Player {
         if self.me.side == side {
             &self.me
@@ -267,3 +398,164 @@ impl Players {
         [&mut self.me, &mut self.enemy].into_iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggling_every_lane_selects_any() {
+        let mut selection = LaneSelection::any();
+
+        selection.toggle(Lane(0), 3);
+        assert_eq!(selection.lanes(3), [Lane(1), Lane(2)]);
+
+        selection.toggle(Lane(1), 3);
+        assert_eq!(selection.lanes(3), [Lane(2)]);
+
+        selection.toggle(Lane(0), 3);
+        selection.toggle(Lane(1), 3);
+        assert!(selection.is_any());
+        assert_eq!(selection.lanes(3), [Lane(0), Lane(1), Lane(2)]);
+    }
+
+    #[test]
+    fn toggling_the_last_lane_selects_any() {
+        let mut selection = LaneSelection::only(&[Lane(1)]);
+
+        selection.toggle(Lane(1), 3);
+        assert!(selection.is_any());
+    }
+
+    #[test]
+    fn options_of_two_lanes() {
+        let options = LaneSelection::options(2);
+
+        assert_eq!(
+            options,
+            [
+                LaneSelection::any(),
+                LaneSelection::only(&[Lane(0)]),
+                LaneSelection::only(&[Lane(0), Lane(1)]),
+                LaneSelection::only(&[Lane(1)]),
+            ]
+        );
+        assert_eq!(options[3].next(2), LaneSelection::any());
+        assert_eq!(LaneSelection::any().previous(2), options[3]);
+    }
+
+    #[test]
+    fn outer_lanes_are_an_option() {
+        let options = LaneSelection::options(5);
+
+        // Any, every lane, every pair of adjacent lanes and the outer lanes
+        assert_eq!(options.len(), 1 + 5 + 4 + 1);
+        assert!(options.contains(&LaneSelection::only(&[Lane(0), Lane(4)])));
+    }
+
+    #[test]
+    fn selection_survives_fewer_lanes() {
+        // A selection made on a map with more lanes than the current one
+        let selection = LaneSelection::only(&[Lane(3), Lane(4)]);
+
+        assert_eq!(selection.lanes(2), [Lane(0), Lane(1)]);
+        assert_eq!(selection.next(2), LaneSelection::only(&[Lane(0)]));
+
+        let partial = LaneSelection::only(&[Lane(1), Lane(3)]);
+        assert_eq!(partial.lanes(2), [Lane(1)]);
+    }
+
+    #[test]
+    fn enqueue_pays_the_cost() {
+        let mut player = Player::new(1, PlayerColor::Blue, Side::Left);
+        let start = player.gold();
+
+        assert!(player.enqueue(UnitName::Warrior, true));
+        assert_eq!(player.queue.len(), 1);
+        assert_eq!(player.gold(), start - UnitName::Warrior.cost());
+    }
+
+    #[test]
+    fn enqueue_rejects_unaffordable_units() {
+        let mut player = Player::new(1, PlayerColor::Blue, Side::Left);
+        player.gold_earned = UnitName::Warrior.cost() - 1.;
+
+        assert!(!player.enqueue(UnitName::Warrior, true));
+        assert!(player.queue.is_empty());
+        assert_eq!(player.gold(), UnitName::Warrior.cost() - 1.);
+
+        // Units are free outside economy games
+        assert!(player.enqueue(UnitName::Warrior, false));
+        assert_eq!(player.gold(), UnitName::Warrior.cost() - 1.);
+    }
+
+    #[test]
+    fn dequeue_refunds_the_cost() {
+        let mut player = Player::new(1, PlayerColor::Blue, Side::Left);
+        let start = player.gold();
+
+        player.enqueue(UnitName::Warrior, true);
+        player.enqueue(UnitName::Lancer, true);
+        player.dequeue(0);
+        assert_eq!(player.queue[0].unit, UnitName::Lancer);
+        assert_eq!(player.gold(), start - UnitName::Lancer.cost());
+
+        // Removing a unit that isn't queued does nothing
+        player.dequeue(5);
+        assert_eq!(player.gold(), start - UnitName::Lancer.cost());
+    }
+
+    /// Player that meets the time and kill requirements to unlock the unit
+    fn veteran(unit: UnitName) -> Player {
+        let unlock = unit.unlock().unwrap();
+        let mut player = Player::new(1, PlayerColor::Blue, Side::Left);
+        player.elapsed = unlock.secs;
+        player.kills = unlock.kills;
+        player
+    }
+
+    #[test]
+    fn unlock_waits_for_time_and_kills() {
+        let mut player = veteran(UnitName::Minotaur);
+        player.tech.unlocked.push(UnitName::Bear);
+        assert!(player.can_unlock(UnitName::Minotaur, false));
+
+        player.elapsed -= 1.;
+        assert!(!player.can_unlock(UnitName::Minotaur, false));
+
+        player.elapsed += 1.;
+        player.kills -= 1;
+        assert!(!player.can_unlock(UnitName::Minotaur, false));
+    }
+
+    #[test]
+    fn unlock_requires_previous_units() {
+        let mut player = veteran(UnitName::Minotaur);
+        assert!(!player.can_unlock(UnitName::Minotaur, false));
+        assert!(!player.unlock(UnitName::Minotaur, false));
+
+        assert!(player.unlock(UnitName::Bear, false));
+        assert!(player.unlock(UnitName::Minotaur, false));
+        assert!(player.can_queue(UnitName::Minotaur));
+        assert_eq!(player.tech.selected, Some(UnitName::Minotaur));
+
+        // Units are only unlocked once, and basic units never
+        assert!(!player.can_unlock(UnitName::Minotaur, false));
+        assert!(!player.can_unlock(UnitName::Warrior, false));
+    }
+
+    #[test]
+    fn unlock_pays_gold_in_economy_games() {
+        let gold = UnitName::Minotaur.unlock().unwrap().gold;
+        let mut player = veteran(UnitName::Minotaur);
+        player.tech.unlocked.push(UnitName::Bear);
+        player.gold_earned = gold - 1.;
+
+        assert!(!player.unlock(UnitName::Minotaur, true));
+        assert!(player.can_unlock(UnitName::Minotaur, false));
+
+        player.gold_earned = gold;
+        assert!(player.unlock(UnitName::Minotaur, true));
+        assert_eq!(player.gold(), 0.);
+    }
+}
@@ -0,0 +1,513 @@
+use crate::core::assets::WorldAssets;
+use crate::core::boosts::{ActivateBoostMsg, AfterBoostCount, Boost};
+use crate::core::constants::*;
+use crate::core::map::map::Maps;
+use crate::core::map::systems::MapCmp;
+use crate::core::map::ui::systems::NoticeMsg;
+use crate::core::mechanics::heroes::UseAbilityMsg;
+use crate::core::mechanics::queue::QueueUnitMsg;
+use crate::core::mechanics::spawn::{SpawnBuildingMsg, SpawnUnitMsg};
+use crate::core::mechanics::tech::UnlockUnitMsg;
+use crate::core::menu::systems::{spawn_bases, Host};
+use crate::core::menu::utils::{add_root_node, add_text};
+use crate::core::player::{LaneSelection, Player, Players, SelectedBoost, Strategy};
+use crate::core::rng::GameRng;
+use crate::core::settings::{PlayerColor, Settings};
+use crate::core::simulation::Step;
+use crate::core::states::{AppState, GameState};
+use crate::core::units::units::UnitName;
+use crate::core::utils::ClientId;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::core::multiplayer::{EntityMap, Population, UpdatePopulationMsg},
+    crate::core::network::{ServerMessage, ServerSendMsg},
+    bincode::config::standard,
+    bincode::serde::{decode_from_slice, encode_to_vec},
+    rfd::FileDialog,
+    std::env::current_dir,
+    std::fs,
+    std::path::PathBuf,
+    std::time::SystemTime,
+};
+
+/// Input of a player that changes the outcome of a match
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum PlayerInput {
+    QueueUnit(ClientId, UnitName),
+    RemoveQueuedUnit(usize),
+    UnlockUnit(ClientId, UnitName),
+    /// Unit spawned by a remote player
+    SpawnUnit(PlayerColor, UnitName),
+    /// Selected boost, and the boost that the AI selected at the same time
+    SelectBoost(Boost, Option<Boost>),
+    ActivateBoost(PlayerColor, Boost, Option<Vec2>),
+    FinishBoost(Boost),
+    UseAbility(PlayerColor, usize),
+    Strategy(Strategy),
+    Direction(LaneSelection),
+    Speed(f32),
+    /// Status of a remote player
+    Status(Box<Player>),
+}
+
+/// Recording of a match
+///
+/// A match is played back from its seed and starting state, applying every input
+/// at the same simulation step it was recorded.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub settings: Settings,
+    pub players: Players,
+    /// Number of simulation steps in the match
+    pub steps: u64,
+    pub inputs: Vec<(u64, PlayerInput)>,
+    /// Random number generator and population of the loaded game the match
+    /// continues, which replaces the seed and the starting bases
+    #[cfg(not(target_arch = "wasm32"))]
+    pub start: Option<(GameRng, Population)>,
+    /// Last recorded strategy, direction and game speed
+    #[serde(skip)]
+    controls: (Strategy, LaneSelection, f32),
+}
+
+impl Replay {
+    pub fn new(seed: u64, settings: &Settings, players: &Players) -> Self {
+        Self {
+            seed,
+            settings: settings.clone(),
+            players: players.clone(),
+            steps: 0,
+            inputs: vec![],
+            #[cfg(not(target_arch = "wasm32"))]
+            start: None,
+            controls: (players.me.strategy, players.me.direction, settings.speed),
+        }
+    }
+
+    pub fn record(&mut self, step: Step, input: PlayerInput) {
+        self.inputs.push((*step, input));
+    }
+}
+
+/// Replay that is being watched
+#[derive(Resource)]
+pub struct ReplayViewer {
+    pub replay: Replay,
+    /// Index of the next input to apply
+    pub next: usize,
+    /// Step to fast-forward to
+    pub target: Option<u64>,
+    /// Settings of the player before starting the replay
+    pub settings: Settings,
+}
+
+#[derive(Component)]
+pub struct ReplayCmp;
+
+#[derive(Component)]
+pub struct ReplayTextCmp;
+
+#[derive(Message)]
+pub struct StartReplayMsg {
+    pub replay: Replay,
+    pub step: u64,
+}
+
+impl StartReplayMsg {
+    pub fn new(replay: Replay, step: u64) -> Self {
+        Self {
+            replay,
+            step,
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Message)]
+pub struct LoadReplayMsg;
+
+#[cfg(not(target_arch = "wasm32"))]
+fn replays_dir() -> Result<PathBuf, String> {
+    current_dir().map(|path| path.join("replays")).map_err(|e| e.to_string())
+}
+
+/// Write the replay to a new file in the replays directory
+#[cfg(not(target_arch = "wasm32"))]
+fn write_replay(replay: &Replay) -> Result<(), String> {
+    let mut file_path = replays_dir()?;
+    fs::create_dir_all(&file_path).map_err(|e| e.to_string())?;
+
+    let time =
+        SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).map_err(|e| e.to_string())?;
+    file_path.push(format!("{}.replay", time.as_secs()));
+
+    let buffer = encode_to_vec(replay, standard()).map_err(|e| e.to_string())?;
+    fs::write(file_path, buffer).map_err(|e| e.to_string())
+}
+
+/// Record the inputs that change the player's state directly
+pub fn record_inputs(
+    mut replay: ResMut<Replay>,
+    players: Res<Players>,
+    settings: Res<Settings>,
+    step: Res<Step>,
+) {
+    let (strategy, direction, speed) = replay.controls;
+
+    if players.me.strategy != strategy {
+        replay.record(*step, PlayerInput::Strategy(players.me.strategy));
+    }
+    if players.me.direction != direction {
+        replay.record(*step, PlayerInput::Direction(players.me.direction));
+    }
+    if settings.speed != speed {
+        replay.record(*step, PlayerInput::Speed(settings.speed));
+    }
+
+    replay.controls = (players.me.strategy, players.me.direction, settings.speed);
+}
+
+/// Apply the recorded inputs up to the current step
+pub fn play_replay(
+    mut viewer: ResMut<ReplayViewer>,
+    mut players: ResMut<Players>,
+    mut settings: ResMut<Settings>,
+    step: Res<Step>,
+    mut queue_unit_msg: MessageWriter<QueueUnitMsg>,
+    mut unlock_unit_msg: MessageWriter<UnlockUnitMsg>,
+    mut spawn_unit_msg: MessageWriter<SpawnUnitMsg>,
+    mut activate_boost_msg: MessageWriter<ActivateBoostMsg>,
+    mut use_ability_msg: MessageWriter<UseAbilityMsg>,
+    game_state: Res<State<GameState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    while let Some((s, input)) = viewer.replay.inputs.get(viewer.next).cloned() {
+        if s > **step {
+            break;
+        }
+
+        viewer.next += 1;
+
+        match input {
+            PlayerInput::QueueUnit(id, unit) => {
+                queue_unit_msg.write(QueueUnitMsg::new(id, unit));
+            },
+            PlayerInput::RemoveQueuedUnit(i) => {
+                players.me.dequeue(i);
+            },
+            PlayerInput::UnlockUnit(id, unit) => {
+                unlock_unit_msg.write(UnlockUnitMsg::new(id, unit));
+            },
+            PlayerInput::SpawnUnit(color, unit) => {
+                spawn_unit_msg.write(SpawnUnitMsg {
+                    cost: if settings.economy {
+                        unit.cost()
+                    } else {
+                        0.
+                    },
+                    ..SpawnUnitMsg::new(color, unit)
+                });
+            },
+            PlayerInput::SelectBoost(boost, enemy_boost) => {
+                players.me.boosts.push(SelectedBoost::new(boost));
+
+                if let Some(enemy_boost) = enemy_boost {
+                    players.enemy.boosts.push(SelectedBoost::new(enemy_boost).active());
+                    activate_boost_msg
+                        .write(ActivateBoostMsg::new(enemy_boost, players.enemy.color));
+                }
+
+                next_game_state.set(GameState::Playing);
+            },
+            PlayerInput::ActivateBoost(color, boost, target) => {
+                if color == players.me.color {
+                    if let Some(b) = players.me.boosts.iter_mut().find(|b| b.name == boost) {
+                        b.active = true;
+                    }
+                }
+
+                activate_boost_msg.write(ActivateBoostMsg {
+                    target,
+                    ..ActivateBoostMsg::new(boost, color)
+                });
+            },
+            PlayerInput::FinishBoost(boost) => {
+                if let Some(b) = players.me.boosts.iter_mut().find(|b| b.name == boost) {
+                    b.timer.finish();
+                }
+            },
+            PlayerInput::UseAbility(color, ability) => {
+                use_ability_msg.write(UseAbilityMsg::new(color, ability));
+            },
+            PlayerInput::Strategy(strategy) => {
+                players.me.strategy = strategy;
+                players.me.strategy_timer.reset();
+            },
+            PlayerInput::Direction(direction) => players.me.direction = direction,
+            PlayerInput::Speed(speed) => settings.speed = speed,
+            PlayerInput::Status(player) => {
+                // Keep the parts of the status that the host simulates
+                let hero = players.enemy.hero.take();
+                players.enemy = Player {
+                    gold_earned: players.enemy.gold_earned,
+                    kills: players.enemy.kills,
+                    hero,
+                    ..*player
+                };
+            },
+        }
+    }
+
+    // The other player's boost selection is already part of the recording
+    if *game_state.get() == GameState::AfterBoostSelection {
+        next_game_state.set(GameState::Playing);
+    }
+}
+
+pub fn start_replay_message(
+    mut commands: Commands,
+    mut start_replay_msg: MessageReader<StartReplayMsg>,
+    viewer: Option<Res<ReplayViewer>>,
+    mut settings: ResMut<Settings>,
+    mut spawn_building_msg: MessageWriter<SpawnBuildingMsg>,
+    #[cfg(not(target_arch = "wasm32"))] mut population_msg: MessageWriter<UpdatePopulationMsg>,
+    maps: Res<Maps>,
+    mut next_app_state: ResMut<NextState<AppState>>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    for msg in start_replay_msg.read() {
+        let replay = msg.replay.clone();
+        let map = maps[&replay.settings.map].clone();
+
+        // Replays of loaded games start from the population of the save
+        #[cfg(not(target_arch = "wasm32"))]
+        let rng = replay.start.clone().map(|(rng, population)| {
+            population_msg.write(UpdatePopulationMsg(population));
+            rng
+        });
+        #[cfg(target_arch = "wasm32")]
+        let rng = None;
+
+        let rng = rng.unwrap_or_else(|| {
+            spawn_bases(
+                [replay.settings.color, replay.settings.enemy_color],
+                &map,
+                &mut spawn_building_msg,
+            );
+            GameRng::new(replay.seed)
+        });
+
+        // Seeking back restarts the replay, so keep the settings from before the first start
+        let own_settings = viewer.as_ref().map_or(settings.clone(), |v| v.settings.clone());
+
+        *settings = Settings {
+            audio: settings.audio,
+            autosave: false,
+            ..replay.settings.clone()
+        };
+
+        commands.insert_resource(Host);
+        commands.insert_resource(map);
+        commands.insert_resource(rng);
+        commands.insert_resource(AfterBoostCount::default());
+        #[cfg(not(target_arch = "wasm32"))]
+        commands.insert_resource(EntityMap::default());
+        commands.insert_resource(replay.players.clone());
+        commands.insert_resource(ReplayViewer {
+            replay,
+            next: 0,
+            target: (msg.step > 0).then_some(msg.step),
+            settings: own_settings,
+        });
+
+        next_game_state.set(GameState::default());
+        next_app_state.set(AppState::Game);
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn load_replay(
+    mut load_replay_msg: MessageReader<LoadReplayMsg>,
+    mut start_replay_msg: MessageWriter<StartReplayMsg>,
+    mut notice_msg: MessageWriter<NoticeMsg>,
+) {
+    for _ in load_replay_msg.read() {
+        let mut dialog = FileDialog::new().add_filter("Replay", &["replay"]);
+        if let Ok(dir) = replays_dir() {
+            dialog = dialog.set_directory(dir);
+        }
+
+        if let Some(file_path) = dialog.pick_file() {
+            let replay = fs::read(file_path).map_err(|e| e.to_string()).and_then(|buffer| {
+                decode_from_slice(&buffer, standard()).map_err(|e| e.to_string())
+            });
+
+            match replay {
+                Ok((replay, _)) => {
+                    start_replay_msg.write(StartReplayMsg::new(replay, 0));
+                },
+                Err(err) => {
+                    notice_msg.write(NoticeMsg::new(format!("Failed to load the replay: {err}")));
+                },
+            }
+        }
+    }
+}
+
+/// Share the recording of the match with the clients, which don't simulate it
+#[cfg(not(target_arch = "wasm32"))]
+pub fn share_replay(
+    replay: Res<Replay>,
+    step: Res<Step>,
+    mut server_send_msg: MessageWriter<ServerSendMsg>,
+) {
+    let replay = Replay {
+        steps: **step,
+        ..replay.clone()
+    };
+
+    server_send_msg.write(ServerSendMsg::new(ServerMessage::Replay(Box::new(replay)), None));
+}
+
+/// Store the recording of the match that just finished in the replays directory
+#[cfg(not(target_arch = "wasm32"))]
+pub fn save_replay(
+    mut commands: Commands,
+    replay: Option<ResMut<Replay>>,
+    mut notice_msg: MessageWriter<NoticeMsg>,
+    step: Res<Step>,
+) {
+    if let Some(mut replay) = replay {
+        commands.remove_resource::<Replay>();
+
+        // Replays shared by the host already contain the number of steps
+        if replay.steps == 0 {
+            replay.steps = **step;
+        }
+
+        if replay.steps > 0 {
+            if let Err(err) = write_replay(&replay) {
+                notice_msg.write(NoticeMsg::new(format!("Failed to save the replay: {err}")));
+            }
+        }
+    }
+}
+
+pub fn exit_replay(
+    mut commands: Commands,
+    viewer: Option<Res<ReplayViewer>>,
+    mut settings: ResMut<Settings>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if let Some(viewer) = viewer {
+        *settings = Settings {
+            audio: settings.audio,
+            ..viewer.settings.clone()
+        };
+
+        commands.remove_resource::<ReplayViewer>();
+        time.set_relative_speed(1.);
+    }
+}
+
+pub fn check_keys_replay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut viewer: ResMut<ReplayViewer>,
+    step: Res<Step>,
+    mut time: ResMut<Time<Virtual>>,
+    mut start_replay_msg: MessageWriter<StartReplayMsg>,
+    mut next_game_state: ResMut<NextState<GameState>>,
+) {
+    let seek = REPLAY_SEEK_SECS * SIMULATION_HZ as u64;
+
+    if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        // Change the playback speed
+        if keyboard.just_released(KeyCode::ArrowRight) {
+            let speed = (time.relative_speed() * 2.).min(MAX_GAME_SPEED);
+            time.set_relative_speed(speed);
+        } else if keyboard.just_released(KeyCode::ArrowLeft) {
+            let speed = (time.relative_speed() * 0.5).max(MIN_GAME_SPEED);
+            time.set_relative_speed(speed);
+        }
+    } else if keyboard.just_released(KeyCode::ArrowRight) {
+        // Don't seek past the end of the recording
+        let target = (viewer.target.unwrap_or(**step) + seek).min(viewer.replay.steps);
+        viewer.target = Some(target);
+        next_game_state.set(GameState::Playing);
+    } else if keyboard.just_released(KeyCode::ArrowLeft) {
+        // The simulation can't run backwards, so restart and fast-forward
+        let target = viewer.target.unwrap_or(**step).saturating_sub(seek);
+        start_replay_msg.write(StartReplayMsg::new(viewer.replay.clone(), target));
+    }
+}
+
+/// Run extra simulation steps until reaching the selected step
+pub fn seek_replay(
+    mut viewer: ResMut<ReplayViewer>,
+    step: Res<Step>,
+    mut time: ResMut<Time<Fixed>>,
+) {
+    if let Some(target) = viewer.target {
+        if **step >= target {
+            viewer.target = None;
+        } else {
+            let steps = (target - **step).min(MAX_SEEK_STEPS) as u32;
+            let timestep = time.timestep();
+            time.accumulate_overstep(timestep * steps);
+        }
+    }
+}
+
+pub fn setup_replay_ui(mut commands: Commands, assets: Res<WorldAssets>, window: Single<&Window>) {
+    // The replay covers the screen to block any interaction with the match
+    commands.spawn((add_root_node(true), GlobalZIndex(5), ReplayCmp, MapCmp)).with_children(
+        |parent| {
+            parent.spawn((
+                Node {
+                    bottom: percent(12.),
+                    position_type: PositionType::Absolute,
+                    ..default()
+                },
+                add_text("", "bold", SUBTITLE_TEXT_SIZE, &assets, &window),
+                ReplayTextCmp,
+            ));
+        },
+    );
+}
+
+pub fn update_replay_ui(
+    mut replay_q: Query<&mut Visibility, With<ReplayCmp>>,
+    mut text_q: Query<&mut Text, With<ReplayTextCmp>>,
+    viewer: Res<ReplayViewer>,
+    step: Res<Step>,
+    time: Res<Time<Virtual>>,
+    game_state: Res<State<GameState>>,
+) {
+    if let Ok(mut visibility) = replay_q.single_mut() {
+        *visibility = match game_state.get() {
+            GameState::GameMenu | GameState::Settings => Visibility::Hidden,
+            _ => Visibility::Inherited,
+        };
+    }
+
+    if let Ok(mut text) = text_q.single_mut() {
+        let format = |step: u64| {
+            let secs = step / SIMULATION_HZ as u64;
+            format!("{:02}:{:02}", secs / 60, secs % 60)
+        };
+
+        text.0 = format!(
+            "Replay {} / {} - x{}",
+            format(**step),
+            format(viewer.replay.steps),
+            time.relative_speed()
+        );
+
+        if viewer.target.is_some() {
+            text.0.push_str(" - Seeking...");
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use rand::rngs::Xoshiro256PlusPlus;
+use rand::{SeedableRng, TryRng};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+
+/// Source of all the randomness in a match
+///
+/// Matches with the same seed and the same inputs play out exactly the same. Visual
+/// effects draw from a separate stream, so they never change the outcome of a match.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng {
+    pub seed: u64,
+    rng: Xoshiro256PlusPlus,
+    visuals: Xoshiro256PlusPlus,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: Xoshiro256PlusPlus::seed_from_u64(seed),
+            visuals: Xoshiro256PlusPlus::seed_from_u64(!seed),
+        }
+    }
+
+    /// Random number generator for cosmetic effects
+    pub fn visuals(&mut self) -> &mut Xoshiro256PlusPlus {
+        &mut self.visuals
+    }
+}
+
+impl TryRng for GameRng {
+    type Error = Infallible;
+
+    fn try_next_u32(&mut self) -> Result<u32, Infallible> {
+        self.rng.try_next_u32()
+    }
+
+    fn try_next_u64(&mut self) -> Result<u64, Infallible> {
+        self.rng.try_next_u64()
+    }
+
+    fn try_fill_bytes(&mut self, dst: &mut [u8]) -> Result<(), Infallible> {
+        self.rng.try_fill_bytes(dst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bincode::config::standard;
+    use bincode::serde::{decode_from_slice, encode_to_vec};
+    use rand::RngExt;
+
+    fn draw(rng: &mut GameRng) -> Vec<u64> {
+        (0..100).map(|_| rng.random()).collect()
+    }
+
+    #[test]
+    fn same_seed_gives_same_numbers() {
+        assert_eq!(draw(&mut GameRng::new(7)), draw(&mut GameRng::new(7)));
+        assert_ne!(draw(&mut GameRng::new(7)), draw(&mut GameRng::new(8)));
+    }
+
+    #[test]
+    fn visuals_dont_change_the_match() {
+        let mut rng = GameRng::new(7);
+        for _ in 0..10 {
+            let _: f32 = rng.visuals().random();
+        }
+
+        assert_eq!(draw(&mut rng), draw(&mut GameRng::new(7)));
+    }
+
+    #[test]
+    fn saved_rng_continues_the_sequence() {
+        let mut rng = GameRng::new(7);
+        draw(&mut rng);
+
+        let bytes = encode_to_vec(&rng, standard()).unwrap();
+        let (mut loaded, _): (GameRng, usize) = decode_from_slice(&bytes, standard()).unwrap();
+
+        assert_eq!(draw(&mut loaded), draw(&mut rng));
+    }
+}
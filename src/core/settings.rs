@@ -1,4 +1,6 @@
-use crate::core::constants::BOOST_TIMER;
+use crate::core::balance::balance;
+use crate::core::map::map::MapName;
+use crate::core::units::heroes::HeroName;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
@@ -9,7 +11,7 @@ pub enum GameMode {
     Multiplayer,
 }
 
-#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(EnumIter, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum PlayerColor {
     Black,
     Blue,
@@ -54,10 +56,18 @@ pub struct Settings {
     pub game_mode: GameMode,
     pub color: PlayerColor,
     pub enemy_color: PlayerColor,
+    /// Hero picked for the next match
+    pub hero: HeroName,
+    pub enemy_hero: HeroName,
+    pub map: MapName,
     pub speed: f32,
+    /// Whether units cost gold to queue
+    pub economy: bool,
     pub boost_timer: Timer,
     pub audio: AudioState,
     pub autosave: bool,
+    /// Seed of the next match, random when not set
+    pub seed: Option<u64>,
 }
 
 impl Default for Settings {
@@ -66,10 +76,15 @@ impl Default for Settings {
             game_mode: GameMode::SinglePlayer,
             color: PlayerColor::Blue,
             enemy_color: PlayerColor::Red,
+            hero: HeroName::default(),
+            enemy_hero: HeroName::default(),
+            map: MapName::default(),
             speed: 1.0,
-            boost_timer: Timer::from_seconds(BOOST_TIMER, TimerMode::Repeating),
+            economy: false,
+            boost_timer: Timer::from_seconds(balance().boost_timer, TimerMode::Repeating),
             audio: AudioState::default(),
             autosave: false,
+            seed: None,
         }
     }
 }
@@ -0,0 +1,188 @@
+use crate::core::audio::PlayAudioMsg;
+use crate::core::boosts::*;
+use crate::core::constants::SIMULATION_HZ;
+use crate::core::map::map::Map;
+use crate::core::mechanics::combat::*;
+use crate::core::mechanics::effects::EffectMsg;
+use crate::core::mechanics::heroes::*;
+use crate::core::mechanics::movement::apply_movement;
+use crate::core::mechanics::queue::*;
+use crate::core::mechanics::spatial::{clear_spatial_grid, update_spatial_grid, SpatialGrid};
+use crate::core::mechanics::spawn::*;
+use crate::core::mechanics::status::update_status_effects;
+use crate::core::mechanics::tech::*;
+use crate::core::menu::systems::Host;
+use crate::core::rng::GameRng;
+use crate::core::settings::Settings;
+use crate::core::states::{AppState, GameState};
+use crate::core::systems::update_strategy_timer;
+use bevy::prelude::*;
+#[cfg(not(target_arch = "wasm32"))]
+use {
+    crate::core::multiplayer::EntityMap,
+    crate::core::network::{ClientSendMsg, ServerSendMsg},
+};
+
+/// Number of simulation steps since the start of the match
+#[derive(Resource, Clone, Copy, Debug, Default, Deref, DerefMut)]
+pub struct Step(pub u64);
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InGameSet;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InPlayingSet;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InPlayingOrPausedSet;
+
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InPlayingOrPausedOrEndSet;
+
+macro_rules! configure_stages {
+    ($app:expr, $set:ident, $run_if:expr) => {
+        $app.configure_sets(First, $set.run_if($run_if))
+            .configure_sets(PreUpdate, $set.run_if($run_if))
+            .configure_sets(FixedUpdate, $set.run_if($run_if))
+            .configure_sets(Update, $set.run_if($run_if))
+            .configure_sets(PostUpdate, $set.run_if($run_if))
+            .configure_sets(Last, $set.run_if($run_if));
+    };
+}
+
+/// Gameplay of a match, without any rendering, audio or user input
+///
+/// Every system runs in a fixed order on a fixed timestep, so the same inputs
+/// always produce the same battle. Together with `StatesPlugin`, this plugin can
+/// run under `MinimalPlugins`, with no window or renderer.
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            // States
+            .init_state::<AppState>()
+            .init_state::<GameState>()
+            // Messages
+            .add_message::<PlayAudioMsg>()
+            .add_message::<QueueUnitMsg>()
+            .add_message::<UnlockUnitMsg>()
+            .add_message::<SpawnBuildingMsg>()
+            .add_message::<SpawnUnitMsg>()
+            .add_message::<SpawnArrowMsg>()
+            .add_message::<DespawnMsg>()
+            .add_message::<ActivateBoostMsg>()
+            .add_message::<UseAbilityMsg>()
+            .add_message::<ApplyDamageMsg>()
+            .add_message::<DamageDealtMsg>()
+            .add_message::<EffectMsg>()
+            // Resources
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .init_resource::<Settings>()
+            .init_resource::<Map>()
+            .init_resource::<GameRng>()
+            .init_resource::<SpatialGrid>()
+            .init_resource::<Step>();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_message::<ServerSendMsg>()
+            .add_message::<ClientSendMsg>()
+            .init_resource::<EntityMap>();
+
+        // Sets
+        configure_stages!(app, InGameSet, in_state(AppState::Game));
+        configure_stages!(
+            app,
+            InPlayingSet,
+            in_state(GameState::Playing).and(in_state(AppState::Game))
+        );
+        configure_stages!(
+            app,
+            InPlayingOrPausedSet,
+            in_state(GameState::Playing)
+                .or(in_state(GameState::Paused))
+                .or(in_state(GameState::BoostSelection))
+                .or(in_state(GameState::AfterBoostSelection))
+                .or(in_state(GameState::UnitInfo))
+                .or(in_state(GameState::TechTree))
+                .and(in_state(AppState::Game))
+        );
+        configure_stages!(
+            app,
+            InPlayingOrPausedOrEndSet,
+            in_state(GameState::Playing)
+                .or(in_state(GameState::Paused))
+                .or(in_state(GameState::EndGame))
+                .and(in_state(AppState::Game))
+        );
+
+        app
+            // Every step is chained to make the order of the systems deterministic
+            .add_systems(
+                FixedUpdate,
+                (
+                    (
+                        update_strategy_timer,
+                        update_boosts,
+                        check_boost_timer.run_if(resource_exists::<Host>),
+                        update_gold.run_if(resource_exists::<Host>),
+                        update_tech,
+                        update_heroes.run_if(resource_exists::<Host>),
+                        queue_resolve,
+                    )
+                        .chain(),
+                    (spawn_building_message, spawn_unit_message, spawn_arrow_message).chain(),
+                    (
+                        update_spatial_grid,
+                        activate_boost_message,
+                        use_ability_message,
+                        update_actions,
+                    )
+                        .chain(),
+                    (
+                        apply_movement,
+                        resolve_attack,
+                        resolve_building_attack,
+                        update_status_effects,
+                        apply_damage_message,
+                    )
+                        .chain()
+                        .run_if(resource_exists::<Host>),
+                    advance_step,
+                )
+                    .chain()
+                    .run_if(no_pending_transition)
+                    .in_set(InPlayingSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (queue_message, unlock_message)
+                    .chain()
+                    .before(update_strategy_timer)
+                    .in_set(InPlayingOrPausedSet),
+            )
+            .add_systems(
+                FixedUpdate,
+                (destroy_buildings, despawn_message)
+                    .chain()
+                    .after(apply_damage_message)
+                    .in_set(InGameSet),
+            )
+            .add_systems(OnEnter(AppState::Game), reset_step)
+            .add_systems(OnExit(AppState::Game), clear_spatial_grid);
+    }
+}
+
+/// Stop stepping as soon as a state transition is queued, so the number of steps
+/// before e.g. a boost selection doesn't depend on the frame rate
+fn no_pending_transition(next_game_state: Res<NextState<GameState>>) -> bool {
+    matches!(*next_game_state, NextState::Unchanged)
+}
+
+fn advance_step(mut step: ResMut<Step>) {
+    **step += 1;
+}
+
+fn reset_step(mut step: ResMut<Step>) {
+    *step = Step::default();
+}
@@ -20,6 +20,7 @@ pub enum GameState {
     Playing,
     Paused,
     UnitInfo,
+    TechTree,
     BoostSelection,
     AfterBoostSelection,
     GameMenu,
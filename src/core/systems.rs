@@ -1,11 +1,15 @@
 use crate::core::audio::PlayAudioMsg;
-use crate::core::constants::{MAX_GAME_SPEED, MIN_GAME_SPEED};
+use crate::core::constants::{ABILITY_KEYS, MAX_GAME_SPEED, MIN_GAME_SPEED};
+use crate::core::map::map::{Lane, Map};
 use crate::core::map::ui::systems::UiCmp;
 use crate::core::mechanics::effects::EffectCmp;
+use crate::core::mechanics::heroes::UseAbilityMsg;
 use crate::core::mechanics::queue::QueueUnitMsg;
+use crate::core::mechanics::status::{Stats, StatusEffects};
 use crate::core::menu::systems::{Host, StartNewGameMsg};
 use crate::core::menu::utils::TextSize;
-use crate::core::player::{PlayerDirection, Players, Side, Strategy};
+use crate::core::player::{LaneSelection, Players, Side, Strategy};
+use crate::core::replay::ReplayViewer;
 use crate::core::settings::Settings;
 use crate::core::states::{AppState, GameState};
 use crate::core::units::units::{Action, Unit, UnitName};
@@ -47,7 +51,7 @@ pub fn check_keys_menu(
             },
             AppState::Game => match game_state.get() {
                 GameState::Playing | GameState::Paused => next_game_state.set(GameState::GameMenu),
-                GameState::UnitInfo | GameState::GameMenu => {
+                GameState::UnitInfo | GameState::TechTree | GameState::GameMenu => {
                     next_game_state.set(GameState::Playing)
                 },
                 GameState::EndGame => next_app_state.set(AppState::MainMenu),
@@ -66,6 +70,14 @@ pub fn check_keys_menu(
         }
     }
 
+    if keyboard.just_released(KeyCode::Tab) {
+        if matches!(game_state.get(), GameState::Playing | GameState::Paused) {
+            next_game_state.set(GameState::TechTree);
+        } else if *game_state.get() == GameState::TechTree {
+            next_game_state.set(GameState::Playing);
+        }
+    }
+
     if keyboard.just_released(KeyCode::Enter) {
         match app_state.get() {
             AppState::MainMenu => next_app_state.set(AppState::SinglePlayerMenu),
@@ -89,6 +101,7 @@ pub fn check_keys_menu(
 pub fn check_keys_game(
     keyboard: Res<ButtonInput<KeyCode>>,
     host: Option<Res<Host>>,
+    viewer: Option<Res<ReplayViewer>>,
     mut settings: ResMut<Settings>,
     game_state: Res<State<GameState>>,
     mut next_game_state: ResMut<NextState<GameState>>,
@@ -99,7 +112,9 @@ pub fn check_keys_game(
             GameState::Paused => next_game_state.set(GameState::Playing),
             _ => (),
         }
-    } else if host.is_some() && keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    } else if host.is_some()
+        && viewer.is_none()
+        && keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
     {
         if keyboard.just_released(KeyCode::ArrowRight) {
             settings.speed = (settings.speed * 2.).min(MAX_GAME_SPEED);
@@ -113,7 +128,9 @@ pub fn check_keys_playing_game(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut players: ResMut<Players>,
     mut queue_unit_msg: MessageWriter<QueueUnitMsg>,
+    mut use_ability_msg: MessageWriter<UseAbilityMsg>,
     mut play_audio_msg: MessageWriter<PlayAudioMsg>,
+    map: Res<Map>,
     mut pressed: Local<bool>,
 ) {
     // Change direction
@@ -130,39 +147,42 @@ pub fn check_keys_playing_game(
     };
 
     if !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        // The arrows select the top, middle and bottom lanes of the map
+        let n_lanes = map.n_lanes();
+        let (top, mid, bot) = (Lane(0), Lane(n_lanes / 2), Lane(n_lanes - 1));
         let mut new_direction = None;
 
         if keyboard.just_released(any_key) {
-            new_direction = Some(PlayerDirection::Any);
+            new_direction = Some(LaneSelection::any());
         } else if keyboard.just_released(mid_key) && !*pressed {
             new_direction = Some(if keyboard.pressed(KeyCode::ArrowUp) {
                 *pressed = true;
-                PlayerDirection::TopMid
+                LaneSelection::only(&[top, mid])
             } else if keyboard.pressed(KeyCode::ArrowDown) {
                 *pressed = true;
-                PlayerDirection::MidBot
+                LaneSelection::only(&[mid, bot])
             } else {
-                PlayerDirection::Mid
+                LaneSelection::only(&[mid])
             });
         } else if keyboard.just_released(KeyCode::ArrowUp) && !*pressed {
             new_direction = Some(if keyboard.pressed(mid_key) {
                 *pressed = true;
-                PlayerDirection::TopMid
+                LaneSelection::only(&[top, mid])
             } else if keyboard.pressed(KeyCode::ArrowDown) {
                 *pressed = true;
-                PlayerDirection::TopBot
+                LaneSelection::only(&[top, bot])
             } else {
-                PlayerDirection::Top
+                LaneSelection::only(&[top])
             });
         } else if keyboard.just_released(KeyCode::ArrowDown) && !*pressed {
             new_direction = Some(if keyboard.pressed(mid_key) {
                 *pressed = true;
-                PlayerDirection::MidBot
+                LaneSelection::only(&[mid, bot])
             } else if keyboard.pressed(KeyCode::ArrowUp) {
                 *pressed = true;
-                PlayerDirection::TopBot
+                LaneSelection::only(&[top, bot])
             } else {
-                PlayerDirection::Bot
+                LaneSelection::only(&[bot])
             });
         }
 
@@ -189,31 +209,44 @@ pub fn check_keys_playing_game(
         }
     }
 
-    // Queue units
+    // Queue units, where the special key queues the unit selected in the tech tree
     for unit in UnitName::iter() {
-        if keyboard.just_released(unit.key()) && players.me.can_queue(unit) {
+        if keyboard.just_released(unit.key())
+            && (unit.is_basic_unit() || players.me.tech.selected == Some(unit))
+        {
             queue_unit_msg.write(QueueUnitMsg::new(players.me.id, unit));
             play_audio_msg.write(PlayAudioMsg::new("button"));
         }
     }
+
+    // Use the abilities of the hero
+    if players.me.hero.is_some() {
+        for (i, key) in ABILITY_KEYS.iter().enumerate() {
+            if keyboard.just_released(*key) {
+                use_ability_msg.write(UseAbilityMsg::new(players.me.color, i));
+            }
+        }
+    }
 }
 
 pub fn update_animations(
-    mut anim_q: Query<(&mut TweenAnim, Option<&Unit>, Option<&EffectCmp>), Without<UiCmp>>,
+    mut anim_q: Query<
+        (&mut TweenAnim, Option<&Unit>, Option<&StatusEffects>, Option<&EffectCmp>),
+        Without<UiCmp>,
+    >,
     settings: Res<Settings>,
     players: Res<Players>,
     game_state: Res<State<GameState>>,
 ) {
     // Play/pause tween animations
-    anim_q.iter_mut().for_each(|(mut tween, unit, explosion)| {
+    anim_q.iter_mut().for_each(|(mut tween, unit, statuses, explosion)| {
         tween.speed = settings.speed as f64;
 
-        // Increase the attack animation of units in berserk mode
-        if let Some(unit) = unit {
-            let player = players.get_by_color(unit.color);
-
-            if player.strategy == Strategy::Berserk && matches!(unit.action, Action::Attack(_)) {
-                tween.speed *= 1.3
+        // Play the attack (or heal) animation at the current attack speed of the unit
+        if let (Some(unit), Some(statuses)) = (unit, statuses) {
+            if matches!(unit.action, Action::Attack(_) | Action::Heal(_)) {
+                let stats = Stats::new(unit, statuses, players.get_by_color(unit.color));
+                tween.speed *= stats.attack_speed.max(0.1) as f64;
             }
         }
 
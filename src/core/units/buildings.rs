@@ -1,10 +1,26 @@
+use crate::core::balance::balance;
 use crate::core::constants::BUILDING_SCALE;
+use crate::core::mechanics::combat::Projectile;
 use crate::core::settings::PlayerColor;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumIter;
 
-#[derive(EnumIter, Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+/// Attack profile of a building
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BuildingAttack {
+    /// Range in units of the unit radius
+    pub range: f32,
+    #[serde(default)]
+    pub physical_damage: f32,
+    #[serde(default)]
+    pub magic_damage: f32,
+    pub projectile: Projectile,
+    /// Attacks per second
+    pub attack_speed: f32,
+}
+
+#[derive(EnumIter, Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
 pub enum BuildingName {
     #[default]
     Barracks,
@@ -46,6 +62,11 @@ impl BuildingName {
             BuildingName::Tower => 500.,
         }
     }
+
+    /// Attack of the building at level 1
+    pub fn attack(&self) -> Option<BuildingAttack> {
+        balance().buildings.attacks.get(self).copied()
+    }
 }
 
 #[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
@@ -54,15 +75,40 @@ pub struct Building {
     pub color: PlayerColor,
     pub is_base: bool,
     pub health: f32,
+    /// Upgrade level, raised by the Tower and Castle boosts
+    pub level: u32,
+    /// Seconds elapsed in the current attack cycle
+    pub attack_timer: f32,
 }
 
 impl Building {
+    pub const MAX_LEVEL: u32 = 3;
+
     pub fn new(name: BuildingName, color: PlayerColor, is_base: bool, health: f32) -> Self {
         Self {
             name,
             color,
             is_base,
             health,
+            level: 1,
+            attack_timer: 0.,
         }
     }
+
+    /// Attack of the building after its upgrades
+    pub fn attack(&self) -> Option<BuildingAttack> {
+        let levels = (self.level - 1) as f32;
+        let (bonus, range) = {
+            let balance = balance();
+            (1. + balance.buildings.bonus * levels, balance.buildings.range * levels)
+        };
+
+        self.name.attack().map(|attack| BuildingAttack {
+            range: attack.range + range,
+            physical_damage: attack.physical_damage * bonus,
+            magic_damage: attack.magic_damage * bonus,
+            attack_speed: attack.attack_speed * bonus,
+            ..attack
+        })
+    }
 }
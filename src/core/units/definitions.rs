@@ -0,0 +1,298 @@
+use crate::core::assets::DataAsset;
+use crate::core::map::map::Movement;
+use crate::core::map::ui::systems::NoticeMsg;
+use crate::core::mechanics::combat::Projectile;
+use crate::core::mechanics::status::Status;
+use crate::core::units::units::{ActionKind, Unit, UnitName};
+use crate::utils::NameFromEnum;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use strum::IntoEnumIterator;
+
+/// Unit definitions shipped with the game, used until the asset is loaded
+const DEFAULT_UNITS: &[u8] = include_bytes!("../../../assets/data/units.ron");
+
+/// Definitions currently in use by the game
+///
+/// This is a global instead of a resource because the stats are read through
+/// accessors like `UnitName::health()`, which are called from plain functions
+/// (e.g. `Unit::new` or `Damage::from_unit`) without access to the world. It's
+/// only written by `update_unit_definitions` when the asset is (re)loaded.
+static UNIT_DEFINITIONS: LazyLock<RwLock<UnitDefinitions>> = LazyLock::new(|| {
+    RwLock::new(UnitDefinitions::from_bytes(DEFAULT_UNITS).expect("Invalid default units file."))
+});
+
+fn default_range() -> f32 {
+    1.
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+/// Damage over time applied by every hit of a unit
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct PoisonDefinition {
+    /// Damage per second of every stack
+    pub damage: f32,
+    /// Seconds the poison lasts after the last hit
+    pub duration: f32,
+    pub max_stacks: u32,
+}
+
+/// Status applied to the target of every hit of a unit
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct HitStatusDefinition {
+    pub status: Status,
+    pub value: f32,
+    /// Seconds the status lasts after the last hit
+    pub duration: f32,
+}
+
+/// Area damage around the target of every hit of a unit
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct SplashDefinition {
+    /// Radius of the splash, in units of the unit's radius
+    pub radius: f32,
+    /// Fraction of the damage lost at the edge of the splash
+    pub falloff: f32,
+    /// Whether the splash also damages buildings
+    #[serde(default)]
+    pub buildings: bool,
+}
+
+/// Requirements to unlock a unit in the tech tree
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnlockDefinition {
+    /// Units that must be unlocked first
+    pub requires: Vec<UnitName>,
+    /// Seconds since the start of the match
+    pub secs: f32,
+    /// Enemy units killed by the player
+    pub kills: u32,
+    /// Gold paid to unlock the unit in economy games
+    pub gold: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UnitDefinition {
+    pub frames: HashMap<ActionKind, u32>,
+    pub spawn_duration: u64,
+    /// Gold paid to queue the unit in economy games
+    pub cost: f32,
+    /// Population taken by the unit
+    #[serde(default = "default_weight")]
+    pub weight: u32,
+    #[serde(default)]
+    pub movement: Movement,
+    pub speed: f32,
+    /// Attacks (or heals) per second
+    #[serde(default)]
+    pub attack_speed: f32,
+    #[serde(default = "default_range")]
+    pub range: f32,
+    #[serde(default)]
+    pub projectile: Option<Projectile>,
+    pub health: f32,
+    #[serde(default)]
+    pub physical_damage: f32,
+    #[serde(default)]
+    pub magic_damage: f32,
+    #[serde(default)]
+    pub armor: f32,
+    #[serde(default)]
+    pub magic_resist: f32,
+    #[serde(default)]
+    pub armor_pen: f32,
+    #[serde(default)]
+    pub magic_pen: f32,
+    #[serde(default)]
+    pub poison: Option<PoisonDefinition>,
+    #[serde(default)]
+    pub on_hit: Option<HitStatusDefinition>,
+    #[serde(default)]
+    pub splash: Option<SplashDefinition>,
+    #[serde(default)]
+    pub unlock: Option<UnlockDefinition>,
+}
+
+impl UnitDefinition {
+    pub fn frames(&self, action: ActionKind) -> u32 {
+        self.frames.get(&action).copied().unwrap_or(0)
+    }
+}
+
+#[derive(Asset, TypePath, Clone, Debug, Deref, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct UnitDefinitions(pub HashMap<UnitName, UnitDefinition>);
+
+impl DataAsset for UnitDefinitions {
+    const EXTENSION: &'static str = "units.ron";
+
+    /// Check that every unit is defined and that its stats make sense
+    fn validate(&self) -> Result<(), String> {
+        for unit in UnitName::iter() {
+            let name = unit.to_name();
+            let Some(def) = self.get(&unit) else {
+                return Err(format!("{name} is not defined."));
+            };
+
+            let error = if def.frames(ActionKind::Idle) == 0 || def.frames(ActionKind::Run) == 0 {
+                Some("requires frames for the Idle and Run actions")
+            } else if def.frames(ActionKind::Attack) > 0 && def.frames(ActionKind::Heal) > 0 {
+                Some("can't both attack and heal")
+            } else if def.spawn_duration == 0 {
+                Some("spawn_duration must be larger than zero")
+            } else if def.cost < 0. {
+                Some("cost can't be negative")
+            } else if def.weight == 0 {
+                Some("weight must be larger than zero")
+            } else if def.speed <= 0. {
+                Some("speed must be larger than zero")
+            } else if (def.frames(ActionKind::Attack) > 0 || def.frames(ActionKind::Heal) > 0)
+                && def.attack_speed <= 0.
+            {
+                Some("attack_speed must be larger than zero")
+            } else if def.health <= 0. {
+                Some("health must be larger than zero")
+            } else if def.range < 1. {
+                Some("range must be at least 1")
+            } else if def.projectile.is_some() && def.frames(ActionKind::Attack) == 0 {
+                Some("has a projectile but can't attack")
+            } else if def.projectile.is_some() && def.range == 1. {
+                Some("has a projectile but is a melee unit")
+            } else if [def.armor, def.magic_resist, def.armor_pen, def.magic_pen]
+                .iter()
+                .any(|v| *v < 0.)
+            {
+                Some("armor, magic_resist and penetration can't be negative")
+            } else if def
+                .poison
+                .is_some_and(|p| p.damage <= 0. || p.duration <= 0. || p.max_stacks == 0)
+            {
+                Some("poison requires a positive damage, duration and max_stacks")
+            } else if def.on_hit.is_some_and(|h| {
+                !matches!(h.status, Status::Slow | Status::Stun | Status::Burn | Status::ArmorShred)
+            }) {
+                Some("on_hit only accepts the Slow, Stun, Burn and ArmorShred statuses")
+            } else if def.on_hit.is_some_and(|h| h.value <= 0. || h.duration <= 0.) {
+                Some("on_hit requires a positive value and duration")
+            } else if def.splash.is_some() && def.frames(ActionKind::Attack) == 0 {
+                Some("has a splash but can't attack")
+            } else if def.splash.is_some_and(|s| s.radius <= 0. || !(0. ..=1.).contains(&s.falloff))
+            {
+                Some("splash requires a positive radius and a falloff between 0 and 1")
+            } else if def.unlock.is_some() && unit.is_basic_unit() {
+                Some("is a basic unit and can't be unlocked")
+            } else if def.unlock.as_ref().is_some_and(|u| u.secs < 0. || u.gold < 0.) {
+                Some("unlock requires a non-negative secs and gold")
+            } else if def.unlock.as_ref().is_some_and(|u| {
+                u.requires.iter().any(|r| {
+                    *r == unit
+                        || !(r.is_basic_unit() || self.get(r).is_some_and(|d| d.unlock.is_some()))
+                })
+            }) {
+                Some("unlock requires units that can't be unlocked")
+            } else {
+                None
+            };
+
+            if let Some(error) = error {
+                return Err(format!("{name} {error}."));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Resource)]
+pub struct UnitDefinitionsHandle(pub Handle<UnitDefinitions>);
+
+/// Run a function over the definition of a unit
+pub fn with_definition<T>(unit: &UnitName, f: impl FnOnce(&UnitDefinition) -> T) -> T {
+    f(&UNIT_DEFINITIONS.read().unwrap()[unit])
+}
+
+pub fn load_unit_definitions(mut commands: Commands, assets: Res<AssetServer>) {
+    commands.insert_resource(UnitDefinitionsHandle(assets.load("data/units.ron")));
+}
+
+pub fn update_unit_definitions(
+    mut unit_q: Query<&mut Unit>,
+    mut asset_msg: MessageReader<AssetEvent<UnitDefinitions>>,
+    mut notice_msg: MessageWriter<NoticeMsg>,
+    handle: Res<UnitDefinitionsHandle>,
+    definitions: Res<Assets<UnitDefinitions>>,
+) {
+    for msg in asset_msg.read() {
+        let reloaded = msg.is_modified(&handle.0);
+        if !reloaded && !msg.is_loaded_with_dependencies(&handle.0) {
+            continue;
+        }
+
+        if let Some(defs) = definitions.get(&handle.0) {
+            // Keep the health fraction of existing units when their maximum health changes
+            let old_health: HashMap<UnitName, f32> =
+                UnitName::iter().map(|u| (u, u.health())).collect();
+
+            *UNIT_DEFINITIONS.write().unwrap() = defs.clone();
+
+            for mut unit in &mut unit_q {
+                unit.health *= unit.name.health() / old_health[&unit.name];
+            }
+
+            if reloaded {
+                notice_msg.write(NoticeMsg::new("Unit definitions reloaded"));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn default_definitions() -> UnitDefinitions {
+        UnitDefinitions::from_bytes(DEFAULT_UNITS).unwrap()
+    }
+
+    #[test]
+    fn default_definitions_are_valid() {
+        assert!(default_definitions().validate().is_ok());
+    }
+
+    #[test]
+    fn missing_unit_is_rejected() {
+        let mut definitions = default_definitions();
+        definitions.0.remove(&UnitName::Archer);
+
+        assert!(definitions.validate().is_err());
+    }
+
+    #[test]
+    fn invalid_stats_are_rejected() {
+        let cases: Vec<fn(&mut UnitDefinition)> = vec![
+            |d| d.spawn_duration = 0,
+            |d| d.cost = -1.,
+            |d| d.weight = 0,
+            |d| d.unlock = Some(UnlockDefinition::default()),
+            |d| d.speed = 0.,
+            |d| d.health = -1.,
+            |d| d.range = 0.5,
+            |d| d.armor = -1.,
+            |d| {
+                d.frames.remove(&ActionKind::Run);
+            },
+        ];
+
+        for (i, case) in cases.iter().enumerate() {
+            let mut definitions = default_definitions();
+            case(definitions.0.get_mut(&UnitName::Warrior).unwrap());
+            assert!(definitions.validate().is_err(), "case {i} should be invalid");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::core::balance::{balance, AbilityBalance};
+use crate::core::units::units::UnitName;
+use serde::{Deserialize, Serialize};
+use strum_macros::EnumIter;
+
+#[derive(EnumIter, Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum HeroName {
+    #[default]
+    Paladin,
+    Ranger,
+    Oracle,
+}
+
+impl HeroName {
+    pub fn description(&self) -> &'static str {
+        match self {
+            HeroName::Paladin => {
+                "A veteran warrior that leads the charge, rallying nearby allies and stunning \
+                the enemies around him."
+            },
+            HeroName::Ranger => {
+                "A master archer that rains arrows on groups of enemies and slows them down \
+                with snares."
+            },
+            HeroName::Oracle => {
+                "A wise priest that heals and shields the units around her from a safe \
+                distance."
+            },
+        }
+    }
+
+    /// Unit the hero is based on
+    pub fn unit(&self) -> UnitName {
+        match self {
+            HeroName::Paladin => UnitName::Warrior,
+            HeroName::Ranger => UnitName::Archer,
+            HeroName::Oracle => UnitName::Priest,
+        }
+    }
+
+    pub fn abilities(&self) -> [Ability; 2] {
+        match self {
+            HeroName::Paladin => [Ability::Rally, Ability::Stomp],
+            HeroName::Ranger => [Ability::Volley, Ability::Snare],
+            HeroName::Oracle => [Ability::Heal, Ability::Shield],
+        }
+    }
+}
+
+#[derive(EnumIter, Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+pub enum Ability {
+    Rally,
+    Stomp,
+    Volley,
+    Snare,
+    Heal,
+    Shield,
+}
+
+impl Ability {
+    pub fn description(&self) -> &'static str {
+        match self {
+            Ability::Rally => "Nearby allies move and attack faster.",
+            Ability::Stomp => "Damage and stun the enemies around the hero.",
+            Ability::Volley => "Damage all enemies in range of the hero.",
+            Ability::Snare => "Slow down all enemies in range of the hero.",
+            Ability::Heal => "Heal nearby allies a fraction of their maximum health.",
+            Ability::Shield => "Shield nearby allies, absorbing damage.",
+        }
+    }
+
+    /// Whether the ability affects the allies (or the enemies) of the hero
+    pub fn on_allies(&self) -> bool {
+        matches!(self, Ability::Rally | Ability::Heal | Ability::Shield)
+    }
+
+    /// Image shown on the ability's button
+    pub fn image(&self) -> &'static str {
+        match self {
+            Ability::Rally => "run",
+            Ability::Stomp => "frozen",
+            Ability::Volley => "arrows",
+            Ability::Snare => "spiders",
+            Ability::Heal => "instant healing",
+            Ability::Shield => "armor gain",
+        }
+    }
+
+    pub fn balance(&self) -> AbilityBalance {
+        balance().hero.abilities[self].clone()
+    }
+}
+
+/// Hero of a player, which is kept between its deaths
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Hero {
+    pub name: HeroName,
+    pub level: u32,
+    /// Experience gathered towards the next level
+    pub xp: u32,
+    /// Whether the hero is on the map
+    pub alive: bool,
+    /// Seconds until the hero respawns
+    pub respawn: f32,
+    /// Seconds until every ability can be used again
+    pub cooldowns: [f32; 2],
+}
+
+impl Hero {
+    pub fn new(name: HeroName) -> Self {
+        Self {
+            name,
+            level: 1,
+            xp: 0,
+            alive: false,
+            respawn: 0.,
+            cooldowns: [0.; 2],
+        }
+    }
+
+    /// Experience needed to reach the next level
+    pub fn next_level(&self) -> u32 {
+        balance().hero.xp * self.level
+    }
+
+    pub fn is_max_level(&self) -> bool {
+        self.level >= balance().hero.max_level
+    }
+
+    /// Add experience, returning whether the hero leveled up
+    pub fn gain_xp(&mut self, xp: u32) -> bool {
+        let level = self.level;
+
+        self.xp += xp;
+        while !self.is_max_level() && self.xp >= self.next_level() {
+            self.xp -= self.next_level();
+            self.level += 1;
+        }
+
+        if self.is_max_level() {
+            self.xp = 0;
+        }
+
+        self.level > level
+    }
+
+    /// Multiplier of the health and damage of the hero's unit
+    pub fn multiplier(&self) -> f32 {
+        let hero = &balance().hero;
+        hero.power * (1. + hero.growth * (self.level - 1) as f32)
+    }
+
+    pub fn is_ready(&self, ability: usize) -> bool {
+        self.alive && self.cooldowns[ability] <= 0.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gain_xp_levels_up() {
+        let mut hero = Hero::new(HeroName::Paladin);
+        let xp = hero.next_level();
+
+        assert!(!hero.gain_xp(xp - 1));
+        assert_eq!((hero.level, hero.xp), (1, xp - 1));
+
+        // The experience left over carries to the next level
+        assert!(hero.gain_xp(2));
+        assert_eq!((hero.level, hero.xp), (2, 1));
+    }
+
+    #[test]
+    fn gain_xp_skips_several_levels() {
+        let mut hero = Hero::new(HeroName::Ranger);
+        let xp = balance().hero.xp;
+
+        // Levels 1 and 2 cost `xp` and `2 * xp`
+        assert!(hero.gain_xp(3 * xp));
+        assert_eq!((hero.level, hero.xp), (3, 0));
+    }
+
+    #[test]
+    fn gain_xp_stops_at_max_level() {
+        let mut hero = Hero::new(HeroName::Oracle);
+        let max_level = balance().hero.max_level;
+
+        assert!(hero.gain_xp(u32::MAX / 2));
+        assert!(hero.is_max_level());
+        assert_eq!((hero.level, hero.xp), (max_level, 0));
+
+        assert!(!hero.gain_xp(100));
+        assert_eq!((hero.level, hero.xp), (max_level, 0));
+    }
+}
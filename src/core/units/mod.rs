@@ -1,3 +1,5 @@
 pub mod buildings;
+pub mod definitions;
+pub mod heroes;
 pub mod systems;
 pub mod units;
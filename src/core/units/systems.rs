@@ -1,18 +1,21 @@
 use crate::core::assets::WorldAssets;
-use crate::core::constants::{FRAME_RATE, HEALTH_SIZE, RADIUS, UNIT_DEFAULT_SIZE};
+use crate::core::constants::{
+    FRAME_RATE, HEALTH_SIZE, POISON_COLOR, RANK_COLORS, UNIT_DEFAULT_SIZE,
+};
 use crate::core::map::utils::SpriteFrameLens;
-use crate::core::mechanics::combat::BuildingDestroyCmp;
-use crate::core::mechanics::spawn::{DespawnMsg, HealthCmp, HealthWrapperCmp};
-use crate::core::player::{Players, Strategy};
-use crate::core::settings::Settings;
+use crate::core::mechanics::spawn::{DespawnMsg, HealthCmp, HealthWrapperCmp, RankCmp};
+use crate::core::mechanics::status::{Status, StatusEffects};
+use crate::core::player::Players;
+use crate::core::rng::GameRng;
 use crate::core::units::buildings::Building;
-use crate::core::units::units::{Action, Unit};
-use crate::utils::{scale_duration, NameFromEnum};
+use crate::core::units::units::{Action, Unit, UnitName};
+use crate::utils::NameFromEnum;
+use bevy::color::palettes::css::LIME;
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
-use bevy_tweening::{RepeatCount, Tween, TweenAnim};
+use bevy_tweening::{CycleCompletedEvent, RepeatCount, Tween, TweenAnim};
 use itertools::Itertools;
-use rand::{rng, RngExt};
+use rand::RngExt;
 use std::time::Duration;
 
 #[derive(Component)]
@@ -26,38 +29,29 @@ pub struct FireAnimCmp;
 
 pub fn update_units(
     mut commands: Commands,
-    mut unit_q: Query<(Entity, &Transform, &mut Sprite, Option<&IsHealing>, &mut Unit)>,
-    building_q: Query<&Building>,
+    mut unit_q: Query<(Entity, &Transform, &mut Sprite, Option<&IsHealing>, &Unit, &StatusEffects)>,
     healing_q: Query<&HealingAnimCmp>,
     mut wrapper_q: Query<(Entity, &mut Visibility), With<HealthWrapperCmp>>,
     mut health_q: Query<
         (&mut Transform, &mut Sprite),
         (With<HealthCmp>, Without<Unit>, Without<Building>),
     >,
+    mut rank_q: Query<
+        (&mut Sprite, &mut Visibility),
+        (With<RankCmp>, Without<Unit>, Without<HealthCmp>, Without<HealthWrapperCmp>),
+    >,
     children_q: Query<&Children>,
     players: Res<Players>,
     assets: Res<WorldAssets>,
 ) {
-    // Collect positions and health and for all units and buildings
-    let units: HashMap<Entity, (Vec3, Unit)> =
-        unit_q.iter().map(|(e, t, _, _, u)| (e, (t.translation, *u))).collect();
-
-    // Get the entities of the units under attack
-    let attacked: Vec<Entity> = unit_q
-        .iter()
-        .filter_map(|(_, _, _, _, u)| {
-            if let Action::Attack(e) = u.action {
-                Some(e)
-            } else {
-                None
-            }
-        })
-        .collect();
+    // Collect the positions of all units
+    let positions: HashMap<Entity, Vec3> =
+        unit_q.iter().map(|(e, t, ..)| (e, t.translation)).collect();
 
     // Get the entities of the units that are being healed
     let healed: Vec<Entity> = unit_q
         .iter()
-        .filter_map(|(_, _, _, _, u)| {
+        .filter_map(|(_, _, _, _, u, _)| {
             if let Action::Heal(e) = u.action {
                 Some(e)
             } else {
@@ -66,52 +60,14 @@ pub fn update_units(
         })
         .collect();
 
-    for (unit_e, unit_t, mut unit_s, heal, mut unit) in &mut unit_q {
-        let player = players.get_by_color(unit.color);
-
-        // If the unit is being attacked and the strategy is Guard, enter guard mode
-        if unit.action != Action::Guard {
-            if unit.name.can_guard()
-                && player.strategy == Strategy::Guard
-                && attacked.contains(&unit_e)
-            {
-                unit.action = Action::Guard;
+    for (unit_e, unit_t, mut unit_s, heal, unit, statuses) in &mut unit_q {
+        // Face the receiver of the action
+        if let Action::Attack(e) | Action::Heal(e) = unit.action {
+            if let Some(pos) = positions.get(&e) {
+                unit_s.flip_x = pos.x < unit_t.translation.x;
             }
-        } else if !attacked.contains(&unit_e) || player.strategy != Strategy::Guard {
-            // Go back to default when no longer under attack
-            unit.action = Action::Idle;
         }
 
-        // Check that the action receiver still exists and is in range, else go back to idle
-        unit.action = match unit.action {
-            Action::Attack(e) => {
-                if building_q.get(e).is_ok() {
-                    unit.action
-                } else if let Some((pos, _)) = units.get(&e) {
-                    if unit_t.translation.distance(*pos) <= unit.range(player) * RADIUS {
-                        unit_s.flip_x = pos.x < unit_t.translation.x;
-                        unit.action
-                    } else {
-                        Action::Idle
-                    }
-                } else {
-                    Action::Idle // Target entity doesn't exist anymore
-                }
-            },
-            Action::Heal(e) => units
-                .get(&e)
-                .filter(|(pos, target)| {
-                    unit_t.translation.distance(*pos) <= unit.range(player) * RADIUS
-                        && target.health < target.name.health()
-                })
-                .map(|(pos, _)| {
-                    unit_s.flip_x = pos.x < unit_t.translation.x;
-                    unit.action
-                })
-                .unwrap_or(Action::Idle),
-            _ => unit.action,
-        };
-
         // Update the action animation
         let atlas = assets.atlas(format!(
             "{}-{}-{}",
@@ -123,18 +79,24 @@ pub fn update_units(
         if unit_s.image != atlas.image {
             unit_s.image = atlas.image;
             unit_s.texture_atlas = Some(atlas.atlas);
+            unit_s.custom_size = Some(Vec2::splat(unit.name.size()));
+
+            // Stretch the attack (or heal) animation to the duration of the cycle, which
+            // update_animations speeds up with the current attack speed of the unit
+            let duration = if matches!(unit.action, Action::Attack(_) | Action::Heal(_)) {
+                unit.name.attack_duration()
+            } else {
+                Duration::from_millis(FRAME_RATE * unit.name.frames(unit.action) as u64)
+            };
 
             commands.entity(unit_e).insert(TweenAnim::new(
                 Tween::new(
                     EaseFunction::Linear,
-                    Duration::from_millis(FRAME_RATE * unit.name.frames(unit.action) as u64),
+                    duration,
                     SpriteFrameLens(unit.name.frames(unit.action) as usize),
                 )
                 .with_repeat_count(RepeatCount::Infinite)
-                .with_cycle_completed_event(matches!(
-                    unit.action,
-                    Action::Guard | Action::Attack(_) | Action::Heal(_)
-                )),
+                .with_cycle_completed_event(unit.action == Action::Guard),
             ));
         }
 
@@ -178,51 +140,60 @@ pub fn update_units(
         // Update the health bar
         for child in children_q.iter_descendants(unit_e) {
             if let Ok((wrapper_e, mut wrapper_v)) = wrapper_q.get_mut(child) {
-                // Show the health bar when the unit is damaged
-                if unit.health < unit.name.health() {
+                // Show the health bar when the unit is damaged or poisoned
+                let poisoned = statuses.has(Status::Poison);
+                let max_health = unit.max_health(players.get_by_color(unit.color));
+                if unit.health < max_health || poisoned {
                     *wrapper_v = Visibility::Inherited;
 
                     for child in children_q.iter_descendants(wrapper_e) {
                         if let Ok((mut health_t, mut health_s)) = health_q.get_mut(child) {
                             if let Some(size) = health_s.custom_size.as_mut() {
-                                size.x = HEALTH_SIZE.x * unit.health / unit.name.health();
+                                size.x = HEALTH_SIZE.x * unit.health / max_health;
                                 health_t.translation.x = (size.x - HEALTH_SIZE.x) * 0.5;
                             }
+
+                            // Tint the health bar of poisoned units
+                            health_s.color = if poisoned {
+                                POISON_COLOR
+                            } else {
+                                Color::from(LIME)
+                            };
                         }
                     }
                 } else {
                     *wrapper_v = Visibility::Hidden;
                 }
             }
+
+            // Show the badge of units with a veterancy rank
+            if let Ok((mut rank_s, mut rank_v)) = rank_q.get_mut(child) {
+                let rank = unit.veterancy.rank();
+                if rank > 0 && !unit.hero {
+                    rank_s.color = RANK_COLORS[(rank - 1).min(RANK_COLORS.len() - 1)];
+                    *rank_v = Visibility::Inherited;
+                } else {
+                    *rank_v = Visibility::Hidden;
+                }
+            }
         }
     }
 }
 
 pub fn update_buildings(
     mut commands: Commands,
-    mut building_q: Query<(Entity, &Transform, Option<&mut BuildingDestroyCmp>, &Building)>,
+    building_q: Query<(Entity, &Building)>,
     mut wrapper_q: Query<(Entity, &mut Visibility), With<HealthWrapperCmp>>,
     mut health_q: Query<(&mut Transform, &mut Sprite), (With<HealthCmp>, Without<Building>)>,
     fire_q: Query<&Transform, (With<FireAnimCmp>, Without<Building>, Without<HealthCmp>)>,
     children_q: Query<&Children>,
     mut despawn_msg: MessageWriter<DespawnMsg>,
-    settings: Res<Settings>,
-    time: Res<Time>,
+    mut rng: ResMut<GameRng>,
     assets: Res<WorldAssets>,
 ) {
-    for (building_e, _, destroy, building) in &mut building_q {
+    for (building_e, building) in &building_q {
         let b_size = building.name.size();
 
-        // Update the destroy timer and despawn when finished
-        if let Some(mut destroy) = destroy {
-            destroy.tick(scale_duration(time.delta(), settings.speed));
-
-            if destroy.just_finished() {
-                despawn_msg.write(DespawnMsg(building_e));
-                continue;
-            }
-        }
-
         // Update the health bar
         for child in children_q.iter_descendants(building_e) {
             if let Ok((wrapper_e, mut wrapper_v)) = wrapper_q.get_mut(child) {
@@ -250,7 +221,7 @@ pub fn update_buildings(
         }
 
         // Update the fire animations
-        let mut rng = rng();
+        let rng = rng.visuals();
 
         let damage = 1. - building.health / building.name.health();
 
@@ -315,3 +286,21 @@ pub fn update_buildings(
         }
     }
 }
+
+pub fn update_guard_animation(
+    mut commands: Commands,
+    mut unit_q: Query<(Entity, &mut Sprite, &Unit)>,
+    mut cycle_completed_msg: MessageReader<CycleCompletedEvent>,
+) {
+    for msg in cycle_completed_msg.read() {
+        if let Ok((unit_e, mut unit_s, unit)) = unit_q.get_mut(msg.anim_entity) {
+            // Turtles stay in the shell during guard
+            if unit.name == UnitName::Turtle && unit.action == Action::Guard {
+                if let Some(atlas) = &mut unit_s.texture_atlas {
+                    atlas.index = 5;
+                    commands.entity(unit_e).remove::<TweenAnim>();
+                }
+            }
+        }
+    }
+}
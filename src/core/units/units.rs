@@ -1,13 +1,19 @@
+use crate::core::balance::balance;
 use crate::core::boosts::Boost;
-use crate::core::constants::{UNIT_DEFAULT_SIZE, UNIT_SCALE};
-use crate::core::map::map::Lane;
+use crate::core::constants::{FLYING_Z, UNITS_Z, UNIT_DEFAULT_SIZE, UNIT_SCALE};
+use crate::core::map::map::{Lane, Map, Movement};
 use crate::core::mechanics::combat::Projectile;
 use crate::core::player::Player;
+use crate::core::rng::GameRng;
 use crate::core::settings::PlayerColor;
-use bevy::prelude::{Component, Entity, KeyCode};
+use crate::core::units::definitions::{
+    with_definition, HitStatusDefinition, PoisonDefinition, SplashDefinition, UnlockDefinition,
+};
+use bevy::prelude::{Component, Entity, KeyCode, Vec2};
 use rand::prelude::IndexedRandom;
-use rand::rng;
+use rand::RngExt;
 use serde::{Deserialize, Serialize};
+use std::time::Duration;
 use strum_macros::{EnumDiscriminants, EnumIter};
 
 #[derive(EnumIter, Clone, Copy, Debug, Default, Eq, Hash, PartialEq, Serialize, Deserialize)]
@@ -30,6 +36,7 @@ pub enum UnitName {
     Spider,
     Troll,
     Turtle,
+    Ghost,
 }
 
 impl UnitName {
@@ -59,6 +66,10 @@ impl UnitName {
                 "A massive forest bully that crushes enemies with its enormous, powerful claws. \
                 Bears are often summoned by priests to defend them in close combat."
             },
+            UnitName::Ghost => {
+                "Ghosts are restless spirits that float over rivers and walls straight to the \
+                enemy base. Only ranged units and buildings can hit them."
+            },
             UnitName::Gnoll => {
                 "A scavengy creature that attacks by hurling bones at its enemies. Gnolls have \
                  lower range than archers and sharks, but deal more damage."
@@ -133,114 +144,12 @@ impl UnitName {
     }
 
     pub fn frames(&self, action: Action) -> u32 {
-        match self {
-            UnitName::Warrior => match action {
-                Action::Idle => 8,
-                Action::Run => 6,
-                Action::Guard => 6,
-                Action::Attack(_) => 8,
-                _ => 0,
-            },
-            UnitName::Lancer => match action {
-                Action::Idle => 12,
-                Action::Run => 6,
-                Action::Attack(_) => 9,
-                _ => 0,
-            },
-            UnitName::Archer => match action {
-                Action::Idle => 6,
-                Action::Run => 4,
-                Action::Attack(_) => 6, // Skip last 2 frames to spawn arrow at end of animation
-                _ => 0,
-            },
-            UnitName::Priest => match action {
-                Action::Idle => 6,
-                Action::Run => 4,
-                Action::Heal(_) => 11,
-                _ => 0,
-            },
-            UnitName::Bear => match action {
-                Action::Idle => 8,
-                Action::Run => 5,
-                Action::Attack(_) => 9,
-                _ => 0,
-            },
-            UnitName::Gnoll => match action {
-                Action::Idle => 6,
-                Action::Run => 8,
-                Action::Attack(_) => 6, // Skip last 2 frames to spawn bone at end of animation
-                _ => 0,
-            },
-            UnitName::Gnome => match action {
-                Action::Idle => 8,
-                Action::Run => 6,
-                Action::Attack(_) => 7,
-                _ => 0,
-            },
-            UnitName::Goblin => match action {
-                Action::Idle => 7,
-                Action::Run => 6,
-                Action::Attack(_) => 8,
-                _ => 0,
-            },
-            UnitName::Hammerhead => match action {
-                Action::Idle => 8,
-                Action::Run => 6,
-                Action::Attack(_) => 6,
-                _ => 0,
-            },
-            UnitName::Minotaur => match action {
-                Action::Idle => 16,
-                Action::Run => 8,
-                Action::Guard => 11,
-                Action::Attack(_) => 12,
-                _ => 0,
-            },
-            UnitName::Shaman => match action {
-                Action::Idle => 8,
-                Action::Run => 4,
-                Action::Attack(_) => 6, // Skip last 2 frames to spawn arrow at end of animation
-                _ => 0,
-            },
-            UnitName::Shark => match action {
-                Action::Idle => 8,
-                Action::Run => 6,
-                Action::Attack(_) => 4, // Skip last 4 frames to spawn arrow at end of animation
-                _ => 0,
-            },
-            UnitName::Skull => match action {
-                Action::Idle => 8,
-                Action::Run => 6,
-                Action::Guard => 7,
-                Action::Attack(_) => 7,
-                _ => 0,
-            },
-            UnitName::Snake => match action {
-                Action::Idle => 8,
-                Action::Run => 8,
-                Action::Attack(_) => 6,
-                _ => 0,
-            },
-            UnitName::Spider => match action {
-                Action::Idle => 8,
-                Action::Run => 5,
-                Action::Attack(_) => 8,
-                _ => 0,
-            },
-            UnitName::Troll => match action {
-                Action::Idle => 12,
-                Action::Run => 10,
-                Action::Attack(_) => 6,
-                _ => 0,
-            },
-            UnitName::Turtle => match action {
-                Action::Idle => 10,
-                Action::Run => 7,
-                Action::Guard => 6,
-                Action::Attack(_) => 10,
-                _ => 0,
-            },
-        }
+        with_definition(self, |d| d.frames(action.into()))
+    }
+
+    /// Duration of one attack (or heal) cycle, after which the damage is applied
+    pub fn attack_duration(&self) -> Duration {
+        Duration::from_secs_f32(1. / self.attack_speed())
     }
 
     pub fn is_basic_unit(self) -> bool {
@@ -259,198 +168,98 @@ impl UnitName {
         self.range() == 1.
     }
 
-    pub fn spawn_duration(&self) -> u64 {
-        match self {
-            UnitName::Warrior => 2500,
-            UnitName::Lancer => 1800,
-            UnitName::Archer => 3300,
-            UnitName::Priest => 3400,
-            UnitName::Bear => 3400,
-            UnitName::Gnoll => 3300,
-            UnitName::Gnome => 1000,
-            UnitName::Goblin => 2000,
-            UnitName::Hammerhead => 1900,
-            UnitName::Minotaur => 7900,
-            UnitName::Shaman => 7000,
-            UnitName::Shark => 3500,
-            UnitName::Skull => 800,
-            UnitName::Snake => 500,
-            UnitName::Spider => 2500,
-            UnitName::Troll => 10000,
-            UnitName::Turtle => 6500,
+    pub fn is_flying(&self) -> bool {
+        self.movement() == Movement::Air
+    }
+
+    /// Height at which the unit is drawn
+    pub fn z(&self) -> f32 {
+        if self.is_flying() {
+            FLYING_Z
+        } else {
+            UNITS_Z
         }
     }
 
+    pub fn spawn_duration(&self) -> u64 {
+        with_definition(self, |d| d.spawn_duration)
+    }
+
+    pub fn cost(&self) -> f32 {
+        with_definition(self, |d| d.cost)
+    }
+
+    pub fn weight(&self) -> u32 {
+        with_definition(self, |d| d.weight)
+    }
+
+    pub fn movement(&self) -> Movement {
+        with_definition(self, |d| d.movement)
+    }
+
     pub fn speed(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 30.,
-            UnitName::Lancer => 35.,
-            UnitName::Archer => 25.,
-            UnitName::Priest => 25.,
-            UnitName::Bear => 40.,
-            UnitName::Gnoll => 25.,
-            UnitName::Gnome => 40.,
-            UnitName::Goblin => 35.,
-            UnitName::Hammerhead => 35.,
-            UnitName::Minotaur => 25.,
-            UnitName::Shaman => 30.,
-            UnitName::Shark => 25.,
-            UnitName::Skull => 40.,
-            UnitName::Snake => 45.,
-            UnitName::Spider => 30.,
-            UnitName::Troll => 20.,
-            UnitName::Turtle => 15.,
-        }
+        with_definition(self, |d| d.speed)
+    }
+
+    pub fn attack_speed(&self) -> f32 {
+        with_definition(self, |d| d.attack_speed)
     }
 
     pub fn range(&self) -> f32 {
-        match self {
-            UnitName::Archer => 3.,
-            UnitName::Gnoll => 2.5,
-            UnitName::Priest => 3.,
-            UnitName::Shaman => 2.5,
-            UnitName::Shark => 3.,
-            _ => 1.,
-        }
+        with_definition(self, |d| d.range)
     }
 
     pub fn projectile(&self) -> Option<Projectile> {
-        match self {
-            UnitName::Archer => Some(Projectile::Arrow),
-            UnitName::Gnoll => Some(Projectile::Bone),
-            UnitName::Shaman => Some(Projectile::Magic),
-            UnitName::Shark => Some(Projectile::Harpoon),
-            _ => None,
-        }
+        with_definition(self, |d| d.projectile)
     }
 
     pub fn health(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 130.,
-            UnitName::Lancer => 100.,
-            UnitName::Archer => 60.,
-            UnitName::Priest => 40.,
-            UnitName::Bear => 200.,
-            UnitName::Gnoll => 110.,
-            UnitName::Gnome => 60.,
-            UnitName::Goblin => 100.,
-            UnitName::Hammerhead => 100.,
-            UnitName::Minotaur => 200.,
-            UnitName::Shaman => 70.,
-            UnitName::Shark => 60.,
-            UnitName::Skull => 60.,
-            UnitName::Snake => 45.,
-            UnitName::Spider => 100.,
-            UnitName::Troll => 500.,
-            UnitName::Turtle => 350.,
-        }
+        with_definition(self, |d| d.health)
     }
 
     pub fn physical_damage(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 15.,
-            UnitName::Lancer => 15.,
-            UnitName::Archer => 10.,
-            UnitName::Priest => -30., // This is the healing done (negative damage)
-            UnitName::Bear => 20.,
-            UnitName::Gnoll => 12.,
-            UnitName::Gnome => 7.,
-            UnitName::Goblin => 15.,
-            UnitName::Skull => 8.,
-            UnitName::Troll => 25.,
-            UnitName::Turtle => 5.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.physical_damage)
     }
 
     pub fn magic_damage(&self) -> f32 {
-        match self {
-            UnitName::Hammerhead => 15.,
-            UnitName::Minotaur => 30.,
-            UnitName::Shaman => 22.,
-            UnitName::Shark => 10.,
-            UnitName::Skull => 2.,
-            UnitName::Snake => 8.,
-            UnitName::Spider => 18.,
-            UnitName::Troll => 10.,
-            UnitName::Turtle => 5.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.magic_damage)
     }
 
     pub fn armor(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 5.,
-            UnitName::Lancer => 3.,
-            UnitName::Archer => 1.,
-            UnitName::Bear => 10.,
-            UnitName::Gnoll => 2.,
-            UnitName::Gnome => 1.,
-            UnitName::Goblin => 4.,
-            UnitName::Hammerhead => 3.,
-            UnitName::Minotaur => 12.,
-            UnitName::Spider => 5.,
-            UnitName::Troll => 17.,
-            UnitName::Turtle => 20.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.armor)
     }
 
     pub fn magic_resist(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 3.,
-            UnitName::Lancer => 3.,
-            UnitName::Priest => 12.,
-            UnitName::Bear => 6.,
-            UnitName::Gnoll => 2.,
-            UnitName::Gnome => 1.,
-            UnitName::Goblin => 4.,
-            UnitName::Hammerhead => 7.,
-            UnitName::Minotaur => 12.,
-            UnitName::Shaman => 17.,
-            UnitName::Shark => 2.,
-            UnitName::Spider => 2.,
-            UnitName::Troll => 17.,
-            UnitName::Turtle => 20.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.magic_resist)
     }
 
     pub fn armor_pen(&self) -> f32 {
-        match self {
-            UnitName::Warrior => 5.,
-            UnitName::Lancer => 8.,
-            UnitName::Archer => 2.,
-            UnitName::Bear => 9.,
-            UnitName::Gnoll => 4.,
-            UnitName::Gnome => 1.,
-            UnitName::Goblin => 12.,
-            UnitName::Hammerhead => 8.,
-            UnitName::Minotaur => 10.,
-            UnitName::Shaman => 6.,
-            UnitName::Shark => 5.,
-            UnitName::Spider => 3.,
-            UnitName::Troll => 12.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.armor_pen)
     }
 
     pub fn magic_pen(&self) -> f32 {
-        match self {
-            UnitName::Gnoll => 2.,
-            UnitName::Hammerhead => 8.,
-            UnitName::Minotaur => 10.,
-            UnitName::Shaman => 8.,
-            UnitName::Shark => 5.,
-            UnitName::Spider => 3.,
-            UnitName::Troll => 12.,
-            _ => 0.,
-        }
+        with_definition(self, |d| d.magic_pen)
+    }
+
+    pub fn poison(&self) -> Option<PoisonDefinition> {
+        with_definition(self, |d| d.poison)
+    }
+
+    pub fn on_hit(&self) -> Option<HitStatusDefinition> {
+        with_definition(self, |d| d.on_hit)
+    }
+
+    pub fn splash(&self) -> Option<SplashDefinition> {
+        with_definition(self, |d| d.splash)
+    }
+
+    pub fn unlock(&self) -> Option<UnlockDefinition> {
+        with_definition(self, |d| d.unlock.clone())
     }
 }
 
 #[derive(EnumDiscriminants, Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
-#[strum_discriminants(name(ActionKind), derive(EnumIter))]
+#[strum_discriminants(name(ActionKind), derive(EnumIter, Hash, Serialize, Deserialize))]
 pub enum Action {
     #[default]
     Idle,
@@ -472,14 +281,51 @@ impl ActionKind {
     }
 }
 
+/// Experience gathered by a unit during its lifetime
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Veterancy {
+    pub kills: u32,
+    /// Damage (or healing) dealt
+    pub damage: f32,
+}
+
+impl Veterancy {
+    /// Veterancy rank, where 0 means no rank
+    pub fn rank(&self) -> usize {
+        balance()
+            .veterancy
+            .iter()
+            .take_while(|r| self.kills >= r.kills || self.damage >= r.damage)
+            .count()
+    }
+
+    /// Fraction of health and damage gained by the rank
+    pub fn bonus(&self) -> f32 {
+        match self.rank() {
+            0 => 0.,
+            rank => balance().veterancy[rank - 1].bonus,
+        }
+    }
+}
+
 #[derive(Component, Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Unit {
+    /// Identifier drawn from the seed of the match, which (unlike the entity) is
+    /// the same in every replay of the match
+    pub id: u64,
     pub name: UnitName,
     pub color: PlayerColor,
     pub action: Action,
     pub health: f32,
     pub lane: Lane,
     pub on_building: Option<Entity>,
+    /// Whether this is the hero of its player
+    pub hero: bool,
+    pub veterancy: Veterancy,
+    /// Seconds elapsed in the current attack (or heal) cycle
+    pub attack_timer: f32,
+    /// Movement in world units per second during the last frame
+    pub velocity: Vec2,
 }
 
 impl Unit {
@@ -488,17 +334,39 @@ impl Unit {
         player: &Player,
         lane: Option<Lane>,
         on_building: Option<Entity>,
+        map: &Map,
+        rng: &mut GameRng,
     ) -> Self {
         Unit {
             name,
             color: player.color,
             action: Action::default(),
             health: name.health(),
-            lane: lane.unwrap_or(*player.direction.lanes().choose(&mut rng()).unwrap()),
+            lane: lane.unwrap_or_else(|| match map.movement(name.movement()) {
+                Movement::Land => *player.direction.lanes(map.n_lanes()).choose(rng).unwrap(),
+                m => Lane(rng.random_range(0..map.lanes_of(m).len())),
+            }),
             on_building,
+            hero: false,
+            veterancy: Veterancy::default(),
+            attack_timer: 0.,
+            velocity: Vec2::ZERO,
+            id: rng.random(),
+        }
+    }
+
+    /// Multiplier of the health and damage of the unit
+    pub fn multiplier(&self, player: &Player) -> f32 {
+        match &player.hero {
+            Some(hero) if self.hero => hero.multiplier(),
+            _ => 1. + self.veterancy.bonus(),
         }
     }
 
+    pub fn max_health(&self, player: &Player) -> f32 {
+        self.name.health() * self.multiplier(player)
+    }
+
     pub fn range(&self, player: &Player) -> f32 {
         let mut range = if self.on_building.is_some() {
             2. * self.name.range()
@@ -507,9 +375,61 @@ impl Unit {
         };
 
         if self.name == UnitName::Archer && player.has_boost(Boost::Longbow) {
-            range *= 1.5;
+            range *= Boost::Longbow.value();
         }
 
         range
     }
 }
+
+/// Total weight of the units of a player, ignoring the units on buildings and the hero
+pub fn population<'a>(units: impl Iterator<Item = &'a Unit>, color: PlayerColor) -> u32 {
+    units
+        .filter(|u| u.color == color && u.on_building.is_none() && !u.hero)
+        .map(|u| u.name.weight())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn veterancy_rank_by_kills_or_damage() {
+        let ranks = balance().veterancy.clone();
+
+        assert_eq!(Veterancy::default().rank(), 0);
+        assert_eq!(Veterancy::default().bonus(), 0.);
+
+        let kills = Veterancy {
+            kills: ranks[0].kills,
+            damage: 0.,
+        };
+        assert_eq!(kills.rank(), 1);
+        assert_eq!(kills.bonus(), ranks[0].bonus);
+
+        let damage = Veterancy {
+            kills: 0,
+            damage: ranks[1].damage,
+        };
+        assert_eq!(damage.rank(), 2);
+
+        let almost = Veterancy {
+            kills: ranks[0].kills - 1,
+            damage: ranks[0].damage - 1.,
+        };
+        assert_eq!(almost.rank(), 0);
+    }
+
+    #[test]
+    fn veterancy_rank_is_capped() {
+        let ranks = balance().veterancy.clone();
+        let veteran = Veterancy {
+            kills: u32::MAX,
+            damage: f32::MAX,
+        };
+
+        assert_eq!(veteran.rank(), ranks.len());
+        assert_eq!(veteran.bonus(), ranks[ranks.len() - 1].bonus);
+    }
+}
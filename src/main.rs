@@ -1,8 +1,5 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
-mod core;
-mod utils;
-
 use bevy::asset::AssetMetaCheck;
 use bevy::ecs::system::NonSendMarker;
 use bevy::prelude::*;
@@ -21,10 +18,9 @@ use std::io::Write;
 use std::panic;
 use std::sync::Mutex;
 
-use crate::core::GamePlugin;
-use crate::utils::NameFromEnum;
-
-pub const TITLE: &str = "TinyWar";
+use tinywar::core::GamePlugin;
+use tinywar::utils::NameFromEnum;
+use tinywar::TITLE;
 
 #[allow(dead_code)]
 static LOG_FILE: Mutex<Option<File>> = Mutex::new(None);